@@ -0,0 +1,74 @@
+//! Registers a service of our own next to the AWS ones. Its resources are kept in memory, where
+//! a real one would ask some in-house control plane.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use cloud_ui::{App, Context, Registry, Resource, ResourceController, ResourceDescription, ServiceType};
+
+#[derive(Deserialize)]
+struct Pipeline {
+    name: String,
+    stages: usize,
+}
+
+impl Resource for Pipeline {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Stages"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![self.name.clone(), self.stages.to_string()]
+    }
+}
+
+struct Pipelines {}
+
+impl Pipelines {
+    fn new(_context: &Context) -> Self {
+        Pipelines {}
+    }
+
+    fn get_pipelines(&self) -> Vec<Pipeline> {
+        vec![
+            Pipeline { name: "ingest".to_string(), stages: 3 },
+            Pipeline { name: "enrich".to_string(), stages: 5 },
+        ]
+    }
+}
+
+#[async_trait]
+impl ResourceController<Pipeline> for Pipelines {
+    async fn list(&self) -> anyhow::Result<Vec<Pipeline>> {
+        Ok(self.get_pipelines())
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Pipeline>>> {
+        Ok(self.get_pipelines()
+            .into_iter()
+            .find(|x| x.name == id)
+            .map(|x| ResourceDescription {
+                id: x.name.clone(),
+                name: Some(x.name),
+                props: [("Stages".to_string(), x.stages.to_string())].into_iter().collect(),
+                references: vec![],
+            }))
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut registry = Registry::default();
+    cloud_ui::cloud::aws::register(&mut registry);
+    registry.register(Box::new(ServiceType::new("Pipelines").resource("Pipelines", Pipelines::new)));
+    cloud_ui::run(App::new(registry)).await
+}
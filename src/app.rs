@@ -1,224 +1,1259 @@
-use std::any;
-use std::io::Stdout;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::time::{Duration, Instant};
-use tui::backend::{Backend, CrosstermBackend};
+use tui::backend::Backend;
 use tui::Terminal;
-use tui::widgets::ListState;
-use crate::service::resource::{Resource, ResourceController, ResourceKind};
-use crate::service::{Service, ServiceKind, ServiceType};
-use crate::ui;
-use crate::ui::service::ServiceState;
+use crate::service::resource::{Action, ActionOutcome, AnyController, LogEvent, Progress, Reference, ReferenceKind, ResourceDescription, Row};
+use crate::cloud::aws::{self, Arn};
+use crate::command::{Catalog, Command};
+use crate::config::RefreshConfig;
+use crate::filter::Filter;
+use crate::keymap::{KeyAction, Keymap, Outcome};
+use crate::list::{Navigable, StatefulList};
+use crate::service::Context;
+use crate::service::metrics::{Period, Series};
+use crate::service::registry::Registry;
+use crate::ui::{self, Areas, Hit, Ui};
+use crate::ui::component::{Header, TableList};
+use crate::ui::editor::Editing;
+use crate::ui::errors::{ErrorEntry, Toast};
+use crate::ui::pager::{Pager, PagerState};
+use crate::ui::tail::{self, Tail, TailState};
+use crate::transfer::TransferQueue;
+use crate::ui::prompt::{PromptKind, PromptState, Question};
+use crate::ui::resource::{ResourceDetail, ResourceDetailState, ResourceList, ResourceListState, METRICS_KEYS};
 
-pub trait GetItems {
-    fn get_items() -> Vec<String>;
+/// Longest gap between the clicks of a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Lines scrolled by a turn of the mouse wheel, over text.
+const SCROLL_LINES: u16 = 3;
+
+/// A controller shared between a view and the background tasks working on its behalf.
+pub type SharedController = Arc<tokio::sync::Mutex<Box<dyn AnyController>>>;
+
+/// Work for a controller, run off the UI loop. `view` identifies the view that asked for it, so
+/// that the response can find its way back even after navigating elsewhere.
+pub struct Request {
+    view: usize,
+    ctrl: SharedController,
+    operation: Operation,
 }
 
-pub struct StatefulEnum<T> {
-    pub current: Option<T>,
-    pub(crate) items: StatefulList<String>,
-    state: ListState,
+#[derive(Clone)]
+pub enum Operation {
+    List,
+    Describe(String),
+    Perform(char, Option<String>),
+    /// Carry on with an action, with the input it asked for.
+    Respond(char, Option<String>, String),
+    /// Read a log on from a time, for events matching a pattern.
+    Tail(String, String, Option<i64>),
+    /// Fetch a resource's metrics over a period.
+    Metrics(String, Period),
 }
 
-impl <T> StatefulEnum<T>
-    where T: GetItems
-{
-    fn new() -> Self {
-        Self {
-            current: None,
-            items: StatefulList::with_items(T::get_items()),
-            state: ListState::default()
-        }
+pub struct Response {
+    view: usize,
+    operation: Operation,
+    reply: anyhow::Result<Reply>,
+}
+
+pub enum Reply {
+    /// The rows, the headers of their columns, and how far the listing had got.
+    Listed(Vec<String>, Vec<Row>, Option<Progress>),
+    Described(Option<ResourceDescription<Row>>),
+    Performed(ActionOutcome),
+    Tailed(Vec<LogEvent>),
+    Metrics(Vec<Series>),
+}
+
+impl Request {
+    pub async fn execute(self) -> Response {
+        let mut ctrl = self.ctrl.lock().await;
+        let reply = match self.operation.clone() {
+            Operation::List => ctrl.list().await.map(|rows| Reply::Listed(ctrl.get_headers(), rows, ctrl.get_progress())),
+            Operation::Describe(id) => ctrl.describe(id).await.map(Reply::Described),
+            Operation::Perform(key, id) => ctrl.perform(key, id).await.map(Reply::Performed),
+            Operation::Respond(key, id, input) => ctrl.respond(key, id, input).await.map(Reply::Performed),
+            Operation::Tail(id, pattern, since) => ctrl.tail(id, pattern, since).await.map(Reply::Tailed),
+            Operation::Metrics(id, period) => ctrl.metrics(id, period).await.map(Reply::Metrics),
+        };
+        Response { view: self.view, operation: self.operation, reply }
     }
 }
 
-impl <T> StatefulEnum<T>
-    where T: From<String>
-{
-    fn select(&mut self) {
-        if let Some(idx) = self.items.state.selected() {
-            let name = self.items.items[idx].clone();
-            self.current = Some(T::from(name));
+/// This struct holds the current state of the app. In particular, it has the `items` field which is a wrapper
+/// around `ListState`. Keeping track of the items state let us render the associated widget with its state
+/// and have access to features such as natural scrolling.
+///
+/// Views that have been navigated away from are kept in `history`, so that escape returns to them
+/// as they were left.
+///
+/// Check the event handling at the bottom to see how to change the state on incoming events.
+/// Check the drawing logic for items on how to specify the highlighting style for selected items.
+pub struct App {
+    pub state: AppState,
+    pub history: Vec<AppState>,
+    /// The services that can be browsed.
+    registry: Registry,
+    /// Where new controllers point their clients.
+    pub context: Context,
+    /// How often listings refresh themselves.
+    pub refresh: RefreshConfig,
+    /// The time of day, for timestamps. Replaceable so that tests draw the same every time.
+    pub clock: fn() -> chrono::DateTime<chrono::Local>,
+    /// Input being typed at the bottom of the screen, if any. Takes all key presses while open.
+    pub prompt: Option<PromptState>,
+    /// Commands run from the `:` prompt, oldest first.
+    pub command_history: Vec<String>,
+    /// Outcome of the last action, shown in the header.
+    pub status: Option<String>,
+    /// Every error so far, oldest first.
+    pub errors: Vec<ErrorEntry>,
+    /// Errors shown briefly as they happen.
+    pub toasts: Vec<Toast>,
+    /// Shown over the current view, taking key presses while open.
+    pub overlay: Option<Overlay>,
+    pub keymap: Keymap,
+    /// Where the screen was last drawn, to find what the mouse is over.
+    pub areas: Areas,
+    /// The last click, for spotting a double click.
+    last_click: Option<(Instant, Hit)>,
+    /// Text waiting to be placed on the terminal's clipboard.
+    pub clipboard: Option<String>,
+    /// Downloads, uploads and the like, running in the background whichever view is open.
+    pub transfers: TransferQueue,
+    /// Text waiting to be handed to the user's editor, which `run_app` does between draws.
+    pub editing: Option<Editing>,
+    requests: Vec<Request>,
+    views: usize,
+}
+
+/// A window over the current view, scrolled by `scroll` lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    Errors { scroll: u16 },
+    Help { scroll: u16 },
+    /// The transfer queue, with the transfer at `selected` picked out for cancelling or retrying.
+    Transfers { selected: usize },
+}
+
+pub enum AppState {
+    Services { services: StatefulList<String> },
+    Resources { service: String, resources: StatefulList<String> },
+    Listing(ResourceListState),
+    Detail(ResourceDetailState),
+    Pager(PagerState),
+    Tail(TailState),
+}
+
+impl AppState {
+    fn get_view(&self) -> Option<usize> {
+        match self {
+            AppState::Listing(state) => Some(state.view),
+            AppState::Detail(state) => Some(state.view),
+            AppState::Tail(state) => Some(state.view),
+            _ => None,
         }
     }
-}
 
-pub struct StatefulList<T> {
-    pub(crate) state: ListState,
-    pub(crate) items: Vec<T>,
+    pub fn get_title(&self) -> String {
+        match self {
+            AppState::Services { .. } => "Services".to_string(),
+            AppState::Resources { service, .. } => service.clone(),
+            AppState::Listing(state) => state.get_title(),
+            AppState::Detail(state) if state.target.label == state.target.id => state.target.id.clone(),
+            AppState::Detail(state) => format!("{} {}", state.target.label, state.target.id),
+            AppState::Pager(state) => state.title.clone(),
+            AppState::Tail(state) => state.title.clone(),
+        }
+    }
 }
 
-impl<T> StatefulList<T> {
-    pub fn with_items(items: Vec<T>) -> StatefulList<T> {
-        StatefulList {
-            state: ListState::default(),
-            items,
+impl App {
+    pub fn new(registry: Registry) -> App {
+        App {
+            state: AppState::Services { services: StatefulList::with_items(get_services(&registry)) },
+            history: vec![],
+            registry,
+            context: Context::default(),
+            refresh: RefreshConfig::default(),
+            clock: chrono::Local::now,
+            prompt: None,
+            command_history: vec![],
+            status: None,
+            errors: vec![],
+            toasts: vec![],
+            overlay: None,
+            keymap: Keymap::default(),
+            areas: Areas::default(),
+            last_click: None,
+            clipboard: None,
+            transfers: TransferQueue::default(),
+            editing: None,
+            requests: vec![],
+            views: 0,
         }
     }
 
-    fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
+    pub fn get_breadcrumbs(&self) -> Vec<String> {
+        self.history.iter()
+            .chain(std::iter::once(&self.state))
+            .map(|x| x.get_title())
+            .collect()
+    }
+
+    pub fn take_requests(&mut self) -> Vec<Request> {
+        std::mem::take(&mut self.requests)
+    }
+
+    fn navigate(&mut self, state: AppState) {
+        self.status = None;
+        let previous = std::mem::replace(&mut self.state, state);
+        self.history.push(previous);
+    }
+
+    fn next_view(&mut self) -> usize {
+        self.views += 1;
+        self.views
+    }
+
+    /// Open the listing of `resource`, narrowed to `scope` if given.
+    fn open_listing(&mut self, service: String, resource: String, scope: Option<Reference>) -> anyhow::Result<()> {
+        let mut ctrl = self.registry.new_controller(&service, &resource, &self.context)?;
+        if let Some(scope) = &scope {
+            ctrl.set_scope(scope.id.clone())?;
+        }
+        let headers = ctrl.get_headers();
+        let actions = ctrl.get_actions();
+        let ctrl: SharedController = Arc::new(tokio::sync::Mutex::new(ctrl));
+        let interval = self.refresh.get_interval(&service, &resource);
+        let view = self.next_view();
+        self.requests.push(Request { view, ctrl: ctrl.clone(), operation: Operation::List });
+        self.navigate(AppState::Listing(ResourceListState {
+            view,
+            service,
+            resource,
+            scope,
+            ctrl,
+            headers,
+            actions,
+            items: StatefulList::with_items(vec![]).with_key(|x| x.id.clone()),
+            loading: true,
+            interval,
+            paused: false,
+            requested: Instant::now(),
+            updated: None,
+            changes: HashMap::new(),
+            progress: None,
+        }));
+        Ok(())
+    }
+
+    /// Open the detail view for `target`, reusing `ctrl`, along with its actions, if it already
+    /// controls the target's type.
+    fn open_detail(&mut self, target: Reference, ctrl: Option<(SharedController, Vec<Action>)>) -> anyhow::Result<()> {
+        let (ctrl, actions) = match ctrl {
+            Some(ctrl) => ctrl,
+            None => {
+                let ctrl = self.registry.new_controller(&target.service, &target.resource, &self.context)?;
+                let actions = ctrl.get_actions();
+                (Arc::new(tokio::sync::Mutex::new(ctrl)), actions)
             }
-            None => 0,
         };
-        self.state.select(Some(i));
+        let metrics = self.registry.get_resource_type(&target.service, &target.resource)?.get_metrics();
+        let view = self.next_view();
+        self.requests.push(Request { view, ctrl: ctrl.clone(), operation: Operation::Describe(target.id.clone()) });
+        let mut state = ResourceDetailState {
+            view,
+            target,
+            ctrl,
+            actions,
+            description: None,
+            references: StatefulList::with_items(vec![]).with_key(|x| format!("{}/{}/{}/{}", x.label, x.service, x.resource, x.id)),
+            loading: true,
+            scroll: 0,
+            metrics,
+            period: Period::default(),
+            series: vec![],
+            metrics_loading: false,
+        };
+        self.requests.extend(metrics_request(&mut state));
+        self.navigate(AppState::Detail(state));
+        Ok(())
+    }
+
+    /// Open whatever `target` points at: a resource, or a listing of them.
+    fn follow(&mut self, target: Reference) -> anyhow::Result<()> {
+        match target.kind {
+            ReferenceKind::Resource => self.open_detail(target, None),
+            ReferenceKind::Listing => {
+                let (service, resource) = (target.service.clone(), target.resource.clone());
+                self.open_listing(service, resource, Some(target))
+            }
+        }
+    }
+
+    /// Recreate the views leading to `service` and, if given, the listing of its `resource`, so
+    /// that escape walks back through them as if they had been navigated by hand.
+    fn open_path(&mut self, service: &str, resource: Option<&str>) -> anyhow::Result<()> {
+        // look everything up first, so that nothing changes if either is unknown
+        let service = self.registry.get_service(service)?.get_name().to_string();
+        let resource = match resource {
+            Some(resource) => Some(self.registry.get_resource_type(&service, resource)?.get_name().to_string()),
+            None => None,
+        };
+
+        let mut services = StatefulList::with_items(get_services(&self.registry));
+        services.select_where(|x| *x == service);
+        let mut resources = StatefulList::with_items(self.registry.get_resources(&service)?);
+        resources.select_where(|x| Some(x) == resource.as_ref());
+
+        self.status = None;
+        self.history = vec![AppState::Services { services }];
+        self.state = AppState::Resources { service: service.clone(), resources };
+        match resource {
+            Some(resource) => self.open_listing(service, resource, None),
+            None => Ok(()),
+        }
     }
 
-    fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.items.len() - 1
-                } else {
-                    i - 1
+    /// Open the detail view for the resource named by `arn`, switching to its region.
+    pub fn jump(&mut self, arn: &Arn) -> anyhow::Result<()> {
+        let target = self.registry.get_reference(&arn.to_string())?;
+        self.context = arn.get_context(&self.context);
+        self.open_path(&target.service, Some(&target.resource))?;
+        self.open_detail(target, None)
+    }
+
+    /// Switch context, reopening the resource type being browsed against the new one. Views
+    /// left in the history belong to the old context, so they are dropped.
+    fn switch_context(&mut self, context: Context) -> anyhow::Result<()> {
+        self.context = context;
+        let location = match &self.state {
+            AppState::Services { .. } => None,
+            AppState::Resources { service, .. } => Some((service.clone(), None)),
+            AppState::Listing(state) => Some((state.service.clone(), Some(state.resource.clone()))),
+            AppState::Detail(state) => Some((state.target.service.clone(), Some(state.target.resource.clone()))),
+            AppState::Pager(_) | AppState::Tail(_) => self.history.iter().rev().find_map(|x| match x {
+                AppState::Listing(state) => Some((state.service.clone(), Some(state.resource.clone()))),
+                _ => None,
+            }),
+        };
+        match location {
+            Some((service, resource)) => self.open_path(&service, resource.as_deref()),
+            None => {
+                self.history = vec![];
+                self.state = AppState::Services { services: StatefulList::with_items(get_services(&self.registry)) };
+                Ok(())
+            }
+        }
+    }
+
+    fn get_catalog(&self) -> Catalog {
+        Catalog {
+            services: self.registry.get_services()
+                .map(|x| {
+                    let resources = x.get_resource_types().iter().map(|x| x.get_name().to_string()).collect();
+                    (x.get_name().to_string(), resources)
+                })
+                .collect(),
+            profiles: aws::get_profiles(),
+            regions: aws::REGIONS.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn run_command(&mut self, command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Open { service, resource } => self.open_path(&service, resource.as_deref()),
+            Command::Context(profile) => self.switch_context(Context { profile: Some(profile), region: None, ..self.context.clone() }),
+            Command::Region(region) => self.switch_context(Context { region: Some(region), ..self.context.clone() }),
+            Command::Arn(arn) => self.jump(&arn),
+        }
+    }
+
+    fn on_arn_prompt(&mut self) {
+        self.prompt = Some(PromptState::new(PromptKind::Arn));
+    }
+
+    fn on_command_prompt(&mut self) {
+        let mut prompt = PromptState::new(PromptKind::Command);
+        prompt.completions = self.get_catalog().complete("");
+        self.prompt = Some(prompt);
+    }
+
+    fn on_prompt_input(&mut self, c: char) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.push(c);
+        }
+        self.on_prompt_changed();
+    }
+
+    fn on_prompt_backspace(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.pop();
+        }
+        self.on_prompt_changed();
+    }
+
+    /// Accept the first completion.
+    fn on_prompt_complete(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            if let Some(completion) = prompt.completions.first() {
+                prompt.input = format!("{} ", completion);
+            }
+        }
+        self.on_prompt_changed();
+    }
+
+    /// Step back through previously run commands.
+    fn on_prompt_history_back(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            if prompt.kind != PromptKind::Command || self.command_history.is_empty() {
+                return;
+            }
+            let idx = match prompt.history {
+                Some(idx) => idx.saturating_sub(1),
+                None => self.command_history.len() - 1,
+            };
+            prompt.history = Some(idx);
+            prompt.input = self.command_history[idx].clone();
+        }
+        self.on_prompt_changed();
+    }
+
+    /// Step forward through previously run commands, back to an empty line past the latest one.
+    fn on_prompt_history_forward(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            match prompt.history {
+                Some(idx) if idx + 1 < self.command_history.len() => {
+                    prompt.history = Some(idx + 1);
+                    prompt.input = self.command_history[idx + 1].clone();
                 }
+                Some(_) => {
+                    prompt.history = None;
+                    prompt.input.clear();
+                }
+                None => {}
             }
-            None => 0,
+        }
+        self.on_prompt_changed();
+    }
+
+    /// Keep whatever depends on the prompt's input up to date as it is typed.
+    fn on_prompt_changed(&mut self) {
+        let (kind, input) = match &self.prompt {
+            Some(prompt) => (prompt.kind, prompt.input.clone()),
+            None => return,
         };
-        self.state.select(Some(i));
+        match kind {
+            PromptKind::Command => {
+                let completions = self.get_catalog().complete(&input);
+                if let Some(prompt) = &mut self.prompt {
+                    prompt.completions = completions;
+                }
+            }
+            PromptKind::Filter => self.apply_filter(&input),
+            PromptKind::Arn | PromptKind::Answer | PromptKind::Pattern | PromptKind::Save => {}
+        }
     }
 
-    fn unselect(&mut self) {
-        self.state.select(None);
+    fn on_filter_prompt(&mut self) {
+        let mut prompt = PromptState::new(PromptKind::Filter);
+        prompt.input = self.get_filter().input.clone();
+        self.prompt = Some(prompt);
     }
-}
 
-/// This struct holds the current state of the app. In particular, it has the `items` field which is a wrapper
-/// around `ListState`. Keeping track of the items state let us render the associated widget with its state
-/// and have access to features such as natural scrolling.
-///
-/// Check the event handling at the bottom to see how to change the state on incoming events.
-/// Check the drawing logic for items on how to specify the highlighting style for selected items.
-pub(crate) struct App<Svc, Res> {
-    pub(crate) state: AppState<Svc, Res>
-}
+    fn get_filter(&self) -> &Filter {
+        match &self.state {
+            AppState::Services { services } => &services.filter,
+            AppState::Resources { resources, .. } => &resources.filter,
+            AppState::Listing(state) => &state.items.filter,
+            AppState::Detail(state) => &state.references.filter,
+            AppState::Pager(state) => &state.lines.filter,
+            AppState::Tail(state) => &state.events.filter,
+        }
+    }
 
-pub(crate) enum AppState<Svc, Res> {
-    Services { service: StatefulEnum<Svc> },
-    Resources { resources: StatefulEnum<Res> },
-}
+    /// Filter the current view's list. Each view keeps its own filter, so it is still in place
+    /// when navigating back to it.
+    fn apply_filter(&mut self, input: &str) {
+        match &mut self.state {
+            AppState::Services { services } => services.set_filter(Filter::parse(input, &["Name"])),
+            AppState::Resources { resources, .. } => resources.set_filter(Filter::parse(input, &["Name"])),
+            AppState::Listing(state) => state.items.set_filter(Filter::parse(input, &state.headers)),
+            AppState::Detail(state) => state.references.set_filter(Filter::parse(input, &["Label", "Target"])),
+            AppState::Pager(state) => state.lines.set_filter(Filter::parse(input, &["Line"])),
+            AppState::Tail(state) => state.events.set_filter(Filter::parse(input, &["Message", "Source"])),
+        }
+    }
 
-impl <Svc, Res> App<Svc, Res>
-    where Svc: ServiceKind,
-          Res: ResourceKind,
-{
-    pub(crate) fn new() -> App<Svc, Res> {
-        App {
-            state: AppState::Services { service: StatefulEnum::new() }
+    fn on_prompt_cancel(&mut self) {
+        if let Some(PromptKind::Filter) = self.prompt.as_ref().map(|x| x.kind) {
+            self.apply_filter("");
         }
+        self.prompt = None;
     }
 
-    fn on_esc(&mut self) {
-        match self.state {
-            AppState::Services { .. } => {}
-            AppState::Resources { .. } => {
-                self.state = AppState::Services { service: StatefulEnum::new() }
+    fn on_prompt_submit(&mut self) {
+        let prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let operation = format!("{}{}", prompt.get_label(), prompt.input.trim());
+        let result = match prompt.kind {
+            PromptKind::Arn => prompt.input.parse().and_then(|arn| self.jump(&arn)),
+            // already applied as it was typed
+            PromptKind::Filter => Ok(()),
+            PromptKind::Command => {
+                let input = prompt.input.trim().to_string();
+                if !input.is_empty() && self.command_history.last() != Some(&input) {
+                    self.command_history.push(input.clone());
+                }
+                self.get_catalog().parse(&input).and_then(|command| self.run_command(command))
             }
+            PromptKind::Answer => match prompt.question {
+                Some(question) => self.on_answer(question, prompt.input),
+                None => Ok(()),
+            },
+            PromptKind::Pattern => self.set_tail_pattern(&prompt.input),
+            PromptKind::Save => self.save_tail(&prompt.input),
+        };
+        if let Err(err) = result {
+            self.on_error(operation, err);
         }
     }
 
-    fn on_up(&mut self) {
+    /// Pass `input` on to the controller that asked for it, if its view is still around.
+    fn on_answer(&mut self, question: Question, input: String) -> anyhow::Result<()> {
+        let ctrl = match self.find_view(question.view) {
+            Some(AppState::Listing(state)) => state.ctrl.clone(),
+            Some(AppState::Detail(state)) => state.ctrl.clone(),
+            _ => anyhow::bail!("the view asking has gone"),
+        };
+        self.requests.push(Request {
+            view: question.view,
+            ctrl,
+            operation: Operation::Respond(question.key, question.id, input),
+        });
+        Ok(())
+    }
+
+    /// Pass the text saved from the editor on to the controller that asked, unless it wasn't
+    /// changed and was being edited rather than composed.
+    pub fn on_edited(&mut self, editing: Editing, text: String) {
+        if text == editing.text && !editing.compose {
+            self.status = Some("No changes made".to_string());
+            return;
+        }
+        if let Err(err) = self.on_answer(editing.question, text) {
+            self.on_error("edit".to_string(), err);
+        }
+    }
+
+    /// Clear the current view's filter if it has one, otherwise go back to the previous view.
+    fn on_esc(&mut self) {
+        if !self.get_filter().is_empty() {
+            self.apply_filter("");
+        } else if let Some(previous) = self.history.pop() {
+            self.status = None;
+            self.state = previous;
+        }
+    }
+
+    /// The list shown by the current view.
+    fn get_list(&mut self) -> &mut dyn Navigable {
         match &mut self.state {
-            AppState::Services {service } => {
-                service.items.next();
+            AppState::Services { services } => services,
+            AppState::Resources { resources, .. } => resources,
+            AppState::Listing(state) => &mut state.items,
+            AppState::Detail(state) => &mut state.references,
+            AppState::Pager(state) => &mut state.lines,
+            AppState::Tail(state) => &mut state.events,
+        }
+    }
+
+    fn on_select(&mut self) {
+        let result = match &mut self.state {
+            AppState::Services { services } => match services.selected().cloned() {
+                Some(name) => self.registry.get_resources(&name).map(|resources| {
+                    let resources = StatefulList::with_items(resources);
+                    self.navigate(AppState::Resources { service: name, resources });
+                }),
+                None => Ok(()),
+            },
+            AppState::Resources { service, resources } => match resources.selected().cloned() {
+                Some(resource) => {
+                    let service = service.clone();
+                    self.open_listing(service, resource, None)
+                }
+                None => Ok(()),
+            },
+            AppState::Listing(state) => match state.items.selected().and_then(|x| x.contents.clone()) {
+                Some(contents) => self.follow(contents),
+                None => return self.on_describe(),
+            },
+            AppState::Detail(state) => match state.references.selected().cloned() {
+                Some(target) => self.follow(target),
+                None => Ok(()),
+            },
+            AppState::Pager(_) => Ok(()),
+            AppState::Tail(state) => {
+                if let Some((title, text)) = state.get_selected_text() {
+                    self.navigate(AppState::Pager(PagerState::new(title, &text)));
+                }
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            self.on_error("open".to_string(), err);
+        }
+    }
+
+    /// Open the detail view for the selected row, even one holding other resources.
+    fn on_describe(&mut self) {
+        let result = match &self.state {
+            AppState::Listing(state) => match state.items.selected() {
+                Some(row) => {
+                    let target = Reference::new(&row.name, &state.service, &state.resource, row.id.clone());
+                    let ctrl = (state.ctrl.clone(), state.actions.clone());
+                    self.open_detail(target, Some(ctrl))
+                }
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            self.on_error("open".to_string(), err);
+        }
+    }
+
+    /// Keys not bound to navigation are offered to the current view's actions: a listing's, on
+    /// the selected row, or a detail view's, on the resource described.
+    fn on_key(&mut self, key: char) {
+        let (view, ctrl, id) = match &self.state {
+            AppState::Listing(state) if state.actions.iter().any(|x| x.key == key) => {
+                (state.view, state.ctrl.clone(), state.items.selected().map(|x| x.id.clone()))
             }
-            AppState::Resources { resources } => {
-                resources.items.next()
+            AppState::Detail(state) if state.actions.iter().any(|x| x.key == key) => {
+                (state.view, state.ctrl.clone(), Some(state.target.id.clone()))
             }
+            AppState::Detail(state) if key == 'M' && !state.metrics.is_empty() => return self.on_period(),
+            AppState::Tail(_) => return self.on_tail_key(key),
+            _ => return,
+        };
+        self.requests.push(Request { view, ctrl, operation: Operation::Perform(key, id) });
+    }
+
+    /// Chart the current detail view's metrics over the next longer period, or the shortest
+    /// again after the longest.
+    fn on_period(&mut self) {
+        if let AppState::Detail(state) = &mut self.state {
+            state.period = state.period.next();
+            state.series.clear();
+            self.requests.extend(metrics_request(state));
         }
     }
 
-    fn on_down(&mut self) {
-        match &mut self.state {
-            AppState::Services {service } => {
-                service.items.previous();
+    /// Change how the current tail shows its events, or ask for a pattern or where to save.
+    fn on_tail_key(&mut self, key: char) {
+        let state = match &mut self.state {
+            AppState::Tail(state) => state,
+            _ => return,
+        };
+        match key {
+            'f' => {
+                state.follow = !state.follow;
+                if state.follow {
+                    state.events.last();
+                }
             }
-            AppState::Resources { resources } => {
-                resources.items.previous()
+            'u' => state.utc = !state.utc,
+            'J' => state.pretty = !state.pretty,
+            'F' => {
+                let mut prompt = PromptState::new(PromptKind::Pattern);
+                prompt.input = state.pattern.clone();
+                self.prompt = Some(prompt);
             }
+            'w' => {
+                let mut prompt = PromptState::new(PromptKind::Save);
+                prompt.input = format!("{}.log", state.title.trim_start_matches('/').replace('/', "-"));
+                self.prompt = Some(prompt);
+            }
+            _ => {}
         }
     }
 
-    fn on_select(&mut self) {
-        match &mut self.state {
-            AppState::Services { service } => {
-                service.select();
-                self.state = AppState::Resources { resources: StatefulEnum::new() }
+    /// Read the current tail again from recent events, matching `pattern`.
+    fn set_tail_pattern(&mut self, pattern: &str) -> anyhow::Result<()> {
+        let state = match &mut self.state {
+            AppState::Tail(state) => state,
+            _ => anyhow::bail!("not following a log"),
+        };
+        state.set_pattern(pattern.trim().to_string());
+        if !state.loading {
+            self.requests.push(tail_request(state));
+        }
+        Ok(())
+    }
+
+    /// Write the current tail's events, as shown, to the file at `path`.
+    fn save_tail(&mut self, path: &str) -> anyhow::Result<()> {
+        let state = match &self.state {
+            AppState::Tail(state) => state,
+            _ => anyhow::bail!("not following a log"),
+        };
+        let path = path.trim();
+        std::fs::write(path, state.get_text()).map_err(|x| anyhow::anyhow!("couldn't write {}: {}", path, x))?;
+        self.status = Some(format!("Saved {} events to {}", state.events.len(), path));
+        Ok(())
+    }
+
+    pub fn on_response(&mut self, response: Response) {
+        let reply = match response.reply {
+            Ok(reply) => reply,
+            Err(err) => {
+                let operation = self.describe_operation(response.view, &response.operation);
+                self.on_error(operation, err);
+                if let Some(state) = self.find_view(response.view) {
+                    match state {
+                        AppState::Listing(state) => state.loading = false,
+                        AppState::Detail(state) if matches!(response.operation, Operation::Metrics(..)) => state.metrics_loading = false,
+                        AppState::Detail(state) => state.loading = false,
+                        // rather than fail again every few seconds
+                        AppState::Tail(state) => {
+                            state.loading = false;
+                            state.paused = true;
+                        }
+                        _ => {}
+                    }
+                }
+                return;
+            }
+        };
+
+        let now = (self.clock)();
+        let current = self.state.get_view() == Some(response.view);
+        let mut reload = vec![];
+        match (self.find_view(response.view), reply) {
+            (Some(AppState::Listing(state)), Reply::Listed(headers, rows, progress)) => {
+                state.headers = headers;
+                state.progress = progress;
+                state.update(rows, now);
+            }
+            (Some(AppState::Listing(state)), Reply::Performed(ActionOutcome::Reload)) => {
+                state.loading = true;
+                state.requested = Instant::now();
+                reload.push(Request { view: state.view, ctrl: state.ctrl.clone(), operation: Operation::List });
             }
-            AppState::Resources { resources } => {
-                // resources.select()
+            (Some(AppState::Detail(state)), Reply::Performed(ActionOutcome::Reload)) => {
+                state.loading = true;
+                reload.extend(metrics_request(state));
+                reload.push(Request { view: state.view, ctrl: state.ctrl.clone(), operation: Operation::Describe(state.target.id.clone()) });
             }
+            (Some(AppState::Detail(state)), Reply::Described(description)) => {
+                state.loading = false;
+                let references = description.as_ref()
+                    .map(|x| x.references.clone())
+                    .unwrap_or_default();
+                state.references.replace(references);
+                state.description = description;
+            }
+            (_, Reply::Performed(ActionOutcome::Copy(text))) => {
+                self.status = Some(format!("Copied {}", text));
+                self.clipboard = Some(text);
+            }
+            // only if still looking at the view that asked, rather than jumping away from elsewhere
+            (_, Reply::Performed(ActionOutcome::Show { title, text })) if current => {
+                self.navigate(AppState::Pager(PagerState::new(title, &text)));
+            }
+            (_, Reply::Performed(ActionOutcome::Ask { prompt, input })) if current => {
+                if let Operation::Perform(key, id) | Operation::Respond(key, id, _) = response.operation {
+                    let question = Question { view: response.view, key, id, prompt };
+                    self.prompt = Some(PromptState::ask(question, input));
+                }
+            }
+            (_, Reply::Performed(ActionOutcome::Edit { name, text })) if current => {
+                if let Operation::Perform(key, id) | Operation::Respond(key, id, _) = response.operation {
+                    let question = Question { view: response.view, key, id, prompt: name.clone() };
+                    self.editing = Some(Editing { question, name, text, compose: false });
+                }
+            }
+            (_, Reply::Performed(ActionOutcome::Compose { name, text })) if current => {
+                if let Operation::Perform(key, id) | Operation::Respond(key, id, _) = response.operation {
+                    let question = Question { view: response.view, key, id, prompt: name.clone() };
+                    self.editing = Some(Editing { question, name, text, compose: true });
+                }
+            }
+            (_, Reply::Performed(ActionOutcome::Status(message))) => {
+                self.status = Some(message);
+            }
+            (_, Reply::Performed(ActionOutcome::Transfer(jobs))) => {
+                self.status = Some(match jobs.len() {
+                    0 => "Nothing to transfer".to_string(),
+                    1 => "Queued 1 transfer, t to show".to_string(),
+                    n => format!("Queued {} transfers, t to show", n),
+                });
+                for job in jobs {
+                    self.transfers.push(job);
+                }
+                self.poll_transfers();
+            }
+            (Some(state), Reply::Performed(ActionOutcome::Tail { title, id })) if current => {
+                let ctrl = match state {
+                    AppState::Listing(state) => state.ctrl.clone(),
+                    AppState::Detail(state) => state.ctrl.clone(),
+                    _ => return,
+                };
+                let mut state = TailState::new(self.next_view(), ctrl, id, title);
+                reload.push(tail_request(&mut state));
+                self.navigate(AppState::Tail(state));
+            }
+            (Some(AppState::Detail(state)), Reply::Metrics(series)) => {
+                // unless the period changed since, and they're on their way again
+                if matches!(response.operation, Operation::Metrics(_, period) if period == state.period) {
+                    state.metrics_loading = false;
+                    state.series = series;
+                }
+            }
+            (Some(AppState::Tail(state)), Reply::Tailed(events)) => {
+                state.loading = false;
+                match response.operation {
+                    // read before the pattern last changed, so read again with the new one
+                    Operation::Tail(_, pattern, _) if pattern != state.pattern => reload.push(tail_request(state)),
+                    _ => state.append(events),
+                }
+            }
+            _ => {}
         }
+        self.requests.extend(reload);
     }
 
-    fn on_unselect(&mut self) {
+    /// Dispatch a key press, to the prompt if one is open, and otherwise through the keymap.
+    /// Returns whether the app should keep running.
+    pub fn on_key_press(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        if self.prompt.is_some() {
+            match key.code {
+                crossterm::event::KeyCode::Char(c) => self.on_prompt_input(c),
+                crossterm::event::KeyCode::Backspace => self.on_prompt_backspace(),
+                crossterm::event::KeyCode::Tab => self.on_prompt_complete(),
+                crossterm::event::KeyCode::Up => self.on_prompt_history_back(),
+                crossterm::event::KeyCode::Down => self.on_prompt_history_forward(),
+                crossterm::event::KeyCode::Enter => self.on_prompt_submit(),
+                crossterm::event::KeyCode::Esc => self.on_prompt_cancel(),
+                _ => {}
+            }
+            return true;
+        }
+        match self.keymap.feed(key.into()) {
+            Outcome::Action(action, count) if self.overlay.is_some() => self.on_overlay_action(action, count),
+            Outcome::Action(action, count) => return self.on_action(action, count),
+            Outcome::Unbound(key) => match (self.overlay, key.get_char()) {
+                (None, Some(c)) => self.on_key(c),
+                (Some(Overlay::Transfers { selected }), Some(c)) => self.on_transfer_key(selected, c),
+                _ => {}
+            },
+            _ => {}
+        }
+        true
+    }
+
+    /// Carry out `action`, `count` times over for motions. Returns whether the app should keep
+    /// running.
+    fn on_action(&mut self, action: KeyAction, count: Option<usize>) -> bool {
+        let times = count.unwrap_or(1);
+        match action {
+            KeyAction::Down => (0..times).for_each(|_| self.get_list().next()),
+            KeyAction::Up => (0..times).for_each(|_| self.get_list().previous()),
+            KeyAction::PageDown => (0..times).for_each(|_| self.get_list().page_down()),
+            KeyAction::PageUp => (0..times).for_each(|_| self.get_list().page_up()),
+            KeyAction::HalfPageDown | KeyAction::HalfPageUp => {
+                let list = self.get_list();
+                let half = (list.get_page() / 2).max(1) as isize * times as isize;
+                list.move_by(if action == KeyAction::HalfPageDown { half } else { -half });
+            }
+            // with a count, both go to that line, as in vim
+            KeyAction::First | KeyAction::Last => match count {
+                Some(line) => self.get_list().select_index(line.saturating_sub(1)),
+                None if action == KeyAction::First => self.get_list().first(),
+                None => self.get_list().last(),
+            },
+            KeyAction::Select => self.on_select(),
+            KeyAction::Describe => self.on_describe(),
+            KeyAction::Unselect => self.get_list().unselect(),
+            KeyAction::Back => self.on_esc(),
+            KeyAction::Command => self.on_command_prompt(),
+            KeyAction::Filter => self.on_filter_prompt(),
+            KeyAction::Arn => self.on_arn_prompt(),
+            KeyAction::Errors => self.overlay = Some(Overlay::Errors { scroll: 0 }),
+            KeyAction::Help => self.overlay = Some(Overlay::Help { scroll: 0 }),
+            KeyAction::Transfers => self.overlay = Some(Overlay::Transfers { selected: 0 }),
+            KeyAction::Pause => self.on_pause(),
+            KeyAction::Quit => return false,
+        }
+        true
+    }
+
+    /// Scroll the overlay, or close it. Quitting closes it rather than the app.
+    fn on_overlay_action(&mut self, action: KeyAction, count: Option<usize>) {
+        let scroll = match &mut self.overlay {
+            Some(Overlay::Errors { scroll }) | Some(Overlay::Help { scroll }) => scroll,
+            Some(Overlay::Transfers { selected }) => {
+                let last = self.transfers.transfers.len().saturating_sub(1);
+                let times = count.unwrap_or(1);
+                match action {
+                    KeyAction::Down => *selected = (*selected + times).min(last),
+                    KeyAction::Up => *selected = selected.saturating_sub(times),
+                    KeyAction::First => *selected = 0,
+                    KeyAction::Last => *selected = last,
+                    KeyAction::Errors => self.overlay = Some(Overlay::Errors { scroll: 0 }),
+                    KeyAction::Help => self.overlay = Some(Overlay::Help { scroll: 0 }),
+                    KeyAction::Back | KeyAction::Quit | KeyAction::Transfers => self.overlay = None,
+                    _ => {}
+                }
+                return;
+            }
+            None => return,
+        };
+        let times = count.unwrap_or(1).min(u16::MAX as usize) as u16;
+        match action {
+            KeyAction::Down => *scroll = scroll.saturating_add(times),
+            KeyAction::Up => *scroll = scroll.saturating_sub(times),
+            KeyAction::PageDown => *scroll = scroll.saturating_add(10 * times),
+            KeyAction::PageUp => *scroll = scroll.saturating_sub(10 * times),
+            KeyAction::HalfPageDown => *scroll = scroll.saturating_add(5 * times),
+            KeyAction::HalfPageUp => *scroll = scroll.saturating_sub(5 * times),
+            KeyAction::First => *scroll = 0,
+            KeyAction::Errors if matches!(self.overlay, Some(Overlay::Help { .. })) => self.overlay = Some(Overlay::Errors { scroll: 0 }),
+            KeyAction::Help if matches!(self.overlay, Some(Overlay::Errors { .. })) => self.overlay = Some(Overlay::Help { scroll: 0 }),
+            KeyAction::Transfers => self.overlay = Some(Overlay::Transfers { selected: 0 }),
+            KeyAction::Back | KeyAction::Quit | KeyAction::Errors | KeyAction::Help => self.overlay = None,
+            _ => {}
+        }
+    }
+
+    /// Cancel, retry or clear transfers from the transfer queue.
+    fn on_transfer_key(&mut self, selected: usize, key: char) {
+        match key {
+            'c' => self.transfers.cancel(selected),
+            'r' => self.transfers.retry(selected),
+            'x' => {
+                self.transfers.clear_done();
+                let last = self.transfers.transfers.len().saturating_sub(1);
+                self.overlay = Some(Overlay::Transfers { selected: selected.min(last) });
+            }
+            _ => {}
+        }
+        self.poll_transfers();
+    }
+
+    /// Move the transfer queue along, logging the transfers that failed.
+    fn poll_transfers(&mut self) {
+        for (name, err) in self.transfers.poll() {
+            self.on_error(name, anyhow::anyhow!(err));
+        }
+    }
+
+    /// The keys that do something in the current view, with what they do: the keymap's
+    /// bindings, then the view's actions.
+    pub fn get_help(&self) -> Vec<(String, String)> {
+        let is_listing = matches!(self.state, AppState::Listing(_));
+        let is_tail = matches!(self.state, AppState::Tail(_));
+        let mut help: Vec<(String, String)> = self.keymap.get_bindings()
+            .into_iter()
+            .filter(|(action, _)| match action {
+                KeyAction::Pause => is_listing || is_tail,
+                KeyAction::Describe => is_listing,
+                _ => true,
+            })
+            .map(|(action, keys)| (keys, action.get_description().to_string()))
+            .collect();
+        let keys = match &self.state {
+            AppState::Detail(state) if !state.metrics.is_empty() => METRICS_KEYS,
+            AppState::Tail(_) => tail::KEYS,
+            _ => &[],
+        };
+        help.extend(keys.iter().map(|(key, name)| (key.to_string(), name.to_string())));
+        let actions = match &self.state {
+            AppState::Listing(state) => state.actions.as_slice(),
+            AppState::Detail(state) => state.actions.as_slice(),
+            _ => &[],
+        };
+        help.extend(actions.iter().map(|x| (x.key.to_string(), x.name.to_string())));
+        help
+    }
+
+    /// Dispatch a mouse event to whatever it landed on. Clicks select items, or open them when
+    /// doubled, and go back to breadcrumbs. The wheel moves through lists, and scrolls text.
+    pub fn on_mouse(&mut self, event: crossterm::event::MouseEvent) {
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        if self.prompt.is_some() {
+            return;
+        }
+        self.keymap.reset();
+        let (column, row) = (event.column, event.row);
+        if self.overlay.is_some() {
+            let inside = ui::contains(ui::get_overlay_area(self.areas.body), column, row);
+            match event.kind {
+                MouseEventKind::ScrollDown if inside => self.on_overlay_action(KeyAction::Down, Some(SCROLL_LINES as usize)),
+                MouseEventKind::ScrollUp if inside => self.on_overlay_action(KeyAction::Up, Some(SCROLL_LINES as usize)),
+                MouseEventKind::Down(MouseButton::Left) if !inside => self.overlay = None,
+                _ => {}
+            }
+            return;
+        }
+
+        let hit = self.hit_test(column, row);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.on_click(hit),
+            MouseEventKind::ScrollDown => self.on_scroll(hit, 1),
+            MouseEventKind::ScrollUp => self.on_scroll(hit, -1),
+            _ => {}
+        }
+    }
+
+    /// Ask whichever component was drawn at `column`, `row` what is there.
+    fn hit_test(&self, column: u16, row: u16) -> Option<Hit> {
+        let Areas { header, body } = self.areas;
+        if ui::contains(header, column, row) {
+            let header_ui = Header { title: String::new(), status: None };
+            return header_ui.hit(header, &self.get_breadcrumbs(), column, row);
+        }
+        match &self.state {
+            AppState::Services { services } => TableList {}.hit(body, services, column, row),
+            AppState::Resources { resources, .. } => TableList {}.hit(body, resources, column, row),
+            AppState::Listing(state) => ResourceList {}.hit(body, state, column, row),
+            AppState::Detail(state) => ResourceDetail {}.hit(body, state, column, row),
+            AppState::Pager(state) => Pager {}.hit(body, state, column, row),
+            AppState::Tail(state) => Tail {}.hit(body, state, column, row),
+        }
+    }
+
+    fn on_click(&mut self, hit: Option<Hit>) {
+        let now = Instant::now();
+        let double = matches!(self.last_click, Some((time, last)) if Some(last) == hit && now - time <= DOUBLE_CLICK);
+        self.last_click = hit.filter(|_| !double).map(|x| (now, x));
+        match hit {
+            Some(Hit::Item(idx)) => {
+                self.get_list().select_index(idx);
+                if double {
+                    self.on_select();
+                }
+            }
+            Some(Hit::Crumb(depth)) => self.go_back_to(depth),
+            _ => {}
+        }
+    }
+
+    /// Move through the list under the mouse, or scroll the properties.
+    fn on_scroll(&mut self, hit: Option<Hit>, delta: isize) {
+        match (&mut self.state, hit) {
+            (AppState::Detail(state), Some(Hit::Props)) => {
+                state.scroll = match delta {
+                    delta if delta > 0 => state.scroll.saturating_add(SCROLL_LINES),
+                    _ => state.scroll.saturating_sub(SCROLL_LINES),
+                };
+            }
+            _ => self.get_list().move_by(delta),
+        }
+    }
+
+    /// Go back to the view at `depth` in the breadcrumbs, as if escape had been pressed until
+    /// reaching it.
+    fn go_back_to(&mut self, depth: usize) {
+        if depth >= self.history.len() {
+            return;
+        }
+        self.history.truncate(depth + 1);
+        if let Some(state) = self.history.pop() {
+            self.status = None;
+            self.state = state;
+        }
+    }
+
+    /// Record a failed operation in the error log, and show it as a toast. Repeats of the last
+    /// error are counted rather than logged again.
+    pub fn on_error(&mut self, operation: String, err: anyhow::Error) {
+        let message = format!("{:#}", err);
+        match self.errors.last_mut() {
+            Some(last) if last.operation == operation && last.message == message => {
+                last.count += 1;
+                last.time = (self.clock)();
+            }
+            _ => self.errors.push(ErrorEntry {
+                time: (self.clock)(),
+                hint: aws::explain_error(&message),
+                operation,
+                message,
+                count: 1,
+            }),
+        }
+        if let Some(entry) = self.errors.last() {
+            let summary = entry.get_summary();
+            self.toasts.retain(|x| x.message != summary);
+            self.toasts.push(Toast::new(summary));
+        }
+    }
+
+    /// What an operation on `view` was doing, for the error log, e.g. `Ec2 Images: deregister
+    /// ami-0abc`.
+    fn describe_operation(&mut self, view: usize, operation: &Operation) -> String {
+        let (target, actions) = match self.find_view(view) {
+            Some(AppState::Listing(state)) => (format!("{} {}", state.service, state.resource), state.actions.as_slice()),
+            Some(AppState::Detail(state)) => (format!("{} {}", state.target.service, state.target.resource), state.actions.as_slice()),
+            Some(AppState::Tail(state)) => (state.title.clone(), &[][..]),
+            _ => ("Unknown view".to_string(), &[][..]),
+        };
+        let operation = match operation {
+            Operation::List => "list".to_string(),
+            Operation::Describe(id) => format!("describe {}", id),
+            Operation::Perform(key, id) | Operation::Respond(key, id, _) => {
+                let name = actions.iter()
+                    .find(|x| x.key == *key)
+                    .map(|x| x.name.to_lowercase())
+                    .unwrap_or_else(|| format!("action '{}'", key));
+                match id {
+                    Some(id) => format!("{} {}", name, id),
+                    None => name,
+                }
+            }
+            Operation::Tail(_, pattern, _) if pattern.is_empty() => "tail".to_string(),
+            Operation::Tail(_, pattern, _) => format!("tail matching {}", pattern),
+            Operation::Metrics(id, period) => format!("metrics of {} over {}", id, period.get_label()),
+        };
+        format!("{}: {}", target, operation)
+    }
+
+    /// Let the services release their clients before exiting.
+    pub fn shutdown(&mut self) {
+        self.registry.shutdown();
+    }
+
+    fn find_view(&mut self, view: usize) -> Option<&mut AppState> {
+        std::iter::once(&mut self.state)
+            .chain(self.history.iter_mut())
+            .find(|x| x.get_view() == Some(view))
+    }
+
+    /// Stop or restart the current listing refreshing itself, or the current tail reading on.
+    fn on_pause(&mut self) {
         match &mut self.state {
-            AppState::Services {service } => {
-                service.items.unselect();
+            AppState::Listing(state) => state.paused = !state.paused,
+            AppState::Tail(state) => state.paused = !state.paused,
+            _ => {}
+        }
+    }
+
+    /// Housekeeping between key presses: refresh the current listing or tail if it's due, and
+    /// let highlights and toasts lapse.
+    pub fn on_tick(&mut self) {
+        self.toasts.retain(|x| !x.is_expired());
+        self.poll_transfers();
+        match &mut self.state {
+            AppState::Listing(state) if state.is_due() => {
+                state.loading = true;
+                state.requested = Instant::now();
+                self.requests.push(Request { view: state.view, ctrl: state.ctrl.clone(), operation: Operation::List });
             }
-            AppState::Resources { resources } => {
-                resources.items.unselect()
+            AppState::Tail(state) if state.is_due() => self.requests.push(tail_request(state)),
+            _ => {}
+        }
+        for state in std::iter::once(&mut self.state).chain(self.history.iter_mut()) {
+            if let AppState::Listing(state) = state {
+                state.expire_changes();
             }
         }
     }
+}
 
-    fn on_tick(&mut self) {
+/// A request for the metrics `state` charts over its period, if it charts any.
+fn metrics_request(state: &mut ResourceDetailState) -> Option<Request> {
+    if state.metrics.is_empty() {
+        return None;
     }
+    state.metrics_loading = true;
+    let operation = Operation::Metrics(state.target.id.clone(), state.period);
+    Some(Request { view: state.view, ctrl: state.ctrl.clone(), operation })
 }
 
+/// Read the log `state` follows on from where it got to.
+fn tail_request(state: &mut TailState) -> Request {
+    state.loading = true;
+    state.requested = Instant::now();
+    let operation = Operation::Tail(state.id.clone(), state.pattern.clone(), state.get_since());
+    Request { view: state.view, ctrl: state.ctrl.clone(), operation }
+}
 
-pub(crate) async fn run_app<B, Svc, Res>(
+/// Names of the services offered by `registry`.
+fn get_services(registry: &Registry) -> Vec<String> {
+    registry.get_services().map(|x| x.get_name().to_string()).collect()
+}
+
+pub async fn run_app<B>(
     mut terminal: Terminal<B>,
-    mut app: App<Svc, Res>,
-    mut rx: tokio::sync::mpsc::Receiver<Vec<String>>,
-    mut shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    mut app: App,
+    shutdown_tx: tokio::sync::broadcast::Sender<()>,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
     tick_rate: Duration,
 ) -> anyhow::Result<Terminal<B>>
     where B: Backend,
-          Svc: ServiceKind,
-          Res: ResourceKind,
 {
     let mut last_tick = Instant::now();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
 
     loop {
+        for request in app.take_requests() {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _ = tx.send(request.execute().await).await;
+            });
+        }
+        if let Some(text) = app.clipboard.take() {
+            if let Err(err) = crate::ui::clipboard::copy(&text) {
+                app.on_error("copy".to_string(), err);
+            }
+        }
+        if let Some(editing) = app.editing.take() {
+            let edited = tokio::task::block_in_place(|| crate::ui::editor::edit(&editing.name, &editing.text));
+            // the editor has drawn over the screen
+            terminal.clear()?;
+            match edited {
+                Ok(text) => app.on_edited(editing, text),
+                Err(err) => app.on_error("edit".to_string(), err),
+            }
+        }
+
         tokio::select! {
             _ = shutdown_rx.recv() => {
+                app.shutdown();
                 return Ok(terminal)
             }
-            // items = rx.recv() => {
-            //     if let Some(items) = items {
-            //         // app.streams.items = items;
-            //     }
-            // },
+            response = rx.recv() => {
+                if let Some(response) = response {
+                    app.on_response(response);
+                }
+            },
             _ = futures::future::ready(()) => {
-                terminal.draw(|f| crate::ui::ui(f, &mut app).unwrap())?;
+                let mut drawn = Ok(());
+                terminal.draw(|f| drawn = crate::ui::ui(f, &mut app))?;
+                if let Err(err) = drawn {
+                    app.on_error("draw".to_string(), err);
+                }
 
                 let timeout = tick_rate
                     .checked_sub(last_tick.elapsed())
                     .unwrap_or_else(|| Duration::from_secs(0));
                 if crossterm::event::poll(timeout)? {
-                    if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
-                        match key.code {
-                            crossterm::event::KeyCode::Char('q') => {
-                                shutdown_tx.send(())?;
-                                return Ok(terminal)
-                            },
-                            crossterm::event::KeyCode::Esc => app.on_esc(),
-                            crossterm::event::KeyCode::Char('j') => app.on_up(),
-                            crossterm::event::KeyCode::Char('k') => app.on_down(),
-                            crossterm::event::KeyCode::Char('h') => app.on_unselect(),
-                            crossterm::event::KeyCode::Enter => app.on_select(),
-                            crossterm::event::KeyCode::Left => app.on_unselect(),
-                            crossterm::event::KeyCode::Up => app.on_up(),
-                            crossterm::event::KeyCode::Down => app.on_down(),
-                            _ => {}
+                    match crossterm::event::read()? {
+                        crossterm::event::Event::Key(key) if !app.on_key_press(key) => {
+                            app.shutdown();
+                            shutdown_tx.send(())?;
+                            return Ok(terminal)
                         }
+                        crossterm::event::Event::Mouse(event) => app.on_mouse(event),
+                        _ => {}
                     }
                 }
                 if last_tick.elapsed() >= tick_rate {
@@ -230,3 +1265,50 @@ pub(crate) async fn run_app<B, Svc, Res>(
     }
 }
 
+/// Leave the terminal as it was found: out of raw mode, on the main screen, with the cursor.
+fn restore_terminal() -> anyhow::Result<()> {
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::event::DisableMouseCapture,
+        crossterm::cursor::Show,
+    )?;
+    Ok(())
+}
+
+/// Take over the terminal and run `app` until it quits, restoring the terminal afterwards, even
+/// if something panics.
+pub async fn run(app: App) -> anyhow::Result<()> {
+    // restore the terminal before the panic message is printed, or it would be lost
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+
+    // setup terminal
+    crossterm::terminal::enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    crossterm::execute!(stdout, crossterm::terminal::EnterAlternateScreen, crossterm::event::EnableMouseCapture)?;
+    let backend = tui::backend::CrosstermBackend::new(stdout);
+    let terminal = Terminal::new(backend)?;
+
+    // run app
+    let tick_rate = Duration::from_millis(250);
+
+    let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+
+    let app_handle = tokio::task::spawn(run_app(
+        terminal,
+        app,
+        shutdown_tx.clone(),
+        shutdown_tx.subscribe(),
+        tick_rate,
+    ));
+    let res = app_handle.await;
+    restore_terminal()?;
+    // a panic has already been reported by the hook
+    res??;
+    Ok(())
+}
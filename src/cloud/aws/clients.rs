@@ -0,0 +1,60 @@
+use std::any::{Any, TypeId};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use crate::service::Context;
+
+/// A client built for `context`, along with the state of the shared config and credentials
+/// files at the time.
+struct Entry {
+    kind: TypeId,
+    context: Context,
+    stamp: Vec<Option<SystemTime>>,
+    client: Box<dyn Any + Send>,
+}
+
+/// Clients built so far. SDK clients share a connection pool across clones, so every controller
+/// for the same service, profile and region gets the same one.
+static CLIENTS: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// Modification times of the shared config and credentials files. When these change, e.g.
+/// after `aws sso login` or a credentials rotation, clients built before may hold stale
+/// credentials and are rebuilt.
+fn get_stamp() -> Vec<Option<SystemTime>> {
+    super::get_shared_files()
+        .iter()
+        .map(|x| std::fs::metadata(x).and_then(|x| x.modified()).ok())
+        .collect()
+}
+
+/// The cached `T` for `context`, unless there is none or its credentials may have changed.
+pub(super) fn get<T>(context: &Context) -> Option<T>
+    where T: Clone + 'static
+{
+    let stamp = get_stamp();
+    let mut clients = CLIENTS.lock().unwrap();
+    clients.retain(|x| x.stamp == stamp);
+    clients.iter()
+        .find(|x| x.kind == TypeId::of::<T>() && x.context == *context)
+        .and_then(|x| x.client.downcast_ref::<T>())
+        .cloned()
+}
+
+pub(super) fn insert<T>(context: &Context, client: T)
+    where T: Send + 'static
+{
+    let mut clients = CLIENTS.lock().unwrap();
+    clients.retain(|x| x.kind != TypeId::of::<T>() || x.context != *context);
+    clients.push(Entry {
+        kind: TypeId::of::<T>(),
+        context: context.clone(),
+        stamp: get_stamp(),
+        client: Box::new(client),
+    });
+}
+
+/// Drop every cached `T`, closing their connections once the last request using them is done.
+pub(super) fn clear<T>()
+    where T: 'static
+{
+    CLIENTS.lock().unwrap().retain(|x| x.kind != TypeId::of::<T>());
+}
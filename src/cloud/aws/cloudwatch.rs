@@ -0,0 +1,72 @@
+use aws_sdk_cloudwatch::model::{Dimension, MetricDataQuery, MetricStat};
+use aws_smithy_types::DateTime;
+use crate::cloud::aws::{clients, AwsProvider};
+use crate::service::metrics::{Metric, Period, Series};
+
+/// The CloudWatch client for `provider`'s context, shared like the services' own. Services
+/// charting metrics drop it along with theirs.
+async fn get_client(provider: &AwsProvider) -> aws_sdk_cloudwatch::Client {
+    if let Some(client) = clients::get(&provider.context) {
+        return client;
+    }
+    let client = aws_sdk_cloudwatch::Client::new(&provider.get_config().await);
+    clients::insert(&provider.context, client.clone());
+    client
+}
+
+/// Values of `metrics` in `namespace` for the resource `dimension` names, e.g.
+/// `("InstanceId", "i-0abc")`, over `period` up to now.
+pub(crate) async fn get_series(
+    provider: &AwsProvider,
+    namespace: &str,
+    dimension: (&str, &str),
+    metrics: Vec<Metric>,
+    period: Period,
+) -> anyhow::Result<Vec<Series>> {
+    let client = get_client(provider).await;
+    let start = period.get_start(chrono::Utc::now().timestamp());
+    let queries: Vec<MetricDataQuery> = metrics.iter()
+        .enumerate()
+        .map(|(i, x)| {
+            let metric = aws_sdk_cloudwatch::model::Metric::builder()
+                .namespace(namespace)
+                .metric_name(x.name)
+                .dimensions(Dimension::builder().name(dimension.0).value(dimension.1).build())
+                .build();
+            let stat = MetricStat::builder()
+                .metric(metric)
+                .period(period.get_step() as i32)
+                .stat(x.statistic)
+                .build();
+            MetricDataQuery::builder().id(format!("m{}", i)).metric_stat(stat).build()
+        })
+        .collect();
+
+    let mut points: Vec<Vec<(i64, f64)>> = vec![vec![]; metrics.len()];
+    let mut token = None;
+    loop {
+        let output = client.get_metric_data()
+            .set_metric_data_queries(Some(queries.clone()))
+            .start_time(DateTime::from_secs(start))
+            .end_time(DateTime::from_secs(start + period.get_length()))
+            .set_next_token(token)
+            .send().await?;
+        for result in output.metric_data_results.unwrap_or_default() {
+            let idx = result.id.as_deref()
+                .and_then(|x| x.strip_prefix('m'))
+                .and_then(|x| x.parse::<usize>().ok());
+            if let Some(points) = idx.and_then(|x| points.get_mut(x)) {
+                let timestamps = result.timestamps.unwrap_or_default().into_iter().map(|x| x.secs());
+                points.extend(timestamps.zip(result.values.unwrap_or_default()));
+            }
+        }
+        token = output.next_token;
+        if token.is_none() {
+            break;
+        }
+    }
+    Ok(metrics.into_iter()
+        .zip(points)
+        .map(|(metric, points)| Series::new(metric, period, start, points))
+        .collect())
+}
@@ -0,0 +1,981 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use aws_sdk_dynamodb::model::{AttributeValue, KeySchemaElement, KeyType, Projection, TableDescription};
+use serde_json::{json, Value};
+use crate::service::registry::ServiceType;
+use crate::service::resource::{is_confirmed, props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::transfer::format_size;
+
+const SERVICE: &str = "DynamoDB";
+
+/// Items read for each page of a scan or query. DynamoDB stops sooner if it reaches 1 MB.
+const PAGE_SIZE: i32 = 100;
+
+/// What an item is edited as, for the editor to highlight it.
+const ITEM_FILE: &str = "item.json";
+
+/// Words in condition expressions that aren't attribute names.
+const KEYWORDS: &[&str] = &["and", "or", "not", "between", "in"];
+
+/// An item, or its primary key, by attribute name.
+type Attributes = HashMap<String, AttributeValue>;
+
+pub(crate) struct DynamoDb {
+    provider: AwsProvider,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for DynamoDb {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> DynamoDb {
+        Self {
+            provider: AwsProvider::new(context),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Tables", Tables::new)
+            .resource("Items", Items::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_dynamodb::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_dynamodb::Client> for DynamoDb {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_dynamodb::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_dynamodb::Client::new(&config);
+        Ok(client)
+    }
+}
+
+impl DynamoDb {
+    async fn describe_table(&self, name: &str) -> anyhow::Result<TableDescription> {
+        self.get_client().await?.describe_table()
+            .table_name(name)
+            .send().await?
+            .table
+            .ok_or_else(|| anyhow::anyhow!("no description of {}", name))
+    }
+}
+
+pub(crate) struct Tables {
+    svc: DynamoDb,
+}
+
+impl Tables {
+    fn new(context: &Context) -> Self {
+        Tables {
+            svc: DynamoDb::new(context)
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Table> for Tables {
+    async fn list(&self) -> anyhow::Result<Vec<Table>> {
+        let client = self.svc.get_client().await?;
+        let mut names = vec![];
+        let mut start = None;
+        loop {
+            let output = client.list_tables().set_exclusive_start_table_name(start).send().await?;
+            names.extend(output.table_names.unwrap_or_default());
+            start = output.last_evaluated_table_name;
+            if start.is_none() {
+                break;
+            }
+        }
+        // names are all the listing gives, so describe each, side by side
+        let tables = futures::future::join_all(names.iter().map(|x| self.svc.describe_table(x))).await;
+        tables.into_iter()
+            .map(|x| x.map(Table::from))
+            .collect()
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Table>>> {
+        let table = Table::from(self.svc.describe_table(&id).await?);
+        Ok(Some(ResourceDescription::from_resource(&table)))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Table {
+    name: String,
+    status: Option<String>,
+    /// The partition key's attribute, then the sort key's if there is one.
+    keys: Vec<String>,
+    item_count: Option<i64>,
+    size: Option<i64>,
+    billing: Option<String>,
+    stream: Option<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl From<TableDescription> for Table {
+    fn from(table: TableDescription) -> Self {
+        let types: HashMap<String, String> = table.attribute_definitions
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| Some((x.attribute_name?, x.attribute_type?.as_str().to_string())))
+            .collect();
+        let key_schema = table.key_schema.unwrap_or_default();
+        let mut details = vec![];
+        for x in &key_schema {
+            let label = match x.key_type {
+                Some(KeyType::Range) => "Sort key",
+                _ => "Partition key",
+            };
+            let name = x.attribute_name.clone().unwrap_or_default();
+            let description = match types.get(&name) {
+                Some(kind) => format!("{} ({})", name, kind),
+                None => name,
+            };
+            details.push((label.to_string(), description));
+        }
+
+        // on-demand tables report no billing mode if they've always been on demand
+        let billing = match (table.billing_mode_summary.and_then(|x| x.billing_mode), &table.provisioned_throughput) {
+            (Some(mode), _) if mode.as_str() == "PAY_PER_REQUEST" => "On demand".to_string(),
+            (_, Some(throughput)) if throughput.read_capacity_units.unwrap_or_default() > 0 => format!(
+                "Provisioned ({} RCU, {} WCU)",
+                throughput.read_capacity_units.unwrap_or_default(),
+                throughput.write_capacity_units.unwrap_or_default(),
+            ),
+            _ => "On demand".to_string(),
+        };
+        let stream = match table.stream_specification {
+            Some(x) if x.stream_enabled == Some(true) => x.stream_view_type.map(|x| x.as_str().to_string()).unwrap_or_default(),
+            _ => "Disabled".to_string(),
+        };
+
+        for index in table.global_secondary_indexes.unwrap_or_default() {
+            let mut description = describe_index(&index.key_schema.unwrap_or_default(), index.projection, index.item_count);
+            if let Some(status) = index.index_status {
+                description.push_str(&format!(", {}", status.as_str()));
+            }
+            details.push((format!("GSI: {}", index.index_name.unwrap_or_default()), description));
+        }
+        for index in table.local_secondary_indexes.unwrap_or_default() {
+            let description = describe_index(&index.key_schema.unwrap_or_default(), index.projection, index.item_count);
+            details.push((format!("LSI: {}", index.index_name.unwrap_or_default()), description));
+        }
+        details.extend([
+            ("ARN", table.table_arn),
+            ("Created", table.creation_date_time.map(|x| x.fmt(aws_smithy_types::date_time::Format::DateTime).unwrap_or_default())),
+            ("Stream ARN", table.latest_stream_arn),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v))));
+
+        Table {
+            name: table.table_name.unwrap_or_default(),
+            status: table.table_status.map(|x| x.as_str().to_string()),
+            keys: key_schema.into_iter().filter_map(|x| x.attribute_name).collect(),
+            item_count: Some(table.item_count),
+            size: Some(table.table_size_bytes),
+            billing: Some(billing),
+            stream: Some(stream),
+            details,
+        }
+    }
+}
+
+impl Resource for Table {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Status", "Keys", "Items", "Size", "Billing", "Stream"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.status.clone().unwrap_or_default(),
+            self.keys.join(", "),
+            self.item_count.map(|x| x.to_string()).unwrap_or_default(),
+            self.size.map(|x| format_size(x as u64)).unwrap_or_default(),
+            self.billing.clone().unwrap_or_default(),
+            self.stream.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Table::get_headers(), self.get_columns());
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.get_contents().into_iter().collect()
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        Some(Reference::listing(&self.name, SERVICE, "Items", self.name.clone()))
+    }
+}
+
+/// An index's keys and projection, e.g. `email, created; KEYS_ONLY; 1042 items`.
+fn describe_index(keys: &[KeySchemaElement], projection: Option<Projection>, items: i64) -> String {
+    let keys: Vec<&str> = keys.iter().filter_map(|x| x.attribute_name.as_deref()).collect();
+    let projection = projection
+        .and_then(|x| x.projection_type)
+        .map(|x| x.as_str().to_string())
+        .unwrap_or_default();
+    format!("{}; {}; {} items", keys.join(", "), projection, items)
+}
+
+/// A table's primary key: the partition key's name and type, then the sort key's if it has one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KeySchema {
+    keys: Vec<(String, String)>,
+}
+
+impl KeySchema {
+    fn from_table(table: &TableDescription) -> Self {
+        let types: HashMap<&str, &str> = table.attribute_definitions.iter()
+            .flatten()
+            .filter_map(|x| Some((x.attribute_name.as_deref()?, x.attribute_type.as_ref()?.as_str())))
+            .collect();
+        let mut keys: Vec<(KeyType, String)> = table.key_schema.iter()
+            .flatten()
+            .filter_map(|x| Some((x.key_type.clone()?, x.attribute_name.clone()?)))
+            .collect();
+        // the partition key first, whichever order they came in
+        keys.sort_by_key(|(kind, _)| *kind == KeyType::Range);
+        KeySchema {
+            keys: keys.into_iter()
+                .map(|(_, name)| {
+                    let kind = types.get(name.as_str()).copied().unwrap_or("S").to_string();
+                    (name, kind)
+                })
+                .collect(),
+        }
+    }
+
+    fn get_partition_key(&self) -> &str {
+        self.keys.first().map(|(x, _)| x.as_str()).unwrap_or_default()
+    }
+
+    fn is_key(&self, name: &str) -> bool {
+        self.keys.iter().any(|(x, _)| x == name)
+    }
+
+    /// Just the key attributes of `item`, failing if any is missing.
+    fn get_key(&self, item: &Attributes) -> anyhow::Result<Attributes> {
+        self.keys.iter()
+            .map(|(name, _)| match item.get(name) {
+                Some(value) => Ok((name.clone(), value.clone())),
+                None => Err(anyhow::anyhow!("the item has no {}, which is part of its key", name)),
+            })
+            .collect()
+    }
+
+    /// The key values of `item`, in order, as the item's name.
+    fn get_name(&self, item: &Attributes) -> String {
+        self.keys.iter()
+            .filter_map(|(name, _)| item.get(name).map(format_value))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    /// An item with only its key attributes, empty, as a start for a new one.
+    fn get_template(&self) -> Attributes {
+        self.keys.iter()
+            .map(|(name, kind)| {
+                let value = match kind.as_str() {
+                    "N" => AttributeValue::N("0".to_string()),
+                    "B" => AttributeValue::B(aws_smithy_types::Blob::new(vec![])),
+                    _ => AttributeValue::S(String::new()),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+/// How a table's items are being read: every item, or those with a partition key, each maybe
+/// narrowed by a filter. Expressions are as the user typed them, before `Expressions` has
+/// replaced names and values with placeholders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Search {
+    Scan { filter: String },
+    Query { condition: String, filter: String },
+}
+
+/// A table's items, a page at a time, from a scan or a query. Attributes vary from one item to
+/// the next, so the columns are those of the items on the page, keys first.
+pub(crate) struct Items {
+    svc: DynamoDb,
+    /// The table, from the scope.
+    table: Option<String>,
+    search: Search,
+    /// A query's key condition, while waiting on its filter.
+    condition: Option<String>,
+    /// Where each page after the first starts, up to the current one.
+    pages: Vec<Attributes>,
+    /// Where the page after the current one starts, if there is one, as of the last listing.
+    next: Mutex<Option<Attributes>>,
+    schema: Mutex<Option<KeySchema>>,
+    headers: Mutex<Vec<String>>,
+}
+
+impl Items {
+    fn new(context: &Context) -> Self {
+        Items {
+            svc: DynamoDb::new(context),
+            table: None,
+            search: Search::Scan { filter: String::new() },
+            condition: None,
+            pages: vec![],
+            next: Mutex::new(None),
+            schema: Mutex::new(None),
+            headers: Mutex::new(vec![]),
+        }
+    }
+
+    fn get_table(&self) -> anyhow::Result<&str> {
+        self.table.as_deref().ok_or_else(|| anyhow::anyhow!("open a table to see its items"))
+    }
+
+    /// The table's key schema, looked up on first use.
+    async fn get_schema(&self) -> anyhow::Result<KeySchema> {
+        if let Some(schema) = self.schema.lock().unwrap().as_ref() {
+            return Ok(schema.clone());
+        }
+        let schema = KeySchema::from_table(&self.svc.describe_table(self.get_table()?).await?);
+        *self.schema.lock().unwrap() = Some(schema.clone());
+        Ok(schema)
+    }
+
+    /// The item the ID names, as it is now.
+    async fn get_item(&self, id: &str) -> anyhow::Result<Attributes> {
+        self.svc.get_client().await?.get_item()
+            .table_name(self.get_table()?)
+            .set_key(Some(parse_id(id)?))
+            .consistent_read(true)
+            .send().await?
+            .item
+            .ok_or_else(|| anyhow::anyhow!("no item {}", id))
+    }
+
+    /// Save the edited `item` over the one the ID names, setting the attributes that changed and
+    /// removing those that went. The key can't change, since that would make it another item.
+    async fn update(&self, id: &str, item: Attributes) -> anyhow::Result<ActionOutcome> {
+        let schema = self.get_schema().await?;
+        let key = parse_id(id)?;
+        if schema.get_key(&item)? != key {
+            anyhow::bail!("the key can't be changed; add a new item with n instead");
+        }
+        let current = self.get_item(id).await?;
+
+        let mut expressions = Expressions::default();
+        let mut set = vec![];
+        for (name, value) in item.iter().filter(|(name, _)| !schema.is_key(name)) {
+            if current.get(name) != Some(value) {
+                set.push(format!("{} = {}", expressions.name(name), expressions.value(value.clone())));
+            }
+        }
+        let remove: Vec<String> = current.keys()
+            .filter(|x| !item.contains_key(*x))
+            .map(|x| expressions.name(x))
+            .collect();
+        let mut update = vec![];
+        if !set.is_empty() {
+            update.push(format!("SET {}", set.join(", ")));
+        }
+        if !remove.is_empty() {
+            update.push(format!("REMOVE {}", remove.join(", ")));
+        }
+        if update.is_empty() {
+            return Ok(ActionOutcome::Status("No changes made".to_string()));
+        }
+        // rather than bring back an item deleted in the meantime
+        let condition = format!("attribute_exists({})", expressions.name(schema.get_partition_key()));
+
+        self.svc.get_client().await?.update_item()
+            .table_name(self.get_table()?)
+            .set_key(Some(key))
+            .update_expression(update.join(" "))
+            .condition_expression(condition)
+            .set_expression_attribute_names(expressions.get_names())
+            .set_expression_attribute_values(expressions.get_values())
+            .send().await?;
+        Ok(ActionOutcome::Reload)
+    }
+
+    /// Add `item`, as long as there isn't one with its key already.
+    async fn put(&self, item: Attributes) -> anyhow::Result<ActionOutcome> {
+        let schema = self.get_schema().await?;
+        schema.get_key(&item)?;
+        let mut expressions = Expressions::default();
+        let condition = format!("attribute_not_exists({})", expressions.name(schema.get_partition_key()));
+        self.svc.get_client().await?.put_item()
+            .table_name(self.get_table()?)
+            .set_item(Some(item))
+            .condition_expression(condition)
+            .set_expression_attribute_names(expressions.get_names())
+            .send().await?;
+        Ok(ActionOutcome::Reload)
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Item> for Items {
+    async fn list(&self) -> anyhow::Result<Vec<Item>> {
+        let table = self.get_table()?;
+        let schema = self.get_schema().await?;
+        let client = self.svc.get_client().await?;
+        let start = self.pages.last().cloned();
+        let mut expressions = Expressions::default();
+        let (items, next) = match &self.search {
+            Search::Scan { filter } => {
+                let filter = expressions.add_optional(filter)?;
+                let output = client.scan()
+                    .table_name(table)
+                    .limit(PAGE_SIZE)
+                    .set_filter_expression(filter)
+                    .set_expression_attribute_names(expressions.get_names())
+                    .set_expression_attribute_values(expressions.get_values())
+                    .set_exclusive_start_key(start)
+                    .send().await?;
+                (output.items, output.last_evaluated_key)
+            }
+            Search::Query { condition, filter } => {
+                let condition = expressions.add(condition)?;
+                let filter = expressions.add_optional(filter)?;
+                let output = client.query()
+                    .table_name(table)
+                    .limit(PAGE_SIZE)
+                    .key_condition_expression(condition)
+                    .set_filter_expression(filter)
+                    .set_expression_attribute_names(expressions.get_names())
+                    .set_expression_attribute_values(expressions.get_values())
+                    .set_exclusive_start_key(start)
+                    .send().await?;
+                (output.items, output.last_evaluated_key)
+            }
+        };
+        *self.next.lock().unwrap() = next.filter(|x| !x.is_empty());
+
+        let items = items.unwrap_or_default();
+        let headers = get_headers(&schema, &items);
+        let items = items.iter()
+            .map(|x| Item::new(&schema, x, &headers))
+            .collect::<anyhow::Result<_>>()?;
+        *self.headers.lock().unwrap() = headers;
+        Ok(items)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Item>>> {
+        let schema = self.get_schema().await?;
+        let item = self.get_item(&id).await?;
+        let headers: Vec<String> = get_headers(&schema, std::slice::from_ref(&item));
+        Ok(Some(ResourceDescription::from_resource(&Item::new(&schema, &item, &headers)?)))
+    }
+
+    fn get_headers(&self) -> Vec<String> {
+        let headers = self.headers.lock().unwrap();
+        match headers.is_empty() {
+            true => Item::get_headers().into_iter().map(|x| x.to_string()).collect(),
+            false => headers.clone(),
+        }
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.table = Some(scope);
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 's', name: "Scan" },
+            Action { key: 'Q', name: "Query" },
+            Action { key: ']', name: "Next page" },
+            Action { key: '[', name: "Previous page" },
+            Action { key: 'o', name: "Show as JSON" },
+            Action { key: 'e', name: "Edit" },
+            Action { key: 'n', name: "New item" },
+            Action { key: 'x', name: "Delete" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        match key {
+            's' => {
+                let filter = match &self.search {
+                    Search::Scan { filter } => filter.clone(),
+                    Search::Query { .. } => String::new(),
+                };
+                return Ok(ActionOutcome::Ask { prompt: "Scan, filtered by (blank for all): ".to_string(), input: filter });
+            }
+            'Q' => {
+                self.condition = None;
+                let condition = match &self.search {
+                    Search::Query { condition, .. } => condition.clone(),
+                    Search::Scan { .. } => format!("{} = ", self.get_schema().await?.get_partition_key()),
+                };
+                return Ok(ActionOutcome::Ask { prompt: "Query, key condition: ".to_string(), input: condition });
+            }
+            ']' => {
+                let next = self.next.lock().unwrap().clone()
+                    .ok_or_else(|| anyhow::anyhow!("already on the last page"))?;
+                self.pages.push(next);
+                return Ok(ActionOutcome::Reload);
+            }
+            '[' => {
+                self.pages.pop().ok_or_else(|| anyhow::anyhow!("already on the first page"))?;
+                return Ok(ActionOutcome::Reload);
+            }
+            'n' => {
+                let text = serde_json::to_string_pretty(&to_json_map(&self.get_schema().await?.get_template()))?;
+                return Ok(ActionOutcome::Edit { name: ITEM_FILE.to_string(), text });
+            }
+            _ => {}
+        }
+
+        let id = id.ok_or_else(|| anyhow::anyhow!("no item selected"))?;
+        match key {
+            'o' => {
+                let item = self.get_item(&id).await?;
+                let title = self.get_schema().await?.get_name(&item);
+                Ok(ActionOutcome::Show { title, text: serde_json::to_string_pretty(&to_json_map(&item))? })
+            }
+            'e' => {
+                let text = serde_json::to_string_pretty(&to_json_map(&self.get_item(&id).await?))?;
+                Ok(ActionOutcome::Edit { name: ITEM_FILE.to_string(), text })
+            }
+            'x' => {
+                let name = self.get_schema().await?.get_name(&parse_id(&id)?);
+                Ok(ActionOutcome::confirm(&format!("Delete {}?", name)))
+            }
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let input = input.trim().to_string();
+        match key {
+            's' => {
+                self.search = Search::Scan { filter: input };
+                self.pages.clear();
+                Ok(ActionOutcome::Reload)
+            }
+            // a query asks twice, for the key condition and then the filter
+            'Q' => match self.condition.take() {
+                None if input.is_empty() => anyhow::bail!("a query needs a key condition"),
+                None => {
+                    let filter = match &self.search {
+                        Search::Query { filter, .. } => filter.clone(),
+                        Search::Scan { .. } => String::new(),
+                    };
+                    self.condition = Some(input);
+                    Ok(ActionOutcome::Ask { prompt: "Query, filtered by (blank for all): ".to_string(), input: filter })
+                }
+                Some(condition) => {
+                    self.search = Search::Query { condition, filter: input };
+                    self.pages.clear();
+                    Ok(ActionOutcome::Reload)
+                }
+            },
+            'e' => {
+                let id = id.ok_or_else(|| anyhow::anyhow!("no item selected"))?;
+                self.update(&id, parse_item(&input)?).await
+            }
+            'n' => self.put(parse_item(&input)?).await,
+            'x' if !is_confirmed(&input) => Ok(ActionOutcome::Status("Not deleted".to_string())),
+            'x' => {
+                let id = id.ok_or_else(|| anyhow::anyhow!("no item selected"))?;
+                self.svc.get_client().await?.delete_item()
+                    .table_name(self.get_table()?)
+                    .set_key(Some(parse_id(&id)?))
+                    .send().await?;
+                Ok(ActionOutcome::Reload)
+            }
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+    }
+}
+
+/// An item's attributes as the listing's columns, and as props.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Item {
+    /// The item's key, as DynamoDB JSON.
+    id: String,
+    name: String,
+    columns: Vec<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    attributes: Vec<(String, String)>,
+}
+
+impl Item {
+    fn new(schema: &KeySchema, item: &Attributes, headers: &[String]) -> anyhow::Result<Self> {
+        let mut attributes: Vec<(String, String)> = item.iter()
+            .map(|(k, v)| (k.clone(), format_value(v)))
+            .collect();
+        attributes.sort();
+        Ok(Item {
+            id: serde_json::to_string(&to_json_map(&schema.get_key(item)?))?,
+            name: schema.get_name(item),
+            columns: headers.iter()
+                .map(|x| item.get(x).map(format_value).unwrap_or_default())
+                .collect(),
+            attributes,
+        })
+    }
+}
+
+impl Resource for Item {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Only until the first page arrives, after which the controller has the page's attributes.
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Key"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        self.attributes.iter().cloned().collect()
+    }
+}
+
+/// The attributes of `items`, as columns: the key's, then the rest alphabetically.
+fn get_headers(schema: &KeySchema, items: &[Attributes]) -> Vec<String> {
+    let others: BTreeSet<&String> = items.iter()
+        .flat_map(|x| x.keys())
+        .filter(|x| !schema.is_key(x))
+        .collect();
+    schema.keys.iter()
+        .map(|(name, _)| name.clone())
+        .chain(others.into_iter().cloned())
+        .collect()
+}
+
+/// Expressions as DynamoDB wants them, with placeholders for attribute names and values, from
+/// expressions as typed, with the names and values in place: `status = "open" and size(tags) > 2`
+/// becomes `#n0 = :v0 and size(#n1) > :v1`. This saves writing the placeholders out by hand,
+/// and means names like `status`, which DynamoDB reserves, need no special treatment.
+#[derive(Default)]
+struct Expressions {
+    names: HashMap<String, String>,
+    values: HashMap<String, AttributeValue>,
+}
+
+impl Expressions {
+    /// The placeholder for the attribute `name`.
+    fn name(&mut self, name: &str) -> String {
+        if let Some((placeholder, _)) = self.names.iter().find(|(_, x)| *x == name) {
+            return placeholder.clone();
+        }
+        let placeholder = format!("#n{}", self.names.len());
+        self.names.insert(placeholder.clone(), name.to_string());
+        placeholder
+    }
+
+    /// A placeholder for `value`.
+    fn value(&mut self, value: AttributeValue) -> String {
+        let placeholder = format!(":v{}", self.values.len());
+        self.values.insert(placeholder.clone(), value);
+        placeholder
+    }
+
+    /// `expression` with placeholders in place of its names and values. Quoted text is a string,
+    /// numbers are numbers, and `true` and `false` are booleans. Words before a bracket are
+    /// functions, and the rest, besides keywords, are attribute names.
+    fn add(&mut self, expression: &str) -> anyhow::Result<String> {
+        let chars: Vec<char> = expression.chars().collect();
+        let mut output = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let start = i;
+            i += 1;
+            if c == '"' || c == '\'' {
+                let mut text = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => anyhow::bail!("unterminated string in {}", expression),
+                        Some(&x) if x == c => break,
+                        Some('\\') => {
+                            i += 1;
+                            text.extend(chars.get(i));
+                        }
+                        Some(&x) => text.push(x),
+                    }
+                    i += 1;
+                }
+                i += 1;
+                output.push_str(&self.value(AttributeValue::S(text)));
+            } else if c.is_ascii_digit() || (c == '-' && chars.get(i).map(|x| x.is_ascii_digit()).unwrap_or(false)) {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                // a list index, as in `tags[0]`, is part of the path
+                match output.ends_with('[') {
+                    true => output.push_str(&number),
+                    false => output.push_str(&self.value(AttributeValue::N(number))),
+                }
+            } else if c.is_alphabetic() || c == '_' || c == '#' || c == ':' {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let function = chars[i..].iter().find(|x| !x.is_whitespace()) == Some(&'(');
+                match word.to_lowercase().as_str() {
+                    // placeholders written out by hand
+                    _ if c == '#' || c == ':' => output.push_str(&word),
+                    _ if function => output.push_str(&word),
+                    x if KEYWORDS.contains(&x) => output.push_str(&word),
+                    "true" => output.push_str(&self.value(AttributeValue::Bool(true))),
+                    "false" => output.push_str(&self.value(AttributeValue::Bool(false))),
+                    _ => output.push_str(&self.name(&word)),
+                }
+            } else {
+                output.push(c);
+            }
+        }
+        Ok(output)
+    }
+
+    /// As `add`, for expressions that may be left out.
+    fn add_optional(&mut self, expression: &str) -> anyhow::Result<Option<String>> {
+        match expression.trim() {
+            "" => Ok(None),
+            expression => self.add(expression).map(Some),
+        }
+    }
+
+    /// The names, unless there are none, as DynamoDB refuses an empty map.
+    fn get_names(&self) -> Option<HashMap<String, String>> {
+        (!self.names.is_empty()).then(|| self.names.clone())
+    }
+
+    fn get_values(&self) -> Option<HashMap<String, AttributeValue>> {
+        (!self.values.is_empty()).then(|| self.values.clone())
+    }
+}
+
+/// The item's key, from its ID.
+fn parse_id(id: &str) -> anyhow::Result<Attributes> {
+    from_json_map(&serde_json::from_str(id)?)
+}
+
+/// An item as edited, in DynamoDB JSON.
+fn parse_item(text: &str) -> anyhow::Result<Attributes> {
+    let value = serde_json::from_str(text).map_err(|x| anyhow::anyhow!("not valid JSON: {}", x))?;
+    from_json_map(&value)
+}
+
+/// A value as shown in a cell: strings and numbers as they are, and anything else as JSON,
+/// without the type tags.
+fn format_value(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::S(x) | AttributeValue::N(x) => x.clone(),
+        value => to_plain(value).to_string(),
+    }
+}
+
+fn to_plain(value: &AttributeValue) -> Value {
+    // numbers too long for a double are kept whole, as strings
+    let number = |x: &String| serde_json::from_str::<serde_json::Number>(x).map(Value::Number).unwrap_or_else(|_| json!(x));
+    match value {
+        AttributeValue::S(x) => json!(x),
+        AttributeValue::N(x) => number(x),
+        AttributeValue::B(x) => json!(base64::encode(x.as_ref())),
+        AttributeValue::Bool(x) => json!(x),
+        AttributeValue::Null(_) => Value::Null,
+        AttributeValue::Ss(x) => json!(x),
+        AttributeValue::Ns(x) => Value::Array(x.iter().map(number).collect()),
+        AttributeValue::Bs(x) => json!(x.iter().map(|x| base64::encode(x.as_ref())).collect::<Vec<_>>()),
+        AttributeValue::L(x) => Value::Array(x.iter().map(to_plain).collect()),
+        AttributeValue::M(x) => Value::Object(x.iter().map(|(k, v)| (k.clone(), to_plain(v))).collect()),
+        _ => Value::Null,
+    }
+}
+
+/// A value in DynamoDB JSON, tagged with its type, as in `{"N": "42"}`, so that nothing is lost
+/// in editing it.
+fn to_json(value: &AttributeValue) -> Value {
+    match value {
+        AttributeValue::S(x) => json!({ "S": x }),
+        AttributeValue::N(x) => json!({ "N": x }),
+        AttributeValue::B(x) => json!({ "B": base64::encode(x.as_ref()) }),
+        AttributeValue::Bool(x) => json!({ "BOOL": x }),
+        AttributeValue::Null(x) => json!({ "NULL": x }),
+        AttributeValue::Ss(x) => json!({ "SS": x }),
+        AttributeValue::Ns(x) => json!({ "NS": x }),
+        AttributeValue::Bs(x) => json!({ "BS": x.iter().map(|x| base64::encode(x.as_ref())).collect::<Vec<_>>() }),
+        AttributeValue::L(x) => json!({ "L": x.iter().map(to_json).collect::<Vec<_>>() }),
+        AttributeValue::M(x) => json!({ "M": to_json_map(x) }),
+        _ => Value::Null,
+    }
+}
+
+fn to_json_map(item: &Attributes) -> Value {
+    Value::Object(item.iter().map(|(k, v)| (k.clone(), to_json(v))).collect())
+}
+
+fn from_json(value: &Value) -> anyhow::Result<AttributeValue> {
+    let (kind, inner) = match value.as_object() {
+        Some(object) if object.len() == 1 => object.iter().next().unwrap(),
+        _ => anyhow::bail!("not a value tagged with its type, like {{\"S\": \"text\"}}: {}", value),
+    };
+    let strings = |x: &Value| -> anyhow::Result<Vec<String>> {
+        x.as_array()
+            .ok_or_else(|| anyhow::anyhow!("not a list: {}", x))?
+            .iter()
+            .map(|x| get_string(x).ok_or_else(|| anyhow::anyhow!("not a string: {}", x)))
+            .collect()
+    };
+    let binary = |x: &str| base64::decode(x).map(aws_smithy_types::Blob::new).map_err(anyhow::Error::from);
+    Ok(match (kind.as_str(), inner) {
+        ("S", Value::String(x)) => AttributeValue::S(x.clone()),
+        ("N", x) if get_string(x).is_some() => AttributeValue::N(get_string(x).unwrap_or_default()),
+        ("B", Value::String(x)) => AttributeValue::B(binary(x)?),
+        ("BOOL", Value::Bool(x)) => AttributeValue::Bool(*x),
+        ("NULL", Value::Bool(x)) => AttributeValue::Null(*x),
+        ("SS", x) => AttributeValue::Ss(strings(x)?),
+        ("NS", x) => AttributeValue::Ns(strings(x)?),
+        ("BS", x) => AttributeValue::Bs(strings(x)?.iter().map(|x| binary(x)).collect::<anyhow::Result<_>>()?),
+        ("L", Value::Array(x)) => AttributeValue::L(x.iter().map(from_json).collect::<anyhow::Result<_>>()?),
+        ("M", x) => AttributeValue::M(from_json_map(x)?),
+        _ => anyhow::bail!("not a DynamoDB value: {}", value),
+    })
+}
+
+/// A string, or a number written without quotes, which is easy to do by mistake for `N`.
+fn get_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(x) => Some(x.clone()),
+        Value::Number(x) => Some(x.to_string()),
+        _ => None,
+    }
+}
+
+fn from_json_map(value: &Value) -> anyhow::Result<Attributes> {
+    value.as_object()
+        .ok_or_else(|| anyhow::anyhow!("not an object of attributes: {}", value))?
+        .iter()
+        .map(|(k, v)| Ok((k.clone(), from_json(v)?)))
+        .collect()
+}
+
+/// The table named by a DynamoDB ARN, `arn:aws:dynamodb:eu-west-1:123456789012:table/orders`,
+/// or by the ARN of one of its indexes or streams, which follow on from the table's.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "dynamodb" {
+        return None;
+    }
+    match arn.get_resource_path()? {
+        ("table", path) => {
+            let name = path.split('/').next()?;
+            Some(Reference::new("Table", SERVICE, "Tables", name))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        let stream: aws::Arn = "arn:aws:dynamodb:eu-west-1:123456789012:table/orders/stream/2021-11-05T09:30:00.000".parse().unwrap();
+        assert_eq!(get_reference(&stream), Some(Reference::new("Table", SERVICE, "Tables", "orders")));
+
+        // Glue catalogs have tables too
+        let table: aws::Arn = "arn:aws:glue:eu-west-1:123456789012:table/db/tbl".parse().unwrap();
+        assert_eq!(get_reference(&table), None);
+    }
+
+    #[test]
+    fn expressions() {
+        let mut expressions = Expressions::default();
+        let condition = expressions.add(r#"status = "open" AND begins_with(sk, 'order#')"#).unwrap();
+        assert_eq!(condition, "#n0 = :v0 AND begins_with(#n1, :v1)");
+        let filter = expressions.add("size(tags) > 2 and tags[0] <> \"a \\\"b\\\"\" or archived = false").unwrap();
+        assert_eq!(filter, "size(#n2) > :v2 and #n2[0] <> :v3 or #n3 = :v4");
+        assert_eq!(expressions.names["#n3"], "archived");
+        assert_eq!(expressions.values[":v2"], AttributeValue::N("2".to_string()));
+        assert_eq!(expressions.values[":v3"], AttributeValue::S("a \"b\"".to_string()));
+        assert_eq!(expressions.values[":v4"], AttributeValue::Bool(false));
+
+        let mut expressions = Expressions::default();
+        assert_eq!(expressions.add("address.city = :city and total < -1.5").unwrap(), "#n0.#n1 = :city and #n2 < :v0");
+        assert!(expressions.add("name = \"unterminated").is_err());
+        assert_eq!(expressions.add_optional("  ").unwrap(), None);
+    }
+
+    #[test]
+    fn json() {
+        let text = r#"{
+            "pk": {"S": "user#1"},
+            "total": {"N": 12.5},
+            "tags": {"SS": ["a", "b"]},
+            "avatar": {"B": "aGk="},
+            "address": {"M": {"city": {"S": "Leeds"}, "visits": {"L": [{"N": "1"}, {"NULL": true}]}}}
+        }"#;
+        let item = parse_item(text).unwrap();
+        assert_eq!(item["total"], AttributeValue::N("12.5".to_string()));
+        assert_eq!(parse_item(&to_json_map(&item).to_string()).unwrap(), item);
+
+        assert_eq!(format_value(&item["pk"]), "user#1");
+        assert_eq!(format_value(&item["tags"]), r#"["a","b"]"#);
+        assert_eq!(format_value(&item["avatar"]), r#""aGk=""#);
+        assert_eq!(format_value(&item["address"]), r#"{"city":"Leeds","visits":[1,null]}"#);
+
+        assert!(parse_item(r#"{"pk": "user#1"}"#).is_err());
+        assert!(parse_item(r#"{"pk": {"S": "a", "N": "1"}}"#).is_err());
+    }
+
+    #[test]
+    fn keys() {
+        let schema = KeySchema { keys: vec![("pk".to_string(), "S".to_string()), ("sk".to_string(), "N".to_string())] };
+        let item = parse_item(r#"{"sk": {"N": "3"}, "pk": {"S": "user#1"}, "name": {"S": "Ada"}}"#).unwrap();
+        assert_eq!(schema.get_name(&item), "user#1 / 3");
+        assert_eq!(get_headers(&schema, std::slice::from_ref(&item)), vec!["pk", "sk", "name"]);
+
+        let id = serde_json::to_string(&to_json_map(&schema.get_key(&item).unwrap())).unwrap();
+        assert_eq!(id, r#"{"pk":{"S":"user#1"},"sk":{"N":"3"}}"#);
+        assert_eq!(parse_id(&id).unwrap().len(), 2);
+        assert!(schema.get_key(&parse_item(r#"{"pk": {"S": "a"}}"#).unwrap()).is_err());
+    }
+}
@@ -1,13 +1,20 @@
-use std::any::Any;
-use crate::cloud::aws::{AwsProvider, AwsService};
+use crate::cloud::aws::AwsProvider;
 use crate::service;
 use crate::cloud::aws;
-use crate::service::resource::{resources, Resource, ResourceDescription, ResourceKind, ResourceType};
+use std::collections::HashMap;
+use crate::service::registry::ServiceType;
+use crate::service::resource::{is_confirmed, props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
 use async_trait::async_trait;
-use crate::service::Service;
+use serde::Deserialize;
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::cloud::aws::cloudwatch;
+use crate::service::metrics::{Metric, Period, Series, Unit};
+
+const SERVICE: &str = "Ec2";
 
 pub struct Ec2 {
-    client: Option<aws_sdk_ec2::Client>,
     provider: AwsProvider,
 }
 
@@ -15,30 +22,37 @@ pub struct Ec2 {
 #[async_trait]
 impl <'a> service::Service<'a> for Ec2 {
     type Provider = AwsProvider;
-    type Resources = Resources;
 
-    fn new() -> Ec2 {
+    fn new(context: &Context) -> Ec2 {
         Self {
-            client: None,
-            provider: AwsProvider::new(),
+            provider: AwsProvider::new(context),
         }
     }
 
-    async fn run(self)  -> anyhow::Result<()> {
-        todo!()
-    }
-
-    fn get_resources(&self) -> anyhow::Result<Vec<ResourceType>> {
-        Ok(vec![
-            ResourceType("Instances".to_string())
-        ])
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Instances", Instances::new)
+            .resource("Images", Images::new)
+            .resource("Vpcs", Vpcs::new)
+            .resource("Subnets", Subnets::new)
+            .resource("SecurityGroups", SecurityGroups::new)
+            .resource("Volumes", Volumes::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(|| {
+                aws::drop_clients::<aws_sdk_ec2::Client>();
+                aws::drop_clients::<aws_sdk_cloudwatch::Client>();
+            })
     }
 }
 
 #[async_trait]
 impl <'a> aws::AwsService<'a, aws_sdk_ec2::Client> for Ec2 {
-    async fn new_client() -> anyhow::Result<aws_sdk_ec2::Client> {
-        let config= Self::Provider::new().get_config().await;
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_ec2::Client> {
+        let config= self.provider.get_config().await;
         let client = aws_sdk_ec2::Client::new(&config);
         Ok(client)
     }
@@ -49,9 +63,9 @@ pub(crate) struct Instances {
 }
 
 impl Instances {
-    fn new() -> Self {
+    fn new(context: &Context) -> Self {
         Instances {
-            svc: Ec2::new()
+            svc: Ec2::new(context)
         }
     }
 }
@@ -59,36 +73,789 @@ impl Instances {
 #[async_trait]
 impl service::resource::ResourceController<Instance> for Instances {
     async fn list(&self) -> anyhow::Result<Vec<Instance>> {
-        let streams = self.svc.client.as_ref().unwrap().describe_instances().send().await?
+        let instances = self.svc.get_client().await?.describe_instances().send().await?
             .reservations
-            .unwrap_or(vec![])
-            .iter()
-            .map(|x| {
-                Instance {
-                    name: x.reservation_id.as_ref().unwrap().clone(),
-                }
-            })
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|x| x.instances.unwrap_or_default())
+            .map(Instance::from)
             .collect();
-        Ok(streams)
+        Ok(instances)
     }
 
     async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Instance>>> {
-        Ok(None)
+        let instance = self.svc.get_client().await?.describe_instances()
+            .instance_ids(&id)
+            .send().await?
+            .reservations
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|x| x.instances.unwrap_or_default())
+            .next()
+            .map(Instance::from);
+
+        Ok(instance.as_ref().map(ResourceDescription::from_resource))
+    }
+
+    async fn metrics(&self, id: String, period: Period) -> anyhow::Result<Vec<Series>> {
+        cloudwatch::get_series(&self.svc.provider, "AWS/EC2", ("InstanceId", &id), Instance::get_metrics(), period).await
     }
 }
 
+#[derive(Default, Deserialize)]
+#[serde(default)]
 pub(crate) struct Instance {
-    name: String,
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: Option<String>,
+    state: Option<String>,
+    instance_type: Option<String>,
+    image_id: Option<String>,
+    vpc_id: Option<String>,
+    subnet_id: Option<String>,
+    security_groups: Vec<String>,
+    volumes: Vec<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl From<aws_sdk_ec2::model::Instance> for Instance {
+    fn from(instance: aws_sdk_ec2::model::Instance) -> Self {
+        let details = [
+            ("Launched", instance.launch_time.map(|x| x.fmt(aws_smithy_types::date_time::Format::DateTime).unwrap_or_default())),
+            ("Availability zone", instance.placement.and_then(|x| x.availability_zone)),
+            ("Private IP", instance.private_ip_address),
+            ("Public IP", instance.public_ip_address),
+            ("Key pair", instance.key_name),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+
+        let tags = get_tags(instance.tags);
+        Instance {
+            id: instance.instance_id.unwrap_or_default(),
+            name: name_tag(&tags),
+            tags,
+            state: instance.state.and_then(|x| x.name).map(|x| x.as_str().to_string()),
+            instance_type: instance.instance_type.map(|x| x.as_str().to_string()),
+            image_id: instance.image_id,
+            vpc_id: instance.vpc_id,
+            subnet_id: instance.subnet_id,
+            security_groups: instance.security_groups
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| x.group_id)
+                .collect(),
+            volumes: instance.block_device_mappings
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| x.ebs.and_then(|ebs| ebs.volume_id))
+                .collect(),
+            details,
+        }
+    }
 }
 
 impl Resource for Instance {
     type Id = String;
 
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "State", "Type"]
+    }
+
+    fn get_metrics() -> Vec<Metric> {
+        vec![
+            Metric::new("CPUUtilization", "Average", Unit::Percent),
+            Metric::new("NetworkIn", "Sum", Unit::Bytes),
+            Metric::new("NetworkOut", "Sum", Unit::Bytes),
+            Metric::new("StatusCheckFailed", "Maximum", Unit::Count),
+        ]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone().unwrap_or_default(),
+            self.id.clone(),
+            self.state.clone().unwrap_or_default(),
+            self.instance_type.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Instance::get_headers(), self.get_columns());
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        let mut references = vec![];
+        if let Some(image) = &self.image_id {
+            references.push(Reference::new("Image", SERVICE, "Images", image));
+        }
+        if let Some(vpc) = &self.vpc_id {
+            references.push(Reference::new("VPC", SERVICE, "Vpcs", vpc));
+        }
+        if let Some(subnet) = &self.subnet_id {
+            references.push(Reference::new("Subnet", SERVICE, "Subnets", subnet));
+        }
+        references.extend(self.security_groups.iter()
+            .map(|group| Reference::new("Security group", SERVICE, "SecurityGroups", group)));
+        references.extend(self.volumes.iter()
+            .map(|volume| Reference::new("Volume", SERVICE, "Volumes", volume)));
+        references
+    }
+}
+
+/// Which AMIs the `Images` listing covers, mapped onto `describe_images` owner filters.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImageOwnership {
+    /// Images owned by the account (`owners = self`).
+    Owned,
+    /// Images other accounts have granted launch permission to (`executable_users = self`).
+    Shared,
+}
+
+impl ImageOwnership {
+    fn toggle(self) -> Self {
+        match self {
+            ImageOwnership::Owned => ImageOwnership::Shared,
+            ImageOwnership::Shared => ImageOwnership::Owned,
+        }
+    }
+}
+
+pub(crate) struct Images {
+    svc: Ec2,
+    ownership: ImageOwnership,
+}
+
+impl Images {
+    fn new(context: &Context) -> Self {
+        Images {
+            svc: Ec2::new(context),
+            ownership: ImageOwnership::Owned,
+        }
+    }
+
+    async fn get_image(&self, id: &str) -> anyhow::Result<aws_sdk_ec2::model::Image> {
+        self.svc.get_client().await?.describe_images()
+            .image_ids(id)
+            .send().await?
+            .images
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("image {} not found", id))
+    }
+
+    /// Deregister the image, optionally deleting the EBS snapshots that back it. Snapshot ids
+    /// have to be read before deregistering, as the image can no longer be described afterwards.
+    async fn deregister(&self, id: &str, delete_snapshots: bool) -> anyhow::Result<()> {
+        let client = self.svc.get_client().await?;
+        let snapshots: Vec<String> = if delete_snapshots {
+            self.get_image(id).await?
+                .block_device_mappings
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| x.ebs.and_then(|ebs| ebs.snapshot_id))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        client.deregister_image().image_id(id).send().await?;
+        for snapshot in snapshots {
+            client.delete_snapshot().snapshot_id(snapshot).send().await?;
+        }
+        Ok(())
+    }
+
+    /// The image id, provided the image can currently be launched.
+    async fn launch_id(&self, id: &str) -> anyhow::Result<String> {
+        let image = self.get_image(id).await?;
+        match image.state {
+            Some(aws_sdk_ec2::model::ImageState::Available) => Ok(id.to_string()),
+            state => anyhow::bail!(
+                "image {} is not available (state: {})",
+                id,
+                state.as_ref().map(|x| x.as_str()).unwrap_or("unknown"),
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Image> for Images {
+    async fn list(&self) -> anyhow::Result<Vec<Image>> {
+        let request = self.svc.get_client().await?.describe_images();
+        let request = match self.ownership {
+            ImageOwnership::Owned => request.owners("self"),
+            ImageOwnership::Shared => request.executable_users("self"),
+        };
+        let images = request.send().await?
+            .images
+            .unwrap_or_default()
+            .into_iter()
+            .map(Image::from)
+            .collect();
+        Ok(images)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Image>>> {
+        let image = Image::from(self.get_image(&id).await?);
+        Ok(Some(ResourceDescription::from_resource(&image)))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'o', name: "Toggle owned/shared" },
+            Action { key: 'd', name: "Deregister" },
+            Action { key: 'D', name: "Deregister and delete snapshots" },
+            Action { key: 'y', name: "Copy AMI ID" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        if key == 'o' {
+            self.ownership = self.ownership.toggle();
+            return Ok(ActionOutcome::Reload);
+        }
+
+        let id = id.ok_or_else(|| anyhow::anyhow!("no image selected"))?;
+        match key {
+            'd' => Ok(ActionOutcome::confirm(&format!("Deregister {}?", id))),
+            'D' => Ok(ActionOutcome::confirm(&format!("Deregister {} and delete its snapshots?", id))),
+            'y' => self.launch_id(&id).await.map(ActionOutcome::Copy),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let id = id.ok_or_else(|| anyhow::anyhow!("no image selected"))?;
+        match key {
+            'd' | 'D' if !is_confirmed(&input) => Ok(ActionOutcome::Status("Not deregistered".to_string())),
+            'd' => self.deregister(&id, false).await.map(|_| ActionOutcome::Reload),
+            'D' => self.deregister(&id, true).await.map(|_| ActionOutcome::Reload),
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Image {
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: Option<String>,
+    architecture: Option<String>,
+    creation_date: Option<String>,
+    root_device: Option<String>,
+    state: Option<String>,
+}
+
+impl From<aws_sdk_ec2::model::Image> for Image {
+    fn from(image: aws_sdk_ec2::model::Image) -> Self {
+        Image {
+            id: image.image_id.unwrap_or_default(),
+            tags: get_tags(image.tags),
+            name: image.name,
+            architecture: image.architecture.map(|x| x.as_str().to_string()),
+            creation_date: image.creation_date,
+            root_device: image.root_device_type.map(|x| x.as_str().to_string()),
+            state: image.state.map(|x| x.as_str().to_string()),
+        }
+    }
+}
+
+impl Resource for Image {
+    type Id = String;
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "Architecture", "Created", "Root device", "State"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone().unwrap_or_default(),
+            self.id.clone(),
+            self.architecture.clone().unwrap_or_default(),
+            self.creation_date.clone().unwrap_or_default(),
+            self.root_device.clone().unwrap_or_default(),
+            self.state.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+pub(crate) struct Vpcs {
+    svc: Ec2,
+}
+
+impl Vpcs {
+    fn new(context: &Context) -> Self {
+        Vpcs {
+            svc: Ec2::new(context)
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Vpc> for Vpcs {
+    async fn list(&self) -> anyhow::Result<Vec<Vpc>> {
+        let vpcs = self.svc.get_client().await?.describe_vpcs().send().await?
+            .vpcs
+            .unwrap_or_default()
+            .into_iter()
+            .map(Vpc::from)
+            .collect();
+        Ok(vpcs)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Vpc>>> {
+        let vpc = self.svc.get_client().await?.describe_vpcs()
+            .vpc_ids(&id)
+            .send().await?
+            .vpcs
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(Vpc::from);
+
+        Ok(vpc.as_ref().map(ResourceDescription::from_resource))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Vpc {
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: Option<String>,
+    cidr_block: Option<String>,
+    state: Option<String>,
+    is_default: bool,
+}
+
+impl From<aws_sdk_ec2::model::Vpc> for Vpc {
+    fn from(vpc: aws_sdk_ec2::model::Vpc) -> Self {
+        let tags = get_tags(vpc.tags);
+        Vpc {
+            id: vpc.vpc_id.unwrap_or_default(),
+            name: name_tag(&tags),
+            tags,
+            cidr_block: vpc.cidr_block,
+            state: vpc.state.map(|x| x.as_str().to_string()),
+            is_default: vpc.is_default.unwrap_or_default(),
+        }
+    }
+}
+
+impl Resource for Vpc {
+    type Id = String;
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "CIDR", "State", "Default"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone().unwrap_or_default(),
+            self.id.clone(),
+            self.cidr_block.clone().unwrap_or_default(),
+            self.state.clone().unwrap_or_default(),
+            self.is_default.to_string(),
+        ]
+    }
+}
+
+pub(crate) struct Subnets {
+    svc: Ec2,
+}
+
+impl Subnets {
+    fn new(context: &Context) -> Self {
+        Subnets {
+            svc: Ec2::new(context)
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Subnet> for Subnets {
+    async fn list(&self) -> anyhow::Result<Vec<Subnet>> {
+        let subnets = self.svc.get_client().await?.describe_subnets().send().await?
+            .subnets
+            .unwrap_or_default()
+            .into_iter()
+            .map(Subnet::from)
+            .collect();
+        Ok(subnets)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Subnet>>> {
+        let subnet = self.svc.get_client().await?.describe_subnets()
+            .subnet_ids(&id)
+            .send().await?
+            .subnets
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(Subnet::from);
+
+        Ok(subnet.as_ref().map(ResourceDescription::from_resource))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Subnet {
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: Option<String>,
+    vpc_id: Option<String>,
+    cidr_block: Option<String>,
+    availability_zone: Option<String>,
+    available_ips: Option<i32>,
+}
+
+impl From<aws_sdk_ec2::model::Subnet> for Subnet {
+    fn from(subnet: aws_sdk_ec2::model::Subnet) -> Self {
+        let tags = get_tags(subnet.tags);
+        Subnet {
+            id: subnet.subnet_id.unwrap_or_default(),
+            name: name_tag(&tags),
+            tags,
+            vpc_id: subnet.vpc_id,
+            cidr_block: subnet.cidr_block,
+            availability_zone: subnet.availability_zone,
+            available_ips: subnet.available_ip_address_count,
+        }
+    }
+}
+
+impl Resource for Subnet {
+    type Id = String;
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "VPC", "CIDR", "Availability zone", "Available IPs"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone().unwrap_or_default(),
+            self.id.clone(),
+            self.vpc_id.clone().unwrap_or_default(),
+            self.cidr_block.clone().unwrap_or_default(),
+            self.availability_zone.clone().unwrap_or_default(),
+            self.available_ips.map(|x| x.to_string()).unwrap_or_default(),
+        ]
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.vpc_id.iter()
+            .map(|vpc| Reference::new("VPC", SERVICE, "Vpcs", vpc))
+            .collect()
+    }
+}
+
+pub(crate) struct SecurityGroups {
+    svc: Ec2,
+}
+
+impl SecurityGroups {
+    fn new(context: &Context) -> Self {
+        SecurityGroups {
+            svc: Ec2::new(context)
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<SecurityGroup> for SecurityGroups {
+    async fn list(&self) -> anyhow::Result<Vec<SecurityGroup>> {
+        let groups = self.svc.get_client().await?.describe_security_groups().send().await?
+            .security_groups
+            .unwrap_or_default()
+            .into_iter()
+            .map(SecurityGroup::from)
+            .collect();
+        Ok(groups)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<SecurityGroup>>> {
+        let group = self.svc.get_client().await?.describe_security_groups()
+            .group_ids(&id)
+            .send().await?
+            .security_groups
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(SecurityGroup::from);
+
+        Ok(group.as_ref().map(ResourceDescription::from_resource))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct SecurityGroup {
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: String,
+    vpc_id: Option<String>,
+    description: Option<String>,
+}
+
+impl From<aws_sdk_ec2::model::SecurityGroup> for SecurityGroup {
+    fn from(group: aws_sdk_ec2::model::SecurityGroup) -> Self {
+        SecurityGroup {
+            id: group.group_id.unwrap_or_default(),
+            tags: get_tags(group.tags),
+            name: group.group_name.unwrap_or_default(),
+            vpc_id: group.vpc_id,
+            description: group.description,
+        }
+    }
+}
+
+impl Resource for SecurityGroup {
+    type Id = String;
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
     fn get_name(&self) -> String {
         self.name.clone()
     }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "VPC", "Description"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.id.clone(),
+            self.vpc_id.clone().unwrap_or_default(),
+            self.description.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.vpc_id.iter()
+            .map(|vpc| Reference::new("VPC", SERVICE, "Vpcs", vpc))
+            .collect()
+    }
+}
+
+pub(crate) struct Volumes {
+    svc: Ec2,
+}
+
+impl Volumes {
+    fn new(context: &Context) -> Self {
+        Volumes {
+            svc: Ec2::new(context)
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Volume> for Volumes {
+    async fn list(&self) -> anyhow::Result<Vec<Volume>> {
+        let volumes = self.svc.get_client().await?.describe_volumes().send().await?
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(Volume::from)
+            .collect();
+        Ok(volumes)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Volume>>> {
+        let volume = self.svc.get_client().await?.describe_volumes()
+            .volume_ids(&id)
+            .send().await?
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(Volume::from);
+
+        Ok(volume.as_ref().map(ResourceDescription::from_resource))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Volume {
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    name: Option<String>,
+    size: Option<i32>,
+    volume_type: Option<String>,
+    state: Option<String>,
+    instances: Vec<String>,
 }
 
-service::resource::resources! {
-    Instances, Instance,
-}
\ No newline at end of file
+impl From<aws_sdk_ec2::model::Volume> for Volume {
+    fn from(volume: aws_sdk_ec2::model::Volume) -> Self {
+        let tags = get_tags(volume.tags);
+        Volume {
+            id: volume.volume_id.unwrap_or_default(),
+            name: name_tag(&tags),
+            tags,
+            size: volume.size,
+            volume_type: volume.volume_type.map(|x| x.as_str().to_string()),
+            state: volume.state.map(|x| x.as_str().to_string()),
+            instances: volume.attachments
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|x| x.instance_id)
+                .collect(),
+        }
+    }
+}
+
+impl Resource for Volume {
+    type Id = String;
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "ID", "Size (GiB)", "Type", "State"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone().unwrap_or_default(),
+            self.id.clone(),
+            self.size.map(|x| x.to_string()).unwrap_or_default(),
+            self.volume_type.clone().unwrap_or_default(),
+            self.state.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.instances.iter()
+            .map(|instance| Reference::new("Attached to", SERVICE, "Instances", instance))
+            .collect()
+    }
+}
+
+/// The resource named by an EC2 ARN, e.g. `arn:aws:ec2:eu-west-1:123456789012:instance/i-0abc`.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "ec2" {
+        return None;
+    }
+    let reference = match arn.get_resource_path()? {
+        ("instance", id) => Reference::new("Instance", SERVICE, "Instances", id),
+        ("image", id) => Reference::new("Image", SERVICE, "Images", id),
+        ("vpc", id) => Reference::new("VPC", SERVICE, "Vpcs", id),
+        ("subnet", id) => Reference::new("Subnet", SERVICE, "Subnets", id),
+        ("security-group", id) => Reference::new("Security group", SERVICE, "SecurityGroups", id),
+        ("volume", id) => Reference::new("Volume", SERVICE, "Volumes", id),
+        _ => return None,
+    };
+    Some(reference)
+}
+
+fn get_tags(tags: Option<Vec<aws_sdk_ec2::model::Tag>>) -> Vec<(String, String)> {
+    tags.unwrap_or_default()
+        .into_iter()
+        .filter_map(|x| Some((x.key?, x.value.unwrap_or_default())))
+        .collect()
+}
+
+/// The value of the `Name` tag, which the console shows in place of the id.
+fn name_tag(tags: &[(String, String)]) -> Option<String> {
+    tags.iter()
+        .find(|(k, _)| k == "Name")
+        .map(|(_, v)| v.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        let instance: aws::Arn = "arn:aws:ec2:eu-west-1:123456789012:instance/i-0abc".parse().unwrap();
+        assert_eq!(get_reference(&instance), Some(Reference::new("Instance", SERVICE, "Instances", "i-0abc")));
+
+        // other services have instances and images of their own
+        let instance: aws::Arn = "arn:aws:connect:us-east-1:123456789012:instance/abc".parse().unwrap();
+        assert_eq!(get_reference(&instance), None);
+        let image: aws::Arn = "arn:aws:sagemaker:us-east-1:123456789012:image/x".parse().unwrap();
+        assert_eq!(get_reference(&image), None);
+    }
+}
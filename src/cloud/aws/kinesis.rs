@@ -1,14 +1,18 @@
-use std::any::Any;
-use std::marker::PhantomData;
-use crate::cloud::aws::{AwsProvider, AwsService};
+use crate::cloud::aws::AwsProvider;
 use crate::service;
 use crate::cloud::aws;
-use crate::service::resource::{Resource, ResourceDescription, ResourceKind, ResourceType};
+use crate::service::registry::ServiceType;
+use crate::service::resource::{Reference, Resource, ResourceDescription};
 use async_trait::async_trait;
-use crate::service::Service;
+use serde::Deserialize;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::cloud::aws::cloudwatch;
+use crate::service::metrics::{Metric, Period, Series, Unit};
+
+const SERVICE: &str = "Kinesis";
 
 pub(crate) struct Kinesis {
-    client: Option<aws_sdk_kinesis::Client>,
     provider: AwsProvider,
 }
 
@@ -17,30 +21,32 @@ pub(crate) struct Kinesis {
 impl <'a> service::Service<'a> for Kinesis
 {
     type Provider = AwsProvider;
-    type Resources = Resources;
 
-    fn new() -> Kinesis {
+    fn new(context: &Context) -> Kinesis {
         Self {
-            client: None,
-            provider: AwsProvider::new(),
+            provider: AwsProvider::new(context),
         }
     }
 
-    async fn run(self)  -> anyhow::Result<()> {
-        todo!()
-    }
-
-    fn get_resources(&self) -> anyhow::Result<Vec<ResourceType>> {
-        Ok(vec![
-            ResourceType("Stream".to_string())
-        ])
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Streams", Streams::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(|| {
+                aws::drop_clients::<aws_sdk_kinesis::Client>();
+                aws::drop_clients::<aws_sdk_cloudwatch::Client>();
+            })
     }
 }
 
 #[async_trait]
 impl <'a> aws::AwsService<'a, aws_sdk_kinesis::Client> for Kinesis {
-    async fn new_client() -> anyhow::Result<aws_sdk_kinesis::Client> {
-        let config= Self::Provider::new().get_config().await;
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_kinesis::Client> {
+        let config= self.provider.get_config().await;
         let client = aws_sdk_kinesis::Client::new(&config);
         Ok(client)
     }
@@ -51,9 +57,9 @@ pub(crate) struct Streams {
 }
 
 impl Streams {
-    fn new() -> Self {
+    fn new(context: &Context) -> Self {
         Self {
-            svc: Kinesis::new()
+            svc: Kinesis::new(context)
         }
     }
 }
@@ -61,7 +67,7 @@ impl Streams {
 #[async_trait]
 impl service::resource::ResourceController<Stream> for Streams {
     async fn list(&self) -> anyhow::Result<Vec<Stream>> {
-        let streams = self.svc.client.as_ref().unwrap().list_streams().send().await?
+        let streams = self.svc.get_client().await?.list_streams().send().await?
             .stream_names
             .unwrap_or(vec![])
             .iter()
@@ -75,21 +81,28 @@ impl service::resource::ResourceController<Stream> for Streams {
     }
 
     async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Stream>>> {
-        let description = self.svc.client.as_ref().unwrap().describe_stream()
+        let description = self.svc.get_client().await?.describe_stream()
             .stream_name(&id)
             .send().await?
             .stream_description
-            .map(|x| {
+            .map(|_x| {
                ResourceDescription {
                    id: id.clone(),
                    name: Some(id.clone()),
-                   props: Default::default()
+                   props: Default::default(),
+                   references: vec![],
                }
             });
         Ok(description)
     }
+
+    async fn metrics(&self, id: String, period: Period) -> anyhow::Result<Vec<Series>> {
+        cloudwatch::get_series(&self.svc.provider, "AWS/Kinesis", ("StreamName", &id), Stream::get_metrics(), period).await
+    }
 }
 
+#[derive(Default, Deserialize)]
+#[serde(default)]
 pub(crate) struct Stream {
     name: String,
 }
@@ -97,11 +110,46 @@ pub(crate) struct Stream {
 impl Resource for Stream {
     type Id = String;
 
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
     fn get_name(&self) -> String {
         self.name.clone()
     }
+
+    fn get_metrics() -> Vec<Metric> {
+        vec![
+            Metric::new("IncomingRecords", "Sum", Unit::Count),
+            Metric::new("IncomingBytes", "Sum", Unit::Bytes),
+            Metric::new("WriteProvisionedThroughputExceeded", "Sum", Unit::Count),
+            Metric::new("GetRecords.IteratorAgeMilliseconds", "Maximum", Unit::Milliseconds),
+        ]
+    }
 }
 
-service::resource::resources! {
-    Streams, Stream,
-}
\ No newline at end of file
+/// The resource named by a Kinesis ARN, e.g. `arn:aws:kinesis:eu-west-1:123456789012:stream/orders`.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "kinesis" {
+        return None;
+    }
+    match arn.get_resource_path()? {
+        ("stream", name) => Some(Reference::new("Stream", SERVICE, "Streams", name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        let stream: aws::Arn = "arn:aws:kinesis:eu-west-1:123456789012:stream/orders".parse().unwrap();
+        assert_eq!(get_reference(&stream), Some(Reference::new("Stream", SERVICE, "Streams", "orders")));
+
+        // video streams belong to a service of their own
+        let stream: aws::Arn = "arn:aws:kinesisvideo:eu-west-1:123456789012:stream/cam/123".parse().unwrap();
+        assert_eq!(get_reference(&stream), None);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::{BTreeMap, HashMap};
+use aws_sdk_lambda::model::{AliasConfiguration, EventSourceMappingConfiguration, FunctionConfiguration, InvocationType, LogType};
+use serde_json::Value;
+use crate::service::registry::ServiceType;
+use crate::service::resource::{props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::transfer::format_size;
+
+const SERVICE: &str = "Lambda";
+
+/// What environment variables show as until revealed.
+const MASK: &str = "********";
+
+pub(crate) struct Lambda {
+    provider: AwsProvider,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for Lambda {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> Lambda {
+        Self {
+            provider: AwsProvider::new(context),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Functions", Functions::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_lambda::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_lambda::Client> for Lambda {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_lambda::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_lambda::Client::new(&config);
+        Ok(client)
+    }
+}
+
+pub(crate) struct Functions {
+    svc: Lambda,
+    /// Whether environment variables show their values, rather than `MASK`.
+    reveal: bool,
+    /// The payload each function was last invoked with, to start from next time.
+    payloads: HashMap<String, String>,
+}
+
+impl Functions {
+    fn new(context: &Context) -> Self {
+        Functions {
+            svc: Lambda::new(context),
+            reveal: false,
+            payloads: HashMap::new(),
+        }
+    }
+
+    async fn list_aliases(&self, name: &str) -> anyhow::Result<Vec<AliasConfiguration>> {
+        let client = self.svc.get_client().await?;
+        let mut aliases = vec![];
+        let mut marker = None;
+        loop {
+            let output = client.list_aliases().function_name(name).set_marker(marker).send().await?;
+            aliases.extend(output.aliases.unwrap_or_default());
+            marker = output.next_marker;
+            if marker.is_none() {
+                return Ok(aliases);
+            }
+        }
+    }
+
+    async fn list_versions(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        let client = self.svc.get_client().await?;
+        let mut versions = vec![];
+        let mut marker = None;
+        loop {
+            let output = client.list_versions_by_function().function_name(name).set_marker(marker).send().await?;
+            versions.extend(output.versions.unwrap_or_default().into_iter().filter_map(|x| x.version));
+            marker = output.next_marker;
+            if marker.is_none() {
+                return Ok(versions);
+            }
+        }
+    }
+
+    async fn list_mappings(&self, name: &str) -> anyhow::Result<Vec<EventSourceMappingConfiguration>> {
+        let client = self.svc.get_client().await?;
+        let mut mappings = vec![];
+        let mut marker = None;
+        loop {
+            let output = client.list_event_source_mappings().function_name(name).set_marker(marker).send().await?;
+            mappings.extend(output.event_source_mappings.unwrap_or_default());
+            marker = output.next_marker;
+            if marker.is_none() {
+                return Ok(mappings);
+            }
+        }
+    }
+
+    async fn invoke(&mut self, name: &str, kind: InvocationType, payload: String) -> anyhow::Result<ActionOutcome> {
+        serde_json::from_str::<Value>(&payload).map_err(|x| anyhow::anyhow!("the payload isn't JSON: {}", x))?;
+        self.payloads.insert(name.to_string(), payload.clone());
+        let sync = kind == InvocationType::RequestResponse;
+        let output = self.svc.get_client().await?.invoke()
+            .function_name(name)
+            .invocation_type(kind)
+            .set_log_type(sync.then_some(LogType::Tail))
+            .payload(aws_smithy_types::Blob::new(payload))
+            .send().await?;
+        if !sync {
+            return Ok(ActionOutcome::Status(format!("Queued an invocation of {} ({})", name, output.status_code)));
+        }
+        let log = output.log_result
+            .and_then(|x| base64::decode(x).ok())
+            .map(|x| String::from_utf8_lossy(&x).into_owned());
+        let response = output.payload.map(|x| x.into_inner()).unwrap_or_default();
+        let text = format_invocation(output.status_code, output.function_error.as_deref(), &response, log.as_deref());
+        Ok(ActionOutcome::Show { title: format!("{} ({})", name, output.status_code), text })
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Function> for Functions {
+    async fn list(&self) -> anyhow::Result<Vec<Function>> {
+        let client = self.svc.get_client().await?;
+        let mut functions = vec![];
+        let mut marker = None;
+        loop {
+            let output = client.list_functions().set_marker(marker).send().await?;
+            functions.extend(output.functions.unwrap_or_default().into_iter().map(Function::from));
+            marker = output.next_marker;
+            if marker.is_none() {
+                return Ok(functions);
+            }
+        }
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Function>>> {
+        let configuration = async {
+            let output = self.svc.get_client().await?.get_function().function_name(&id).send().await?;
+            anyhow::Ok(output.configuration)
+        };
+        let (configuration, aliases, versions, mappings) = futures::try_join!(
+            configuration,
+            self.list_aliases(&id),
+            self.list_versions(&id),
+            self.list_mappings(&id),
+        )?;
+        let configuration = match configuration {
+            Some(configuration) => configuration,
+            None => return Ok(None),
+        };
+        let mut function = Function::from(configuration);
+        function.reveal = self.reveal;
+        function.add_aliases(&aliases, &versions);
+        function.add_mappings(mappings);
+        Ok(Some(ResourceDescription::from_resource(&function)))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'r', name: "Invoke" },
+            Action { key: 'R', name: "Invoke asynchronously" },
+            Action { key: 'v', name: if self.reveal { "Mask environment" } else { "Reveal environment" } },
+            Action { key: 'y', name: "Copy ARN" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let name = id.ok_or_else(|| anyhow::anyhow!("no function selected"))?;
+        match key {
+            'r' | 'R' => {
+                let payload = self.payloads.get(&name).cloned().unwrap_or_else(|| "{}\n".to_string());
+                Ok(ActionOutcome::Compose { name: "payload.json".to_string(), text: payload })
+            }
+            'v' => {
+                self.reveal = !self.reveal;
+                Ok(ActionOutcome::Reload)
+            }
+            'y' => {
+                let arn = self.svc.get_client().await?.get_function()
+                    .function_name(&name)
+                    .send().await?
+                    .configuration
+                    .and_then(|x| x.function_arn)
+                    .unwrap_or_default();
+                Ok(ActionOutcome::Copy(arn))
+            }
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let name = id.ok_or_else(|| anyhow::anyhow!("no function selected"))?;
+        match key {
+            'r' => self.invoke(&name, InvocationType::RequestResponse, input).await,
+            'R' => self.invoke(&name, InvocationType::Event, input).await,
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Function {
+    name: String,
+    runtime: String,
+    memory: Option<i32>,
+    timeout: Option<i32>,
+    last_modified: String,
+    code_size: u64,
+    environment: BTreeMap<String, String>,
+    reveal: bool,
+    /// ARNs of the streams, queues and tables the function is mapped to.
+    sources: Vec<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl From<FunctionConfiguration> for Function {
+    fn from(function: FunctionConfiguration) -> Self {
+        let layers = function.layers.unwrap_or_default()
+            .into_iter()
+            .map(|x| {
+                let arn = x.arn.unwrap_or_default();
+                // arn:aws:lambda:eu-west-1:123456789012:layer:shared:3, shown as shared:3
+                let name = arn.splitn(7, ':').last().unwrap_or_default().to_string();
+                (format!("Layer {}", name), format_size(x.code_size.max(0) as u64))
+            });
+        let details = [
+            ("ARN", function.function_arn),
+            ("Description", function.description.filter(|x| !x.is_empty())),
+            ("Handler", function.handler),
+            ("Role", function.role),
+            ("Package", function.package_type.map(|x| x.as_str().to_string())),
+            ("State", function.state.map(|x| x.as_str().to_string())),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .chain(layers)
+            .collect();
+
+        Function {
+            name: function.function_name.unwrap_or_default(),
+            runtime: function.runtime.map(|x| x.as_str().to_string()).unwrap_or_default(),
+            memory: function.memory_size,
+            timeout: function.timeout,
+            last_modified: function.last_modified.as_deref().map(format_modified).unwrap_or_default(),
+            code_size: function.code_size.max(0) as u64,
+            environment: function.environment
+                .and_then(|x| x.variables)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            reveal: false,
+            sources: vec![],
+            details,
+        }
+    }
+}
+
+impl Function {
+    /// Each alias with the version it points at, and any share of traffic routed to another,
+    /// then every published version.
+    fn add_aliases(&mut self, aliases: &[AliasConfiguration], versions: &[String]) {
+        for alias in aliases {
+            let mut target = alias.function_version.clone().unwrap_or_default();
+            let weights = alias.routing_config.as_ref()
+                .and_then(|x| x.additional_version_weights.as_ref())
+                .map(|x| x.iter().collect::<BTreeMap<_, _>>())
+                .unwrap_or_default();
+            for (version, weight) in weights {
+                target.push_str(&format!(", {:.0}% to {}", weight * 100.0, version));
+            }
+            self.details.push((format!("Alias {}", alias.name.as_deref().unwrap_or_default()), target));
+        }
+        if !versions.is_empty() {
+            self.details.push(("Versions".to_string(), versions.join(", ")));
+        }
+    }
+
+    fn add_mappings(&mut self, mappings: Vec<EventSourceMappingConfiguration>) {
+        for mapping in mappings {
+            let source = match mapping.event_source_arn {
+                Some(arn) => arn,
+                None => continue,
+            };
+            let mut summary = vec![mapping.state.unwrap_or_default()];
+            summary.extend(mapping.batch_size.map(|x| format!("batch {}", x)));
+            summary.extend(mapping.starting_position.map(|x| format!("from {}", x.as_str())));
+            summary.extend(mapping.last_processing_result.map(|x| format!("last {}", x)));
+            self.details.push((format!("Trigger {}", get_source_name(&source)), summary.join(", ")));
+            self.sources.push(source);
+        }
+    }
+}
+
+impl Resource for Function {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Runtime", "Memory", "Timeout", "Last modified", "Code size"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.runtime.clone(),
+            self.memory.map(|x| format!("{} MB", x)).unwrap_or_default(),
+            self.timeout.map(|x| format!("{}s", x)).unwrap_or_default(),
+            self.last_modified.clone(),
+            format_size(self.code_size),
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Function::get_headers(), self.get_columns());
+        props.extend(self.details.clone());
+        for (name, value) in &self.environment {
+            let value = if self.reveal { value.clone() } else { MASK.to_string() };
+            props.insert(format!("Env {}", name), value);
+        }
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.sources.iter()
+            .filter_map(|x| x.parse::<aws::Arn>().ok())
+            .filter_map(|x| get_source_reference(&x))
+            .map(|x| Reference { label: "Event source".to_string(), ..x })
+            .collect()
+    }
+}
+
+/// The stream, queue or table behind an event source mapping. A DynamoDB stream's ARN leads
+/// to its table.
+fn get_source_reference(arn: &aws::Arn) -> Option<Reference> {
+    match arn.service.as_str() {
+        "kinesis" => aws::kinesis::get_reference(arn),
+        "sqs" => aws::sqs::get_reference(arn),
+        "dynamodb" => aws::dynamodb::get_reference(arn),
+        _ => None,
+    }
+}
+
+/// The stream, queue or table's name, from its ARN: `arn:aws:kinesis:...:stream/orders`,
+/// `arn:aws:sqs:...:orders` or `arn:aws:dynamodb:...:table/orders/stream/2021-10-04T08:12:51.107`.
+fn get_source_name(arn: &str) -> &str {
+    let resource = arn.splitn(6, ':').last().unwrap_or(arn);
+    match resource.split_once('/') {
+        Some((_, path)) => path.split('/').next().unwrap_or(path),
+        None => resource,
+    }
+}
+
+/// What a synchronous invocation returned: the status, the error if the function failed, the
+/// response, laid out if it's JSON, and the tail of the log.
+fn format_invocation(status: i32, error: Option<&str>, response: &[u8], log: Option<&str>) -> String {
+    let mut lines = vec![format!("Status: {}", status)];
+    lines.extend(error.map(|x| format!("Error: {}", x)));
+    let response = String::from_utf8_lossy(response);
+    let response = serde_json::from_str::<Value>(&response)
+        .ok()
+        .and_then(|x| serde_json::to_string_pretty(&x).ok())
+        .unwrap_or_else(|| response.into_owned());
+    lines.extend(["".to_string(), "Response".to_string(), response]);
+    if let Some(log) = log {
+        lines.extend(["".to_string(), "Log".to_string(), log.trim_end().to_string()]);
+    }
+    lines.join("\n")
+}
+
+/// Lambda's `2021-11-05T09:30:00.000+0000` as `2021-11-05T09:30:00Z`.
+fn format_modified(date: &str) -> String {
+    chrono::DateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .map(|x| x.with_timezone(&chrono::Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// The function named by a Lambda ARN, `arn:aws:lambda:eu-west-1:123456789012:function:orders`,
+/// with or without a version or alias after it.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "lambda" {
+        return None;
+    }
+    match arn.get_resource_path()? {
+        ("function", path) => {
+            let name = path.split(':').next()?;
+            Some(Reference::new("Function", SERVICE, "Functions", name))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_lambda::model::{AliasRoutingConfiguration, EnvironmentResponse, Runtime};
+
+    #[test]
+    fn functions() {
+        let configuration = FunctionConfiguration::builder()
+            .function_name("orders")
+            .runtime(Runtime::Python39)
+            .memory_size(256)
+            .timeout(30)
+            .last_modified("2021-11-05T10:30:00.000+0100")
+            .code_size(1536)
+            .environment(EnvironmentResponse::builder().variables("TABLE", "orders").build())
+            .build();
+        let mut function = Function::from(configuration);
+        assert_eq!(function.get_columns(), vec!["orders", "python3.9", "256 MB", "30s", "2021-11-05T09:30:00Z", "1.5 KiB"]);
+        assert_eq!(function.get_props()["Env TABLE"], MASK);
+        function.reveal = true;
+        assert_eq!(function.get_props()["Env TABLE"], "orders");
+
+        let alias = AliasConfiguration::builder()
+            .name("live")
+            .function_version("3")
+            .routing_config(AliasRoutingConfiguration::builder().additional_version_weights("4", 0.1).build())
+            .build();
+        function.add_aliases(&[alias], &["$LATEST".to_string(), "3".to_string(), "4".to_string()]);
+        function.add_mappings(vec![EventSourceMappingConfiguration::builder()
+            .event_source_arn("arn:aws:dynamodb:eu-west-1:123456789012:table/orders/stream/2021-10-04T08:12:51.107")
+            .state("Enabled")
+            .batch_size(100)
+            .build()]);
+        let props = function.get_props();
+        assert_eq!(props["Alias live"], "3, 10% to 4");
+        assert_eq!(props["Versions"], "$LATEST, 3, 4");
+        assert_eq!(props["Trigger orders"], "Enabled, batch 100");
+        assert_eq!(function.get_references(), vec![Reference::new("Event source", "DynamoDB", "Tables", "orders")]);
+    }
+
+    #[test]
+    fn invocations() {
+        let text = format_invocation(200, Some("Unhandled"), br#"{"errorMessage":"boom"}"#, Some("START\nEND\n"));
+        assert_eq!(text, "Status: 200\nError: Unhandled\n\nResponse\n{\n  \"errorMessage\": \"boom\"\n}\n\nLog\nSTART\nEND");
+    }
+}
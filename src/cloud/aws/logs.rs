@@ -0,0 +1,815 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::Context as _;
+use aws_sdk_cloudwatchlogs::model::{FilteredLogEvent, LogGroup, LogStream, OrderBy, QueryStatus};
+use chrono::TimeZone;
+use crate::service::registry::ServiceType;
+use crate::service::resource::{props, Action, ActionOutcome, LogEvent, Progress, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::config;
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::transfer::format_size;
+
+const SERVICE: &str = "Logs";
+
+/// How far back a tail starts, in milliseconds.
+const TAIL_WINDOW: i64 = 10 * 60 * 1000;
+
+/// The most events read by a tail at once. It carries on from the last of them next time.
+const MAX_EVENTS: usize = 10_000;
+
+/// What a Logs Insights listing starts with, until another query is written.
+const DEFAULT_QUERY: &str = "fields @timestamp, @message\n| sort @timestamp desc\n| limit 100";
+
+/// How far back queries look, until another range is picked.
+const DEFAULT_RANGE: &str = "1h";
+
+/// What queries are edited as.
+const QUERY_FILE: &str = "query.txt";
+
+/// Where saved queries are kept, under the config directory.
+const QUERIES_FILE: &str = "queries.json";
+
+/// The field Logs Insights points back at each event in the results with.
+const POINTER_FIELD: &str = "@ptr";
+
+/// Streams listed for a group, the most recently written first. Busy groups have many more
+/// that have long gone quiet.
+const MAX_STREAMS: usize = 500;
+
+pub(crate) struct Logs {
+    provider: AwsProvider,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for Logs {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> Logs {
+        Self {
+            provider: AwsProvider::new(context),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Groups", Groups::new)
+            .resource("Streams", Streams::new)
+            .resource("Insights", Insights::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_cloudwatchlogs::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_cloudwatchlogs::Client> for Logs {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_cloudwatchlogs::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_cloudwatchlogs::Client::new(&config);
+        Ok(client)
+    }
+}
+
+impl Logs {
+    /// Events in `group`, or just its `stream`, matching `pattern`, from `since` on, or from
+    /// `TAIL_WINDOW` ago if not given.
+    async fn tail(&self, group: &str, stream: Option<&str>, pattern: &str, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        let client = self.get_client().await?;
+        let start = since.unwrap_or_else(|| chrono::Utc::now().timestamp_millis() - TAIL_WINDOW);
+        let mut events = vec![];
+        let mut token = None;
+        loop {
+            let output = client.filter_log_events()
+                .log_group_name(group)
+                .set_log_stream_names(stream.map(|x| vec![x.to_string()]))
+                .set_filter_pattern(Some(pattern.to_string()).filter(|x| !x.is_empty()))
+                .start_time(start)
+                .set_next_token(token)
+                .send().await?;
+            events.extend(output.events.unwrap_or_default().into_iter().map(to_log_event));
+            token = output.next_token;
+            if token.is_none() || events.len() >= MAX_EVENTS {
+                break;
+            }
+        }
+        events.sort_by_key(|x| x.timestamp);
+        Ok(events)
+    }
+}
+
+fn to_log_event(event: FilteredLogEvent) -> LogEvent {
+    LogEvent {
+        id: event.event_id.unwrap_or_default(),
+        timestamp: event.timestamp.unwrap_or_default(),
+        source: event.log_stream_name.unwrap_or_default(),
+        message: event.message.unwrap_or_default(),
+    }
+}
+
+pub(crate) struct Groups {
+    svc: Logs,
+}
+
+impl Groups {
+    fn new(context: &Context) -> Self {
+        Groups {
+            svc: Logs::new(context)
+        }
+    }
+
+    async fn list_groups(&self, prefix: Option<&str>) -> anyhow::Result<Vec<Group>> {
+        let client = self.svc.get_client().await?;
+        let mut groups = vec![];
+        let mut token = None;
+        loop {
+            let output = client.describe_log_groups()
+                .set_log_group_name_prefix(prefix.map(|x| x.to_string()))
+                .set_next_token(token)
+                .send().await?;
+            groups.extend(output.log_groups.unwrap_or_default().into_iter().map(Group::from));
+            token = output.next_token;
+            if token.is_none() {
+                return Ok(groups);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Group> for Groups {
+    async fn list(&self) -> anyhow::Result<Vec<Group>> {
+        self.list_groups(None).await
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Group>>> {
+        Ok(self.list_groups(Some(&id)).await?
+            .iter()
+            .find(|x| x.name == id)
+            .map(ResourceDescription::from_resource))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'T', name: "Tail" },
+            Action { key: 'y', name: "Copy name" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let name = id.ok_or_else(|| anyhow::anyhow!("no log group selected"))?;
+        match key {
+            'T' => Ok(ActionOutcome::Tail { title: name.clone(), id: name }),
+            'y' => Ok(ActionOutcome::Copy(name)),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn tail(&self, id: String, pattern: String, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        self.svc.tail(&id, None, &pattern, since).await
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Group {
+    name: String,
+    /// Days events are kept for, or forever if not set.
+    retention: Option<i32>,
+    stored: u64,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl From<LogGroup> for Group {
+    fn from(group: LogGroup) -> Self {
+        let details = [
+            ("ARN", group.arn),
+            ("Created", group.creation_time.and_then(format_timestamp)),
+            ("Metric filters", group.metric_filter_count.map(|x| x.to_string())),
+            ("KMS key", group.kms_key_id),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+
+        Group {
+            name: group.log_group_name.unwrap_or_default(),
+            retention: group.retention_in_days,
+            stored: group.stored_bytes.unwrap_or_default().max(0) as u64,
+            details,
+        }
+    }
+}
+
+impl Resource for Group {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Retention", "Stored"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        let retention = match self.retention {
+            Some(1) => "1 day".to_string(),
+            Some(days) => format!("{} days", days),
+            None => "Never expire".to_string(),
+        };
+        vec![self.name.clone(), retention, format_size(self.stored)]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Group::get_headers(), self.get_columns());
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        let mut references: Vec<Reference> = self.get_contents().into_iter().collect();
+        references.push(Reference::listing("Insights", SERVICE, "Insights", self.name.clone()));
+        references
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        Some(Reference::listing("Streams", SERVICE, "Streams", self.name.clone()))
+    }
+}
+
+/// The streams of a log group, which they must be scoped to. They're known by the group's
+/// name and theirs, joined by `:`, which group names can't hold.
+pub(crate) struct Streams {
+    svc: Logs,
+    group: Option<String>,
+}
+
+impl Streams {
+    fn new(context: &Context) -> Self {
+        Streams {
+            svc: Logs::new(context),
+            group: None,
+        }
+    }
+
+    fn get_group(&self) -> anyhow::Result<&str> {
+        self.group.as_deref().ok_or_else(|| anyhow::anyhow!("log streams are listed from their group"))
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Stream> for Streams {
+    async fn list(&self) -> anyhow::Result<Vec<Stream>> {
+        let group = self.get_group()?;
+        let client = self.svc.get_client().await?;
+        let mut streams = vec![];
+        let mut token = None;
+        loop {
+            let output = client.describe_log_streams()
+                .log_group_name(group)
+                .order_by(OrderBy::LastEventTime)
+                .descending(true)
+                .set_next_token(token)
+                .send().await?;
+            streams.extend(output.log_streams.unwrap_or_default().into_iter().map(|x| Stream::new(group, x)));
+            token = output.next_token;
+            if token.is_none() || streams.len() >= MAX_STREAMS {
+                return Ok(streams);
+            }
+        }
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Stream>>> {
+        let (group, name) = split_id(&id)?;
+        let stream = self.svc.get_client().await?.describe_log_streams()
+            .log_group_name(group)
+            .log_stream_name_prefix(name)
+            .send().await?
+            .log_streams
+            .unwrap_or_default()
+            .into_iter()
+            .find(|x| x.log_stream_name.as_deref() == Some(name))
+            .map(|x| Stream::new(group, x));
+        Ok(stream.as_ref().map(ResourceDescription::from_resource))
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.group = Some(scope);
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'T', name: "Tail" },
+            Action { key: 'y', name: "Copy name" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let id = id.ok_or_else(|| anyhow::anyhow!("no log stream selected"))?;
+        let (_, name) = split_id(&id)?;
+        match key {
+            'T' => Ok(ActionOutcome::Tail { title: name.to_string(), id }),
+            'y' => Ok(ActionOutcome::Copy(name.to_string())),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn tail(&self, id: String, pattern: String, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        let (group, name) = split_id(&id)?;
+        self.svc.tail(group, Some(name), &pattern, since).await
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Stream {
+    /// The group's name and the stream's, as in `/aws/lambda/orders:2021/11/05/[$LATEST]9f1c`.
+    id: String,
+    last_event: Option<String>,
+    first_event: Option<String>,
+    created: Option<String>,
+}
+
+impl Stream {
+    fn new(group: &str, stream: LogStream) -> Self {
+        Stream {
+            id: format!("{}:{}", group, stream.log_stream_name.unwrap_or_default()),
+            last_event: stream.last_event_timestamp.and_then(format_timestamp),
+            first_event: stream.first_event_timestamp.and_then(format_timestamp),
+            created: stream.creation_time.and_then(format_timestamp),
+        }
+    }
+}
+
+impl Resource for Stream {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        split_id(&self.id).map(|(_, name)| name.to_string()).unwrap_or_default()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Last event", "First event", "Created"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.get_name(),
+            self.last_event.clone().unwrap_or_default(),
+            self.first_event.clone().unwrap_or_default(),
+            self.created.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        split_id(&self.id)
+            .map(|(group, _)| Reference::new("Group", SERVICE, "Groups", group))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Logs Insights queries against log groups, and their results. The groups come from the scope,
+/// joined by commas, which group names can't hold. Nothing runs until a query is written, or
+/// run, and the listing is fetched again every `PENDING_INTERVAL` until it completes.
+pub(crate) struct Insights {
+    svc: Logs,
+    groups: Vec<String>,
+    query: String,
+    /// How far back the query looks, as `parse_range` reads it.
+    range: String,
+    /// The query last started, which the listing shows the results of.
+    query_id: Option<String>,
+    /// Where saved queries are kept, if anywhere.
+    saved: Option<PathBuf>,
+    progress: Mutex<Option<Progress>>,
+    headers: Mutex<Vec<String>>,
+    /// The last results, for describing those without a `@ptr` to read them again by.
+    records: Mutex<Vec<Record>>,
+}
+
+impl Insights {
+    fn new(context: &Context) -> Self {
+        Insights {
+            svc: Logs::new(context),
+            groups: vec![],
+            query: DEFAULT_QUERY.to_string(),
+            range: DEFAULT_RANGE.to_string(),
+            query_id: None,
+            saved: config::get_dir().map(|x| x.join(QUERIES_FILE)),
+            progress: Mutex::new(None),
+            headers: Mutex::new(vec![]),
+            records: Mutex::new(vec![]),
+        }
+    }
+
+    /// Run the query over the range up to now, in place of any run before.
+    async fn start(&mut self) -> anyhow::Result<ActionOutcome> {
+        if self.groups.is_empty() {
+            anyhow::bail!("no log groups to query");
+        }
+        let end = chrono::Utc::now().timestamp();
+        let query_id = self.svc.get_client().await?.start_query()
+            .set_log_group_names(Some(self.groups.clone()))
+            .start_time(end - parse_range(&self.range)?)
+            .end_time(end)
+            .query_string(&self.query)
+            .send().await?
+            .query_id
+            .ok_or_else(|| anyhow::anyhow!("the query wasn't started"))?;
+        self.query_id = Some(query_id);
+        Ok(ActionOutcome::Reload)
+    }
+
+    fn get_saved(&self) -> anyhow::Result<(&PathBuf, SavedQueries)> {
+        let path = self.saved.as_ref().ok_or_else(|| anyhow::anyhow!("nowhere to keep saved queries; set HOME"))?;
+        Ok((path, SavedQueries::load(path)?))
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Record> for Insights {
+    async fn list(&self) -> anyhow::Result<Vec<Record>> {
+        let query_id = match &self.query_id {
+            Some(query_id) => query_id,
+            None => {
+                let note = "not run yet, r to run, e to edit the query".to_string();
+                *self.progress.lock().unwrap() = Some(Progress { note, pending: false });
+                return Ok(vec![]);
+            }
+        };
+        let output = self.svc.get_client().await?.get_query_results()
+            .query_id(query_id)
+            .send().await?;
+
+        let status = output.status.unwrap_or(QueryStatus::UnknownValue);
+        let mut note = status.as_str().to_string();
+        if let Some(statistics) = output.statistics {
+            note.push_str(&format!(
+                ", {} of {} records matched, {} scanned",
+                statistics.records_matched,
+                statistics.records_scanned,
+                format_size(statistics.bytes_scanned.max(0.0) as u64),
+            ));
+        }
+        let pending = matches!(status, QueryStatus::Scheduled | QueryStatus::Running);
+        *self.progress.lock().unwrap() = Some(Progress { note, pending });
+
+        let results: Vec<Vec<(String, String)>> = output.results.unwrap_or_default()
+            .into_iter()
+            .map(|x| x.into_iter()
+                .filter_map(|x| Some((x.field?, x.value.unwrap_or_default())))
+                .collect())
+            .collect();
+        let mut headers: Vec<String> = vec![];
+        for (field, _) in results.iter().flatten() {
+            if field != POINTER_FIELD && !headers.contains(field) {
+                headers.push(field.clone());
+            }
+        }
+        let records: Vec<Record> = results.into_iter()
+            .enumerate()
+            .map(|(i, fields)| Record::new(i, fields, &headers))
+            .collect();
+        *self.headers.lock().unwrap() = headers;
+        *self.records.lock().unwrap() = records.clone();
+        Ok(records)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Record>>> {
+        if id.starts_with('#') {
+            return Ok(self.records.lock().unwrap()
+                .iter()
+                .find(|x| x.id == id)
+                .map(ResourceDescription::from_resource));
+        }
+        let fields = self.svc.get_client().await?.get_log_record()
+            .log_record_pointer(&id)
+            .send().await?
+            .log_record
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect();
+        let record = Record { id, columns: vec![], fields };
+        Ok(Some(ResourceDescription::from_resource(&record)))
+    }
+
+    fn get_headers(&self) -> Vec<String> {
+        let headers = self.headers.lock().unwrap();
+        match headers.is_empty() {
+            true => Record::get_headers().into_iter().map(|x| x.to_string()).collect(),
+            false => headers.clone(),
+        }
+    }
+
+    fn get_progress(&self) -> Option<Progress> {
+        self.progress.lock().unwrap().clone()
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.groups = parse_groups(&scope);
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'e', name: "Edit and run the query" },
+            Action { key: 'T', name: "Time range" },
+            Action { key: 'l', name: "Log groups" },
+            Action { key: 'r', name: "Run" },
+            Action { key: 'x', name: "Stop" },
+            Action { key: 'S', name: "Save the query" },
+            Action { key: 'L', name: "Load a saved query" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, _id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        match key {
+            'e' => Ok(ActionOutcome::Compose { name: QUERY_FILE.to_string(), text: self.query.clone() }),
+            'T' => Ok(ActionOutcome::Ask { prompt: "Time range, e.g. 15m, 6h or 7d: ".to_string(), input: self.range.clone() }),
+            'l' => Ok(ActionOutcome::Ask { prompt: "Log groups, separated by commas: ".to_string(), input: self.groups.join(", ") }),
+            'r' => self.start().await,
+            'x' => {
+                let query_id = self.query_id.as_ref().ok_or_else(|| anyhow::anyhow!("no query has been run"))?;
+                self.svc.get_client().await?.stop_query().query_id(query_id).send().await?;
+                Ok(ActionOutcome::Reload)
+            }
+            'S' => Ok(ActionOutcome::Ask { prompt: "Save the query as: ".to_string(), input: String::new() }),
+            'L' => {
+                let (_, saved) = self.get_saved()?;
+                let names = saved.get_names(&self.groups);
+                if names.is_empty() {
+                    return Ok(ActionOutcome::Status("No queries saved for these log groups".to_string()));
+                }
+                Ok(ActionOutcome::Ask { prompt: format!("Load ({}): ", names.join(", ")), input: String::new() })
+            }
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, _id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let input = input.trim();
+        match key {
+            'e' => {
+                if input.is_empty() {
+                    return Ok(ActionOutcome::Status("The query is empty".to_string()));
+                }
+                self.query = input.to_string();
+            }
+            'T' => {
+                parse_range(input)?;
+                self.range = input.to_string();
+            }
+            'l' => self.groups = parse_groups(input),
+            'S' => {
+                if input.is_empty() {
+                    return Ok(ActionOutcome::Status("Not saved, as there was no name".to_string()));
+                }
+                let (path, mut saved) = self.get_saved()?;
+                saved.insert(&self.groups, input, &self.query);
+                saved.save(path)?;
+                return Ok(ActionOutcome::Status(format!("Saved the query as {}", input)));
+            }
+            'L' => {
+                let saved = self.get_saved()?.1;
+                self.query = saved.get(&self.groups, input)
+                    .ok_or_else(|| anyhow::anyhow!("no query saved as {}", input))?
+                    .to_string();
+            }
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+        match key {
+            // a new range or groups apply to the query running, if there is one
+            'T' | 'l' if self.query_id.is_none() => Ok(ActionOutcome::Status(format!("Querying {} over {}", self.groups.join(", "), self.range))),
+            _ => self.start().await,
+        }
+    }
+}
+
+/// A row of a query's results.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Record {
+    /// The `@ptr` Logs Insights gives each event in the results, which reads it again in full,
+    /// or its place in the results, as `#3`, for rows such as aggregates that have none.
+    id: String,
+    /// The values of the fields in the results, in the order of the listing's headers.
+    columns: Vec<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    fields: Vec<(String, String)>,
+}
+
+impl Record {
+    /// The `i`th row of the results, holding `fields`, laid out in columns under `headers`.
+    fn new(i: usize, fields: Vec<(String, String)>, headers: &[String]) -> Self {
+        let get = |name: &str| fields.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+        Record {
+            id: get(POINTER_FIELD).unwrap_or_else(|| format!("#{}", i + 1)),
+            columns: headers.iter().map(|x| get(x).unwrap_or_default()).collect(),
+            fields: fields.into_iter().filter(|(k, _)| k != POINTER_FIELD).collect(),
+        }
+    }
+}
+
+impl Resource for Record {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.columns.first().cloned().unwrap_or_else(|| self.id.clone())
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["@timestamp", "@message"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        self.fields.iter().cloned().collect()
+    }
+}
+
+/// Queries saved by name, for each log group they were saved against, as kept in
+/// `QUERIES_FILE`.
+#[derive(Default, Deserialize, Serialize)]
+struct SavedQueries(BTreeMap<String, BTreeMap<String, String>>);
+
+impl SavedQueries {
+    /// Those saved in the file at `path`, if it's been written yet.
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(SavedQueries::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Save `query` as `name` for each of `groups`, over any saved as that before.
+    fn insert(&mut self, groups: &[String], name: &str, query: &str) {
+        for group in groups {
+            self.0.entry(group.clone()).or_default().insert(name.to_string(), query.to_string());
+        }
+    }
+
+    /// The query saved as `name` for any of `groups`.
+    fn get(&self, groups: &[String], name: &str) -> Option<&str> {
+        groups.iter().find_map(|x| self.0.get(x)?.get(name)).map(|x| x.as_str())
+    }
+
+    /// Names of the queries saved for any of `groups`.
+    fn get_names(&self, groups: &[String]) -> Vec<&str> {
+        let names: BTreeSet<&str> = groups.iter()
+            .filter_map(|x| self.0.get(x))
+            .flat_map(|x| x.keys().map(|x| x.as_str()))
+            .collect();
+        names.into_iter().collect()
+    }
+}
+
+/// Log group names, separated by commas.
+fn parse_groups(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// A range in seconds, from a number and a unit: `m`inutes, `h`ours, `d`ays or `w`eeks.
+fn parse_range(text: &str) -> anyhow::Result<i64> {
+    let text = text.trim();
+    let unit = match text.chars().last() {
+        Some('m') => 60,
+        Some('h') => 3600,
+        Some('d') => 86400,
+        Some('w') => 7 * 86400,
+        _ => anyhow::bail!("{} isn't a time range; give a number of minutes, hours, days or weeks, e.g. 15m", text),
+    };
+    match text[..text.len() - 1].parse::<i64>() {
+        Ok(count) if count > 0 => Ok(count * unit),
+        _ => anyhow::bail!("{} isn't a time range; give a number of minutes, hours, days or weeks, e.g. 15m", text),
+    }
+}
+
+/// A stream's group and name, from its ID.
+fn split_id(id: &str) -> anyhow::Result<(&str, &str)> {
+    id.split_once(':').ok_or_else(|| anyhow::anyhow!("{} isn't a log stream", id))
+}
+
+/// Milliseconds since the epoch, as CloudWatch gives times, e.g. `2021-11-05T09:30:00Z`.
+fn format_timestamp(millis: i64) -> Option<String> {
+    Some(chrono::Utc.timestamp_millis_opt(millis).single()?.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+/// The group, or stream, named by a CloudWatch Logs ARN:
+/// `arn:aws:logs:eu-west-1:123456789012:log-group:/aws/lambda/orders:*`, or the same with
+/// `:log-stream:` and the stream's name in place of the `*`.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "logs" {
+        return None;
+    }
+    let rest = arn.resource.strip_prefix("log-group:")?;
+    match rest.split_once(":log-stream:") {
+        Some((group, stream)) => Some(Reference::new("Stream", SERVICE, "Streams", format!("{}:{}", group, stream))),
+        None => Some(Reference::new("Group", SERVICE, "Groups", rest.trim_end_matches(":*"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        let group: aws::Arn = "arn:aws:logs:eu-west-1:123456789012:log-group:/aws/lambda/orders:*".parse().unwrap();
+        assert_eq!(get_reference(&group), Some(Reference::new("Group", SERVICE, "Groups", "/aws/lambda/orders")));
+        let stream: aws::Arn = "arn:aws:logs:eu-west-1:123456789012:log-group:/aws/lambda/orders:log-stream:2021/11/05/[$LATEST]9f1c"
+            .parse()
+            .unwrap();
+        let reference = get_reference(&stream).unwrap();
+        assert_eq!(reference.id, "/aws/lambda/orders:2021/11/05/[$LATEST]9f1c");
+
+        let stream = Stream { id: reference.id, ..Default::default() };
+        assert_eq!(stream.get_name(), "2021/11/05/[$LATEST]9f1c");
+        assert_eq!(stream.get_references(), vec![Reference::new("Group", SERVICE, "Groups", "/aws/lambda/orders")]);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("15m").unwrap(), 900);
+        assert_eq!(parse_range(" 6h").unwrap(), 6 * 3600);
+        assert_eq!(parse_range("2w").unwrap(), 14 * 86400);
+        assert!(parse_range("0d").is_err());
+        assert!(parse_range("h").is_err());
+        assert!(parse_range("10").is_err());
+    }
+
+    #[test]
+    fn records() {
+        let fields = |x: &[(&str, &str)]| x.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let headers = vec!["@timestamp".to_string(), "@message".to_string()];
+        let record = Record::new(0, fields(&[("@timestamp", "2021-11-05 09:30:00.000"), ("@message", "started"), ("@ptr", "CmAK")]), &headers);
+        assert_eq!(record.get_id(), "CmAK");
+        assert_eq!(record.get_columns(), vec!["2021-11-05 09:30:00.000", "started"]);
+        assert!(!record.get_props().contains_key("@ptr"));
+
+        // aggregates have no pointer, nor every column
+        let record = Record::new(2, fields(&[("@message", "3")]), &headers);
+        assert_eq!(record.get_id(), "#3");
+        assert_eq!(record.get_columns(), vec!["", "3"]);
+    }
+
+    #[test]
+    fn saved_queries() {
+        let path = std::env::temp_dir().join(format!("cloud-ui-{}", std::process::id())).join(QUERIES_FILE);
+        let groups = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let mut saved = SavedQueries::load(&path).unwrap();
+        saved.insert(&groups(&["/ecs/web", "audit"]), "errors", "filter @message like /ERROR/");
+        saved.insert(&groups(&["audit"]), "logins", "filter event = 'login'");
+        saved.save(&path).unwrap();
+
+        let saved = SavedQueries::load(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(saved.get_names(&groups(&["/ecs/web"])), vec!["errors"]);
+        assert_eq!(saved.get_names(&groups(&["/ecs/web", "audit"])), vec!["errors", "logins"]);
+        assert_eq!(saved.get(&groups(&["/ecs/web"]), "errors"), Some("filter @message like /ERROR/"));
+        assert_eq!(saved.get(&groups(&["/ecs/web"]), "logins"), None);
+    }
+}
@@ -1,46 +1,230 @@
-use std::future::Future;
+use std::fmt;
+use std::str::FromStr;
 use crate::service;
 use async_trait::async_trait;
-use crate::service::Service;
+use crate::service::{Context, Service};
+use crate::service::registry::Registry;
+use crate::service::resource::Reference;
 use kinesis::Kinesis;
 use ec2::Ec2;
+use s3::S3;
+use dynamodb::DynamoDb;
+use sqs::Sqs;
+use sns::Sns;
+use lambda::Lambda;
+use logs::Logs;
+use iam::Iam;
 
-pub(crate) mod kinesis;
+pub mod kinesis;
 mod ec2;
+mod s3;
+mod dynamodb;
+mod sqs;
+mod sns;
+mod lambda;
+mod logs;
+mod iam;
+mod clients;
+mod cloudwatch;
 
-struct Arn(String);
+/// An Amazon Resource Name, `arn:partition:service:region:account:resource`. The resource part
+/// is kept whole, as each service lays it out differently (`stream/name`, `instance/i-...`, ...).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Arn {
+    pub partition: String,
+    pub service: String,
+    pub region: String,
+    pub account: String,
+    pub resource: String,
+}
 
-pub struct AwsProvider {
+impl Arn {
+    /// The resource type and id, for resources of the form `type/id` or `type:id`.
+    pub fn get_resource_path(&self) -> Option<(&str, &str)> {
+        let idx = self.resource.find(['/', ':'])?;
+        Some((&self.resource[..idx], &self.resource[idx + 1..]))
+    }
 
+    /// A context pointing at the region the resource lives in. Global resources, which have no
+    /// region, leave the context as it is.
+    pub fn get_context(&self, context: &Context) -> Context {
+        let mut context = context.clone();
+        if !self.region.is_empty() {
+            context.region = Some(self.region.clone());
+        }
+        context
+    }
+}
+
+impl FromStr for Arn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().splitn(6, ':').collect();
+        match parts.as_slice() {
+            ["arn", partition, service, region, account, resource]
+                if !partition.is_empty() && !service.is_empty() && !resource.is_empty() => {
+                Ok(Arn {
+                    partition: partition.to_string(),
+                    service: service.to_string(),
+                    region: region.to_string(),
+                    account: account.to_string(),
+                    resource: resource.to_string(),
+                })
+            }
+            _ => anyhow::bail!("invalid ARN: {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Arn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arn:{}:{}:{}:{}:{}", self.partition, self.service, self.region, self.account, self.resource)
+    }
+}
+
+/// Resolve names that are ARNs with `resolve`, for `ServiceType::references`.
+pub(crate) fn arns(resolve: fn(&Arn) -> Option<Reference>) -> impl Fn(&str) -> Option<Reference> + Send + Sync {
+    move |name| resolve(&name.parse().ok()?)
+}
+
+/// Regions offered when completing the `region` command.
+pub const REGIONS: &[&str] = &[
+    "af-south-1", "ap-east-1", "ap-northeast-1", "ap-northeast-2", "ap-northeast-3",
+    "ap-south-1", "ap-southeast-1", "ap-southeast-2", "ca-central-1", "eu-central-1",
+    "eu-north-1", "eu-south-1", "eu-west-1", "eu-west-2", "eu-west-3", "me-south-1",
+    "sa-east-1", "us-east-1", "us-east-2", "us-west-1", "us-west-2",
+];
+
+/// The shared config and credentials files, which the SDK reads profiles from.
+fn get_shared_files() -> [String; 2] {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let config = std::env::var("AWS_CONFIG_FILE")
+        .unwrap_or_else(|_| format!("{}/.aws/config", home));
+    let credentials = std::env::var("AWS_SHARED_CREDENTIALS_FILE")
+        .unwrap_or_else(|_| format!("{}/.aws/credentials", home));
+    [config, credentials]
+}
+
+/// Error codes, as they appear in SDK errors, with an explanation for each group.
+const EXPLANATIONS: &[(&[&str], &str)] = &[
+    (
+        &["AccessDenied", "UnauthorizedOperation", "AuthorizationError"],
+        "Access denied. The profile's credentials aren't allowed to do this; check the IAM \
+         policies attached to them, or switch profile with :ctx.",
+    ),
+    (
+        &["Throttling", "RequestLimitExceeded", "TooManyRequests", "SlowDown"],
+        "Throttled. AWS is limiting how fast this account can make requests; wait a moment and \
+         try again.",
+    ),
+    (
+        &["ExpiredToken", "RequestExpired", "InvalidClientTokenId", "UnrecognizedClient"],
+        "The credentials have expired or aren't valid. Log in again, e.g. with aws sso login, \
+         and they'll be picked up on the next request.",
+    ),
+    (
+        &["no providers in chain provided credentials", "credential provider was not enabled"],
+        "No credentials were found. Configure a profile in ~/.aws, or switch to one with :ctx.",
+    ),
+];
+
+/// A plain explanation for common AWS errors.
+pub fn explain_error(message: &str) -> Option<&'static str> {
+    let lowercase = message.to_lowercase();
+    EXPLANATIONS.iter()
+        .find(|(codes, _)| codes.iter().any(|x| lowercase.contains(&x.to_lowercase())))
+        .map(|(_, explanation)| *explanation)
+}
+
+/// Profile names declared in the shared config and credentials files.
+pub fn get_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = get_shared_files().iter()
+        .filter_map(|x| std::fs::read_to_string(x).ok())
+        .flat_map(|x| x.lines()
+            .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']').map(|x| x.to_string()))
+            .collect::<Vec<_>>())
+        // the config file prefixes all but the default profile
+        .map(|x| x.strip_prefix("profile ").map(|x| x.trim().to_string()).unwrap_or(x))
+        .collect();
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+pub struct AwsProvider {
+    context: Context,
 }
 
 #[async_trait]
 trait AwsService<'a, T>
-    where Self: Service<'a>
+    where Self: Service<'a> + Sync,
+          T: Clone + Send + 'static
 {
-    async fn new_client() -> anyhow::Result<T>;
-}
+    fn get_provider(&self) -> &AwsProvider;
 
-trait AwsResource {}
+    async fn new_client(&self) -> anyhow::Result<T>;
+
+    /// The client for the provider's context, built with `new_client` on first use and shared
+    /// from then on.
+    async fn get_client(&self) -> anyhow::Result<T> {
+        let context = &self.get_provider().context;
+        if let Some(client) = clients::get(context) {
+            return Ok(client);
+        }
+        let client = self.new_client().await?;
+        clients::insert(context, client.clone());
+        Ok(client)
+    }
+}
 
 impl AwsProvider {
-    fn new() -> Self {
-        AwsProvider {}
+    fn new(context: &Context) -> Self {
+        AwsProvider {
+            context: context.clone(),
+        }
     }
 
     async fn get_config(&self) -> aws_config::Config {
-        aws_config::load_from_env().await
+        let mut loader = aws_config::from_env();
+        if let Some(profile) = &self.context.profile {
+            let region = aws_config::default_provider::region::DefaultRegionChain::builder()
+                .profile_name(profile)
+                .build();
+            let credentials = aws_config::default_provider::credentials::DefaultCredentialsChain::builder()
+                .profile_name(profile)
+                .build().await;
+            loader = loader.region(region).credentials_provider(credentials);
+        }
+        if let Some(region) = &self.context.region {
+            loader = loader.region(aws_types::region::Region::new(region.clone()));
+        }
+        loader.load().await
     }
 }
 
-#[async_trait]
 impl service::Provider for AwsProvider {
-    async fn new() -> anyhow::Result<Self> {
-        Ok(AwsProvider {})
+    fn new(context: &Context) -> anyhow::Result<Self> {
+        Ok(AwsProvider::new(context))
     }
 }
 
-service::services! {
-    kinesis, Kinesis,
-    ec2, Ec2,
-}
\ No newline at end of file
+/// Drop the cached clients of type `T`, when shutting down.
+fn drop_clients<T>()
+    where T: 'static
+{
+    clients::clear::<T>()
+}
+
+/// Add the AWS services to `registry`.
+pub fn register(registry: &mut Registry) {
+    registry.register(Box::new(Kinesis::get_service_type()));
+    registry.register(Box::new(Ec2::get_service_type()));
+    registry.register(Box::new(S3::get_service_type()));
+    registry.register(Box::new(DynamoDb::get_service_type()));
+    registry.register(Box::new(Sqs::get_service_type()));
+    registry.register(Box::new(Sns::get_service_type()));
+    registry.register(Box::new(Lambda::get_service_type()));
+    registry.register(Box::new(Logs::get_service_type()));
+    registry.register(Box::new(Iam::get_service_type()));
+}
@@ -0,0 +1,1006 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use aws_sdk_s3::ByteStream;
+use futures::TryStreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::service::registry::ServiceType;
+use crate::service::resource::{props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::transfer::{format_size, Job, Progress};
+
+const SERVICE: &str = "S3";
+
+/// Objects listed at once, the most S3 returns from a single request.
+const PAGE_SIZE: i32 = 1000;
+
+/// Bytes fetched from the start of an object to preview it.
+const PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// Most text a compressed preview may inflate to.
+const PREVIEW_TEXT: u64 = 8 * 1024 * 1024;
+
+/// Size of each part of a multipart upload, bar the last. Files no larger go up in one piece.
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Most parts S3 takes in an upload, so larger files have larger parts.
+const MAX_PARTS: u64 = 10_000;
+
+/// How object IDs name a version, as in S3 URLs: `bucket/key?versionId=abc`.
+const VERSION_ID: &str = "?versionId=";
+
+pub(crate) struct S3 {
+    provider: AwsProvider,
+    /// Regions looked up so far, by bucket.
+    regions: Mutex<HashMap<String, String>>,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for S3 {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> S3 {
+        Self {
+            provider: AwsProvider::new(context),
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Buckets", Buckets::new)
+            .resource("Objects", Objects::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_s3::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_s3::Client> for S3 {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_s3::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_s3::Client::new(&config);
+        Ok(client)
+    }
+}
+
+impl S3 {
+    /// The region `bucket` lives in. Buckets in `us-east-1` have no location constraint, and the
+    /// oldest ones in `eu-west-1` have `EU`.
+    async fn get_region(&self, bucket: &str) -> anyhow::Result<String> {
+        if let Some(region) = self.regions.lock().unwrap().get(bucket) {
+            return Ok(region.clone());
+        }
+        let location = self.get_client().await?.get_bucket_location()
+            .bucket(bucket)
+            .send().await?
+            .location_constraint;
+        let region = match location.as_ref().map(|x| x.as_str()) {
+            None | Some("") => "us-east-1",
+            Some("EU") => "eu-west-1",
+            Some(region) => region,
+        }.to_string();
+        self.regions.lock().unwrap().insert(bucket.to_string(), region.clone());
+        Ok(region)
+    }
+
+    /// A client for the region `bucket` lives in, which S3 insists on for anything inside it.
+    async fn get_bucket_client(&self, bucket: &str) -> anyhow::Result<aws_sdk_s3::Client> {
+        let region = self.get_region(bucket).await?;
+        let context = Context { region: Some(region), ..self.provider.context.clone() };
+        S3::new(&context).get_client().await
+    }
+}
+
+pub(crate) struct Buckets {
+    svc: S3,
+}
+
+impl Buckets {
+    fn new(context: &Context) -> Self {
+        Buckets {
+            svc: S3::new(context)
+        }
+    }
+
+    async fn get_bucket(&self, bucket: aws_sdk_s3::model::Bucket) -> Bucket {
+        let name = bucket.name.unwrap_or_default();
+        Bucket {
+            region: self.svc.get_region(&name).await.ok(),
+            created: bucket.creation_date.map(|x| x.fmt(aws_smithy_types::date_time::Format::DateTime).unwrap_or_default()),
+            name,
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Bucket> for Buckets {
+    async fn list(&self) -> anyhow::Result<Vec<Bucket>> {
+        let buckets = self.svc.get_client().await?.list_buckets().send().await?
+            .buckets
+            .unwrap_or_default();
+        // a lookup for each bucket, so run them side by side
+        Ok(futures::future::join_all(buckets.into_iter().map(|x| self.get_bucket(x))).await)
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Bucket>>> {
+        let bucket = self.svc.get_client().await?.list_buckets().send().await?
+            .buckets
+            .unwrap_or_default()
+            .into_iter()
+            .find(|x| x.name.as_deref() == Some(id.as_str()));
+        let mut bucket = match bucket {
+            Some(bucket) => self.get_bucket(bucket).await,
+            None => return Ok(None),
+        };
+
+        let client = self.svc.get_bucket_client(&id).await?;
+        let versioning = client.get_bucket_versioning().bucket(&id).send().await?.status;
+        bucket.versioning = Some(versioning.map(|x| x.as_str().to_string()).unwrap_or_else(|| "Disabled".to_string()));
+        bucket.tags = match client.get_bucket_tagging().bucket(&id).send().await {
+            Ok(output) => get_tags(output.tag_set),
+            // rather than an empty set, buckets without tags have none at all
+            Err(err) if format!("{:?}", err).contains("NoSuchTagSet") => vec![],
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(ResourceDescription::from_resource(&bucket)))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Bucket {
+    name: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    region: Option<String>,
+    created: Option<String>,
+    versioning: Option<String>,
+}
+
+impl Resource for Bucket {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Region", "Created"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.region.clone().unwrap_or_default(),
+            self.created.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Bucket::get_headers(), self.get_columns());
+        props.extend(self.versioning.clone().map(|x| ("Versioning".to_string(), x)));
+        props.extend(self.tags.iter().map(|(k, v)| (format!("Tag: {}", k), v.clone())));
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.get_contents().into_iter().collect()
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        Some(Reference::listing(&self.name, SERVICE, "Objects", format!("{}/", self.name)))
+    }
+}
+
+/// Where a page of a listing starts.
+#[derive(Clone)]
+enum Marker {
+    /// The continuation token from `list_objects_v2`.
+    Token(String),
+    /// The key and version markers from `list_object_versions`.
+    Version { key: Option<String>, version: Option<String> },
+}
+
+/// Browses a bucket like a file manager: the objects directly under a prefix, with the prefixes
+/// below it as folders. Unscoped, the buckets are the folders.
+pub(crate) struct Objects {
+    svc: S3,
+    /// The bucket and prefix, from the scope.
+    location: Option<(String, String)>,
+    /// Whether to list every version of each object, rather than only the latest.
+    versions: bool,
+    /// Where each page after the first starts, up to the current one.
+    pages: Vec<Marker>,
+    /// Where the page after the current one starts, if there is one, as of the last listing.
+    next: Mutex<Option<Marker>>,
+}
+
+impl Objects {
+    fn new(context: &Context) -> Self {
+        Objects {
+            svc: S3::new(context),
+            location: None,
+            versions: false,
+            pages: vec![],
+            next: Mutex::new(None),
+        }
+    }
+
+    async fn list_buckets(&self) -> anyhow::Result<Vec<Object>> {
+        Ok(self.svc.get_client().await?.list_buckets().send().await?
+            .buckets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| x.name)
+            .map(|x| Object::folder(format!("{}/", x)))
+            .collect())
+    }
+
+    async fn list_latest(&self, bucket: &str, prefix: &str) -> anyhow::Result<Vec<Object>> {
+        let token = match self.pages.last() {
+            Some(Marker::Token(token)) => Some(token.clone()),
+            _ => None,
+        };
+        let output = self.svc.get_bucket_client(bucket).await?.list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .delimiter("/")
+            .max_keys(PAGE_SIZE)
+            .set_continuation_token(token)
+            .send().await?;
+        *self.next.lock().unwrap() = output.next_continuation_token.map(Marker::Token);
+
+        let folders = output.common_prefixes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| x.prefix)
+            .map(|x| Object::folder(format!("{}/{}", bucket, x)));
+        let objects = output.contents
+            .unwrap_or_default()
+            .into_iter()
+            // the placeholder some tools create for an empty folder
+            .filter(|x| x.key.as_deref() != Some(prefix))
+            .map(|x| Object {
+                id: format!("{}/{}", bucket, x.key.unwrap_or_default()),
+                size: Some(x.size),
+                last_modified: x.last_modified.map(format_date),
+                storage_class: x.storage_class.map(|x| x.as_str().to_string()),
+                ..Default::default()
+            });
+        Ok(folders.chain(objects).collect())
+    }
+
+    async fn list_versions(&self, bucket: &str, prefix: &str) -> anyhow::Result<Vec<Object>> {
+        let (key, version) = match self.pages.last() {
+            Some(Marker::Version { key, version }) => (key.clone(), version.clone()),
+            _ => (None, None),
+        };
+        let output = self.svc.get_bucket_client(bucket).await?.list_object_versions()
+            .bucket(bucket)
+            .prefix(prefix)
+            .delimiter("/")
+            .max_keys(PAGE_SIZE)
+            .set_key_marker(key)
+            .set_version_id_marker(version)
+            .send().await?;
+        *self.next.lock().unwrap() = output.is_truncated.then_some(Marker::Version {
+            key: output.next_key_marker,
+            version: output.next_version_id_marker,
+        });
+
+        let folders = output.common_prefixes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| x.prefix)
+            .map(|x| Object::folder(format!("{}/{}", bucket, x)));
+        let versions = output.versions
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|x| x.key.as_deref() != Some(prefix))
+            .map(|x| Object {
+                id: get_version_id(bucket, &x.key.unwrap_or_default(), x.version_id.as_deref()),
+                size: Some(x.size),
+                last_modified: x.last_modified.map(format_date),
+                storage_class: x.storage_class.map(|x| x.as_str().to_string()),
+                version: Some(describe_version(x.version_id, x.is_latest, false)),
+                ..Default::default()
+            });
+        let markers = output.delete_markers
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| Object {
+                id: get_version_id(bucket, &x.key.unwrap_or_default(), x.version_id.as_deref()),
+                last_modified: x.last_modified.map(format_date),
+                version: Some(describe_version(x.version_id, x.is_latest, true)),
+                ..Default::default()
+            });
+        let mut objects: Vec<Object> = versions.chain(markers).collect();
+        // each key's versions together, newest first, as they would be without the delete markers
+        objects.sort_by(|a, b| get_key(&a.id).cmp(get_key(&b.id)).then(b.last_modified.cmp(&a.last_modified)));
+        Ok(folders.chain(objects).collect())
+    }
+
+    /// Every object under `prefix`, however deep, with its size and when it was last modified.
+    async fn list_keys(&self, bucket: &str, prefix: &str) -> anyhow::Result<Vec<aws_sdk_s3::model::Object>> {
+        let client = self.svc.get_bucket_client(bucket).await?;
+        let mut objects = vec![];
+        let mut token = None;
+        loop {
+            let output = client.list_objects_v2()
+                .bucket(bucket)
+                .prefix(prefix)
+                .set_continuation_token(token)
+                .send().await?;
+            objects.extend(output.contents.unwrap_or_default());
+            token = output.next_continuation_token;
+            if token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    /// The current bucket and prefix, which uploads go into.
+    fn get_location(&self) -> anyhow::Result<(&str, &str)> {
+        self.location.as_ref()
+            .map(|(bucket, prefix)| (bucket.as_str(), prefix.as_str()))
+            .ok_or_else(|| anyhow::anyhow!("open a bucket to upload into"))
+    }
+
+    /// Download the object `id` names into `dir`, or for a folder, everything under it.
+    async fn download(&self, id: &str, dir: &Path) -> anyhow::Result<Vec<Job>> {
+        let (bucket, key, version) = parse_id(id)?;
+        let client = self.svc.get_bucket_client(bucket).await?;
+        if !key.is_empty() && !key.ends_with('/') {
+            let path = get_local_path(dir, &get_name(id))?;
+            return Ok(vec![download_job(client, bucket, key, version, path)]);
+        }
+        // the folder itself goes in the directory, rather than only what's in it
+        let folder = get_name(id);
+        let mut jobs = vec![];
+        for object in self.list_keys(bucket, key).await? {
+            let path = object.key.unwrap_or_default();
+            if path.ends_with('/') {
+                continue;
+            }
+            let local = get_local_path(dir, &format!("{}{}", folder, &path[key.len()..]))?;
+            jobs.push(download_job(client.clone(), bucket, &path, None, local));
+        }
+        Ok(jobs)
+    }
+
+    /// Upload the file at `path` to the current prefix, or for a directory, everything in it.
+    async fn upload(&self, path: &Path) -> anyhow::Result<Vec<Job>> {
+        let (bucket, prefix) = self.get_location()?;
+        let client = self.svc.get_bucket_client(bucket).await?;
+        let name = path.file_name()
+            .ok_or_else(|| anyhow::anyhow!("nothing to upload at {}", path.display()))?
+            .to_string_lossy();
+        if !path.is_dir() {
+            let key = format!("{}{}", prefix, name);
+            return Ok(vec![upload_job(client, bucket, &key, path.to_path_buf())]);
+        }
+        Ok(walk(path)?
+            .into_iter()
+            .map(|(relative, file)| {
+                let key = format!("{}{}/{}", prefix, name, relative);
+                upload_job(client.clone(), bucket, &key, file)
+            })
+            .collect())
+    }
+
+    /// Upload the files in `dir` that the current prefix lacks, or has a different or older
+    /// copy of, like `aws s3 sync`.
+    async fn sync(&self, dir: &Path) -> anyhow::Result<Vec<Job>> {
+        let (bucket, prefix) = self.get_location()?;
+        if !dir.is_dir() {
+            anyhow::bail!("not a directory: {}", dir.display());
+        }
+        let client = self.svc.get_bucket_client(bucket).await?;
+        let remote: HashMap<String, (i64, i64)> = self.list_keys(bucket, prefix).await?
+            .into_iter()
+            .map(|x| (x.key.unwrap_or_default(), (x.size, x.last_modified.map(|x| x.secs()).unwrap_or_default())))
+            .collect();
+        let mut jobs = vec![];
+        for (relative, file) in walk(dir)? {
+            let key = format!("{}{}", prefix, relative);
+            let metadata = std::fs::metadata(&file)?;
+            let modified = metadata.modified()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|x| x.as_secs() as i64)
+                .unwrap_or_default();
+            let stale = match remote.get(&key) {
+                Some((size, uploaded)) => *size as u64 != metadata.len() || modified > *uploaded,
+                None => true,
+            };
+            if stale {
+                jobs.push(upload_job(client.clone(), bucket, &key, file));
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// The start of the object, as text: inflated if gzipped, and laid out if it's JSON.
+    async fn preview(&self, id: &str) -> anyhow::Result<ActionOutcome> {
+        let (bucket, key, version) = parse_id(id)?;
+        if key.is_empty() || key.ends_with('/') {
+            anyhow::bail!("{} is a folder", id);
+        }
+        let output = self.svc.get_bucket_client(bucket).await?.get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_version_id(version.map(|x| x.to_string()))
+            .range(format!("bytes=0-{}", PREVIEW_BYTES - 1))
+            .send().await?;
+        let gzip = key.ends_with(".gz") || output.content_encoding.as_deref() == Some("gzip");
+        let json = key.trim_end_matches(".gz").ends_with(".json")
+            || output.content_type.as_deref().map(|x| x.contains("json")).unwrap_or(false);
+        // e.g. `bytes 0-1048575/5452595`, telling how much was left out
+        let total = output.content_range.as_deref()
+            .and_then(|x| x.rsplit('/').next())
+            .and_then(|x| x.parse::<i64>().ok());
+        let data = output.body.collect().await?.into_bytes();
+
+        let mut text = decode(&data, gzip, json)?;
+        if let Some(total) = total.filter(|x| *x > data.len() as i64) {
+            text.push_str(&format!("\n... (first {} of {})", format_size(data.len() as u64), format_size(total as u64)));
+        }
+        Ok(ActionOutcome::Show { title: get_name(id), text })
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Object> for Objects {
+    async fn list(&self) -> anyhow::Result<Vec<Object>> {
+        match &self.location {
+            None => self.list_buckets().await,
+            Some((bucket, prefix)) if self.versions => self.list_versions(bucket, prefix).await,
+            Some((bucket, prefix)) => self.list_latest(bucket, prefix).await,
+        }
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Object>>> {
+        let (bucket, key, version) = parse_id(&id)?;
+        if key.is_empty() || key.ends_with('/') {
+            return Ok(Some(ResourceDescription::from_resource(&Object::folder(id))));
+        }
+        let client = self.svc.get_bucket_client(bucket).await?;
+        let head = client.head_object()
+            .bucket(bucket)
+            .key(key)
+            .set_version_id(version.map(|x| x.to_string()))
+            .send().await?;
+        let mut details: Vec<(String, String)> = [
+            ("Content type", head.content_type),
+            ("Content encoding", head.content_encoding),
+            ("ETag", head.e_tag),
+            ("Version", head.version_id),
+            ("Encryption", head.server_side_encryption.map(|x| x.as_str().to_string())),
+            ("Cache control", head.cache_control),
+            ("Expiration", head.expiration),
+            ("Restore", head.restore),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+        details.extend(head.metadata
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (format!("Metadata: {}", k), v)));
+
+        // tags need a permission of their own, which shouldn't hide everything else
+        let tags = match client.get_object_tagging().bucket(bucket).key(key).set_version_id(version.map(|x| x.to_string())).send().await {
+            Ok(output) => get_tags(output.tag_set),
+            Err(err) => {
+                details.push(("Tags".to_string(), format!("unavailable: {}", err)));
+                vec![]
+            }
+        };
+
+        let object = Object {
+            id: id.clone(),
+            size: Some(head.content_length),
+            last_modified: head.last_modified.map(format_date),
+            // only returned for classes other than STANDARD
+            storage_class: Some(head.storage_class.map(|x| x.as_str().to_string()).unwrap_or_else(|| "STANDARD".to_string())),
+            version: version.map(|x| x.to_string()),
+            tags,
+            details,
+        };
+        Ok(Some(ResourceDescription::from_resource(&object)))
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        let (bucket, prefix) = scope.split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("not a bucket and prefix: {}", scope))?;
+        self.location = Some((bucket.to_string(), prefix.to_string()));
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'o', name: "Preview" },
+            Action { key: 'V', name: "Toggle versions" },
+            Action { key: ']', name: "Next page" },
+            Action { key: '[', name: "Previous page" },
+            Action { key: 'y', name: "Copy S3 URI" },
+            Action { key: 'd', name: "Download" },
+            Action { key: 'u', name: "Upload" },
+            Action { key: 's', name: "Sync a directory up" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        match key {
+            'V' => {
+                self.versions = !self.versions;
+                self.pages.clear();
+                return Ok(ActionOutcome::Reload);
+            }
+            ']' => {
+                let next = self.next.lock().unwrap().take();
+                let next = next.ok_or_else(|| anyhow::anyhow!("no more objects"))?;
+                self.pages.push(next);
+                return Ok(ActionOutcome::Reload);
+            }
+            '[' => {
+                self.pages.pop().ok_or_else(|| anyhow::anyhow!("already on the first page"))?;
+                return Ok(ActionOutcome::Reload);
+            }
+            'u' | 's' => {
+                let (bucket, prefix) = self.get_location()?;
+                let verb = if key == 'u' { "Upload" } else { "Sync" };
+                let prompt = format!("{} to s3://{}/{} from: ", verb, bucket, prefix);
+                return Ok(ActionOutcome::Ask { prompt, input: get_current_dir() });
+            }
+            _ => {}
+        }
+
+        let id = id.ok_or_else(|| anyhow::anyhow!("no object selected"))?;
+        match key {
+            'o' => self.preview(&id).await,
+            'y' => Ok(ActionOutcome::Copy(format!("s3://{}", get_key(&id)))),
+            'd' => Ok(ActionOutcome::Ask { prompt: format!("Download {} to: ", get_name(&id)), input: get_current_dir() }),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let path = expand_home(input.trim());
+        let jobs = match key {
+            'd' => self.download(&id.ok_or_else(|| anyhow::anyhow!("no object selected"))?, &path).await?,
+            'u' => self.upload(&path).await?,
+            's' => self.sync(&path).await?,
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        };
+        Ok(ActionOutcome::Transfer(jobs))
+    }
+}
+
+fn download_job(client: aws_sdk_s3::Client, bucket: &str, key: &str, version: Option<&str>, path: PathBuf) -> Job {
+    let (bucket, key, version) = (bucket.to_string(), key.to_string(), version.map(|x| x.to_string()));
+    Job::new(format!("Download s3://{}/{}", bucket, key), move |progress| {
+        Box::pin(download(client.clone(), bucket.clone(), key.clone(), version.clone(), path.clone(), progress))
+    })
+}
+
+/// Stream the object to a file alongside `path`, moving it into place once complete so that a
+/// failed download doesn't leave part of a file looking like the whole.
+async fn download(
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    key: String,
+    version: Option<String>,
+    path: PathBuf,
+    progress: Arc<Progress>,
+) -> anyhow::Result<()> {
+    let output = client.get_object()
+        .bucket(&bucket)
+        .key(&key)
+        .set_version_id(version)
+        .send().await?;
+    progress.set_total(output.content_length.max(0) as u64);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut partial = path.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut guard = RemovePartial { path: Some(partial.clone()) };
+    let mut file = tokio::fs::File::create(&partial).await?;
+    let mut body = output.body;
+    while let Some(chunk) = body.try_next().await? {
+        file.write_all(&chunk).await?;
+        progress.add(chunk.len() as u64);
+    }
+    file.flush().await?;
+    tokio::fs::rename(&partial, &path).await?;
+    guard.path = None;
+    Ok(())
+}
+
+/// Removes the part of a download that didn't complete, whether it failed or was cancelled.
+struct RemovePartial {
+    path: Option<PathBuf>,
+}
+
+impl Drop for RemovePartial {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn upload_job(client: aws_sdk_s3::Client, bucket: &str, key: &str, path: PathBuf) -> Job {
+    let (bucket, key) = (bucket.to_string(), key.to_string());
+    Job::new(format!("Upload s3://{}/{}", bucket, key), move |progress| {
+        Box::pin(upload(client.clone(), bucket.clone(), key.clone(), path.clone(), progress))
+    })
+}
+
+/// Upload the file in one piece if it's small, and in parts otherwise.
+async fn upload(
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    key: String,
+    path: PathBuf,
+    progress: Arc<Progress>,
+) -> anyhow::Result<()> {
+    let size = tokio::fs::metadata(&path).await?.len();
+    progress.set_total(size);
+    let part_size = PART_SIZE.max(size.div_ceil(MAX_PARTS));
+    if size <= part_size {
+        let data = tokio::fs::read(&path).await?;
+        client.put_object().bucket(&bucket).key(&key).body(ByteStream::from(data)).send().await?;
+        progress.add(size);
+        return Ok(());
+    }
+
+    let upload_id = client.create_multipart_upload().bucket(&bucket).key(&key).send().await?
+        .upload_id
+        .ok_or_else(|| anyhow::anyhow!("no upload ID for {}", key))?;
+    let mut guard = AbortUpload {
+        client: client.clone(),
+        bucket: bucket.clone(),
+        key: key.clone(),
+        upload_id: Some(upload_id.clone()),
+    };
+    let mut file = tokio::fs::File::open(&path).await?;
+    let mut parts = vec![];
+    for number in 1.. {
+        let mut buffer = Vec::with_capacity(part_size as usize);
+        (&mut file).take(part_size).read_to_end(&mut buffer).await?;
+        if buffer.is_empty() {
+            break;
+        }
+        let length = buffer.len() as u64;
+        let e_tag = client.upload_part()
+            .bucket(&bucket)
+            .key(&key)
+            .upload_id(&upload_id)
+            .part_number(number)
+            .body(ByteStream::from(buffer))
+            .send().await?
+            .e_tag;
+        parts.push(aws_sdk_s3::model::CompletedPart::builder().set_e_tag(e_tag).part_number(number).build());
+        progress.add(length);
+    }
+    client.complete_multipart_upload()
+        .bucket(&bucket)
+        .key(&key)
+        .upload_id(&upload_id)
+        .multipart_upload(aws_sdk_s3::model::CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+        .send().await?;
+    guard.upload_id = None;
+    Ok(())
+}
+
+/// Aborts a multipart upload that didn't complete, whether it failed or was cancelled, since
+/// S3 otherwise keeps the parts, and bills for them, indefinitely.
+struct AbortUpload {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    key: String,
+    upload_id: Option<String>,
+}
+
+impl Drop for AbortUpload {
+    fn drop(&mut self) {
+        if let Some(upload_id) = self.upload_id.take() {
+            let request = self.client.abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&self.key)
+                .upload_id(upload_id);
+            tokio::spawn(async move {
+                let _ = request.send().await;
+            });
+        }
+    }
+}
+
+/// Files under `dir`, however deep, each with its path relative to `dir` as a key would have it.
+fn walk(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(dir)?
+                    .components()
+                    .map(|x| x.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Where the key `relative` goes under `dir`. Keys are only names, so one that would climb out
+/// of the directory, like `../../.bashrc`, is refused.
+fn get_local_path(dir: &Path, relative: &str) -> anyhow::Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative.components().any(|x| !matches!(x, Component::Normal(_))) {
+        anyhow::bail!("refusing to download outside {}: {}", dir.display(), relative.display());
+    }
+    Ok(dir.join(relative))
+}
+
+fn get_current_dir() -> String {
+    std::env::current_dir().map(|x| x.display().to_string()).unwrap_or_default()
+}
+
+/// `path`, with a leading `~` standing for the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// An object, a version of one, or a folder: a prefix shared by the keys below it.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Object {
+    /// `bucket/key`, with the version as in `VERSION_ID` when listing versions. Folders end in
+    /// a slash.
+    id: String,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    tags: Vec<(String, String)>,
+    size: Option<i64>,
+    last_modified: Option<String>,
+    storage_class: Option<String>,
+    version: Option<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl Object {
+    fn folder(id: String) -> Self {
+        Object { id, ..Default::default() }
+    }
+
+    fn is_folder(&self) -> bool {
+        self.id.ends_with('/')
+    }
+}
+
+impl Resource for Object {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        get_name(&self.id)
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Size", "Last modified", "Storage class", "Version"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.get_name(),
+            self.size.map(|x| format_size(x as u64)).unwrap_or_default(),
+            self.last_modified.clone().unwrap_or_default(),
+            self.storage_class.clone().unwrap_or_default(),
+            self.version.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Object::get_headers(), self.get_columns());
+        props.insert("Key".to_string(), get_key(&self.id).to_string());
+        props.extend(self.details.clone());
+        props.extend(self.tags.iter().map(|(k, v)| (format!("Tag: {}", k), v.clone())));
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        let path = get_key(&self.id);
+        let (bucket, _) = path.split_once('/').unwrap_or((path, ""));
+        let mut references = vec![Reference::new("Bucket", SERVICE, "Buckets", bucket)];
+        let parent = path.trim_end_matches('/').rsplit_once('/').map(|(x, _)| x);
+        if let Some(parent) = parent.filter(|x| *x != bucket) {
+            let parent = format!("{}/", parent);
+            references.push(Reference::listing("Folder", SERVICE, "Objects", parent));
+        }
+        references.extend(self.get_contents());
+        references
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        match self.is_folder() {
+            true => Some(Reference::listing(&self.get_name(), SERVICE, "Objects", self.id.clone())),
+            false => None,
+        }
+    }
+}
+
+/// The ID of a version of `key`, or of the key itself for objects that predate versioning.
+fn get_version_id(bucket: &str, key: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{}/{}{}{}", bucket, key, VERSION_ID, version),
+        None => format!("{}/{}", bucket, key),
+    }
+}
+
+/// `bucket/key`, without any version.
+fn get_key(id: &str) -> &str {
+    id.rsplit_once(VERSION_ID).map(|(x, _)| x).unwrap_or(id)
+}
+
+/// The bucket, key and version an object ID names.
+fn parse_id(id: &str) -> anyhow::Result<(&str, &str, Option<&str>)> {
+    let version = id.rsplit_once(VERSION_ID).map(|(_, x)| x);
+    let (bucket, key) = get_key(id).split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("not a bucket and key: {}", id))?;
+    Ok((bucket, key, version))
+}
+
+/// The last part of the path, keeping the slash that marks a folder.
+fn get_name(id: &str) -> String {
+    let path = get_key(id);
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+    match path.ends_with('/') {
+        true => format!("{}/", name),
+        false => name.to_string(),
+    }
+}
+
+fn describe_version(version: Option<String>, latest: bool, delete_marker: bool) -> String {
+    let mut description = version.unwrap_or_else(|| "null".to_string());
+    match (latest, delete_marker) {
+        (true, true) => description.push_str(" (latest, deleted)"),
+        (true, false) => description.push_str(" (latest)"),
+        (false, true) => description.push_str(" (deleted)"),
+        (false, false) => {}
+    }
+    description
+}
+
+fn format_date(date: aws_smithy_types::DateTime) -> String {
+    date.fmt(aws_smithy_types::date_time::Format::DateTime).unwrap_or_default()
+}
+
+/// `data` as text, inflated first if `gzip`, and pretty printed if `json`. Since only the start
+/// of the object is fetched, inflating stops at the end of the data rather than failing, and
+/// JSON that doesn't parse is left as it is.
+fn decode(data: &[u8], gzip: bool, json: bool) -> anyhow::Result<String> {
+    let data = if gzip {
+        let mut inflated = vec![];
+        let result = flate2::read::GzDecoder::new(data).take(PREVIEW_TEXT).read_to_end(&mut inflated);
+        if let (Err(err), true) = (result, inflated.is_empty()) {
+            anyhow::bail!("can't inflate: {}", err);
+        }
+        inflated
+    } else {
+        data.to_vec()
+    };
+    if data.contains(&0) {
+        anyhow::bail!("binary data, which can't be previewed");
+    }
+    let text = String::from_utf8_lossy(&data).into_owned();
+    if json {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+            return Ok(serde_json::to_string_pretty(&value)?);
+        }
+    }
+    Ok(text)
+}
+
+fn get_tags(tags: Option<Vec<aws_sdk_s3::model::Tag>>) -> Vec<(String, String)> {
+    tags.unwrap_or_default()
+        .into_iter()
+        .filter_map(|x| Some((x.key?, x.value?)))
+        .collect()
+}
+
+/// The resource named by an S3 ARN: a bucket, `arn:aws:s3:::media`, or an object within one,
+/// `arn:aws:s3:::media/logs/a.txt`.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "s3" {
+        return None;
+    }
+    Some(match arn.resource.split_once('/') {
+        None => Reference::new("Bucket", SERVICE, "Buckets", arn.resource.clone()),
+        Some(_) if arn.resource.ends_with('/') => Reference::listing(&get_name(&arn.resource), SERVICE, "Objects", arn.resource.clone()),
+        Some(_) => Reference::new("Object", SERVICE, "Objects", arn.resource.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use super::*;
+
+    #[test]
+    fn ids() {
+        assert_eq!(parse_id("media/logs/a.txt").unwrap(), ("media", "logs/a.txt", None));
+        assert_eq!(parse_id("media/a.txt?versionId=v2").unwrap(), ("media", "a.txt", Some("v2")));
+        assert_eq!(parse_id("media/").unwrap(), ("media", "", None));
+        assert!(parse_id("media").is_err());
+        assert_eq!(get_name("media/logs/a.txt?versionId=v2"), "a.txt");
+        assert_eq!(get_name("media/logs/"), "logs/");
+        assert_eq!(get_name("media/"), "media/");
+    }
+
+    #[test]
+    fn decodes_json_and_gzip() {
+        assert_eq!(decode(br#"{"a":1}"#, false, true).unwrap(), "{\n  \"a\": 1\n}");
+        // cut short, as a preview of a larger object would be
+        assert_eq!(decode(br#"{"a":1"#, false, true).unwrap(), r#"{"a":1"#);
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        let text: String = (0..10000).map(|x| format!("line {}\n", x)).collect();
+        encoder.write_all(text.as_bytes()).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(decode(&data, true, false).unwrap(), text);
+        assert!(decode(&data[..data.len() / 2], true, false).unwrap().starts_with("line 0\nline 1\n"));
+
+        assert!(decode(b"\x00\x01binary", false, false).is_err());
+    }
+
+    #[test]
+    fn local_paths() {
+        let dir = Path::new("/tmp/out");
+        assert_eq!(get_local_path(dir, "orders/2021/a.json").unwrap(), Path::new("/tmp/out/orders/2021/a.json"));
+        assert!(get_local_path(dir, "orders/../../etc/passwd").is_err());
+        assert!(get_local_path(dir, "/etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,537 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::{BTreeMap, HashMap};
+use aws_sdk_sns::model::{MessageAttributeValue, Subscription as SubscriptionSummary};
+use serde_json::Value;
+use crate::service::registry::ServiceType;
+use crate::service::resource::{props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+
+const SERVICE: &str = "SNS";
+
+/// What SNS gives as the ARN of a subscription until its endpoint confirms it.
+const PENDING: &str = "PendingConfirmation";
+
+pub(crate) struct Sns {
+    provider: AwsProvider,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for Sns {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> Sns {
+        Self {
+            provider: AwsProvider::new(context),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Topics", Topics::new)
+            .resource("Subscriptions", Subscriptions::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_sns::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_sns::Client> for Sns {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_sns::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_sns::Client::new(&config);
+        Ok(client)
+    }
+}
+
+pub(crate) struct Topics {
+    svc: Sns,
+}
+
+impl Topics {
+    fn new(context: &Context) -> Self {
+        Topics {
+            svc: Sns::new(context)
+        }
+    }
+
+    async fn list_arns(&self) -> anyhow::Result<Vec<String>> {
+        let client = self.svc.get_client().await?;
+        let mut arns = vec![];
+        let mut token = None;
+        loop {
+            let output = client.list_topics().set_next_token(token).send().await?;
+            arns.extend(output.topics.unwrap_or_default().into_iter().filter_map(|x| x.topic_arn));
+            token = output.next_token;
+            if token.is_none() {
+                return Ok(arns);
+            }
+        }
+    }
+
+    async fn get_topic(&self, arn: &str) -> anyhow::Result<Topic> {
+        let attributes = self.svc.get_client().await?.get_topic_attributes()
+            .topic_arn(arn)
+            .send().await?
+            .attributes
+            .unwrap_or_default();
+        Ok(Topic::new(arn, attributes))
+    }
+
+    async fn publish(&self, arn: &str, text: &str) -> anyhow::Result<ActionOutcome> {
+        let message: Outgoing = serde_json::from_str(text).map_err(|x| anyhow::anyhow!("not a message: {}", x))?;
+        let mut attributes = HashMap::new();
+        for (name, value) in message.attributes {
+            let (kind, value) = match value {
+                Value::String(x) => ("String", x),
+                Value::Number(x) => ("Number", x.to_string()),
+                x @ Value::Array(_) => ("String.Array", x.to_string()),
+                x => anyhow::bail!("attribute {} isn't a string, a number or an array: {}", name, x),
+            };
+            attributes.insert(name, MessageAttributeValue::builder().data_type(kind).string_value(value).build());
+        }
+        let id = self.svc.get_client().await?.publish()
+            .topic_arn(arn)
+            .set_subject(message.subject.filter(|x| !x.is_empty()))
+            .message(message.message)
+            .set_message_attributes(Some(attributes).filter(|x| !x.is_empty()))
+            .set_message_group_id(message.group_id)
+            .set_message_deduplication_id(message.deduplication_id)
+            .send().await?
+            .message_id
+            .unwrap_or_default();
+        Ok(ActionOutcome::Status(format!("Published message {}", id)))
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Topic> for Topics {
+    async fn list(&self) -> anyhow::Result<Vec<Topic>> {
+        let arns = self.list_arns().await?;
+        let topics = futures::future::join_all(arns.iter().map(|x| self.get_topic(x))).await;
+        topics.into_iter().collect()
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Topic>>> {
+        let topic = self.get_topic(&id).await?;
+        Ok(Some(ResourceDescription::from_resource(&topic)))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 's', name: "Publish a message" },
+            Action { key: 'y', name: "Copy ARN" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let arn = id.ok_or_else(|| anyhow::anyhow!("no topic selected"))?;
+        match key {
+            's' => {
+                let fifo = arn.ends_with(".fifo");
+                let message = Outgoing {
+                    group_id: fifo.then(String::new),
+                    ..Default::default()
+                };
+                Ok(ActionOutcome::Edit { name: "message.json".to_string(), text: serde_json::to_string_pretty(&message)? })
+            }
+            'y' => Ok(ActionOutcome::Copy(arn)),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let arn = id.ok_or_else(|| anyhow::anyhow!("no topic selected"))?;
+        match key {
+            's' => self.publish(&arn, &input).await,
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Topic {
+    arn: String,
+    display_name: String,
+    fifo: bool,
+    confirmed: Option<i64>,
+    pending: Option<i64>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl Topic {
+    fn new(arn: &str, attributes: HashMap<String, String>) -> Self {
+        let get = |name: &str| attributes.get(name).cloned();
+        let count = |name: &str| get(name).and_then(|x| x.parse().ok());
+        let details = [
+            ("Owner", get("Owner")),
+            ("Deleted subscriptions", get("SubscriptionsDeleted")),
+            ("KMS key", get("KmsMasterKeyId")),
+            ("Content-based deduplication", get("ContentBasedDeduplication")),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+
+        Topic {
+            arn: arn.to_string(),
+            display_name: get("DisplayName").unwrap_or_default(),
+            fifo: get("FifoTopic").as_deref() == Some("true"),
+            confirmed: count("SubscriptionsConfirmed"),
+            pending: count("SubscriptionsPending"),
+            details,
+        }
+    }
+}
+
+impl Resource for Topic {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.arn.clone()
+    }
+
+    fn get_name(&self) -> String {
+        get_arn_name(&self.arn).to_string()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Type", "Display name", "Subscriptions", "Pending"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        let count = |x: Option<i64>| x.map(|x| x.to_string()).unwrap_or_default();
+        vec![
+            self.get_name(),
+            if self.fifo { "FIFO" } else { "Standard" }.to_string(),
+            self.display_name.clone(),
+            count(self.confirmed),
+            count(self.pending),
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Topic::get_headers(), self.get_columns());
+        props.insert("ARN".to_string(), self.arn.clone());
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.get_contents().into_iter().collect()
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        Some(Reference::listing(&self.get_name(), SERVICE, "Subscriptions", self.arn.clone()))
+    }
+}
+
+/// A message to publish, as edited. Attributes take their type from their value: strings,
+/// numbers, or arrays for `String.Array`.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Outgoing {
+    subject: Option<String>,
+    message: String,
+    attributes: BTreeMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deduplication_id: Option<String>,
+}
+
+/// Subscriptions to all topics, or when scoped to a topic's ARN, just that topic's.
+pub(crate) struct Subscriptions {
+    svc: Sns,
+    topic: Option<String>,
+}
+
+impl Subscriptions {
+    fn new(context: &Context) -> Self {
+        Subscriptions {
+            svc: Sns::new(context),
+            topic: None,
+        }
+    }
+
+    async fn list_summaries(&self) -> anyhow::Result<Vec<SubscriptionSummary>> {
+        let client = self.svc.get_client().await?;
+        let mut summaries = vec![];
+        let mut token = None;
+        loop {
+            let (subscriptions, next) = match &self.topic {
+                Some(topic) => {
+                    let output = client.list_subscriptions_by_topic().topic_arn(topic).set_next_token(token).send().await?;
+                    (output.subscriptions, output.next_token)
+                }
+                None => {
+                    let output = client.list_subscriptions().set_next_token(token).send().await?;
+                    (output.subscriptions, output.next_token)
+                }
+            };
+            summaries.extend(subscriptions.unwrap_or_default());
+            token = next;
+            if token.is_none() {
+                return Ok(summaries);
+            }
+        }
+    }
+
+    /// The subscription, with its attributes once it's confirmed; until then SNS has none.
+    async fn get_subscription(&self, summary: SubscriptionSummary) -> anyhow::Result<Subscription> {
+        let arn = summary.subscription_arn.clone().unwrap_or_default();
+        let attributes = match arn.as_str() {
+            PENDING | "" => HashMap::new(),
+            arn => self.svc.get_client().await?.get_subscription_attributes()
+                .subscription_arn(arn)
+                .send().await?
+                .attributes
+                .unwrap_or_default(),
+        };
+        Ok(Subscription::new(summary, attributes))
+    }
+
+    /// The subscription `id`, from its attributes, which hold what its summary would. Pending
+    /// subscriptions have no ARN to ask with, nor attributes, so they're found among the summaries.
+    async fn find(&self, id: &str) -> anyhow::Result<Option<Subscription>> {
+        if id.contains(":pending:") {
+            let summaries = self.list_summaries().await?;
+            return Ok(summaries.into_iter().map(|x| Subscription::new(x, HashMap::new())).find(|x| x.get_id() == id));
+        }
+        let attributes = self.svc.get_client().await?.get_subscription_attributes()
+            .subscription_arn(id)
+            .send().await?
+            .attributes
+            .unwrap_or_default();
+        let get = |name: &str| attributes.get(name).cloned();
+        let summary = SubscriptionSummary::builder()
+            .subscription_arn(id)
+            .set_owner(get("Owner"))
+            .set_protocol(get("Protocol"))
+            .set_endpoint(get("Endpoint"))
+            .set_topic_arn(get("TopicArn"))
+            .build();
+        Ok(Some(Subscription::new(summary, attributes)))
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Subscription> for Subscriptions {
+    async fn list(&self) -> anyhow::Result<Vec<Subscription>> {
+        let summaries = self.list_summaries().await?;
+        let subscriptions = futures::future::join_all(summaries.into_iter().map(|x| self.get_subscription(x))).await;
+        subscriptions.into_iter().collect()
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Subscription>>> {
+        Ok(self.find(&id).await?.as_ref().map(ResourceDescription::from_resource))
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.topic = Some(scope);
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'o', name: "Show filter policy" },
+            Action { key: 'y', name: "Copy endpoint" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let id = id.ok_or_else(|| anyhow::anyhow!("no subscription selected"))?;
+        let subscription = self.find(&id).await?
+            .ok_or_else(|| anyhow::anyhow!("subscription not found"))?;
+        match key {
+            'o' => {
+                let policy = subscription.filter_policy.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("{} has no filter policy", subscription.endpoint))?;
+                Ok(ActionOutcome::Show { title: subscription.endpoint.clone(), text: format_json(policy, true) })
+            }
+            'y' => Ok(ActionOutcome::Copy(subscription.endpoint)),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Subscription {
+    /// The subscription's ARN, or for one pending confirmation, which has none, the topic's
+    /// ARN followed by `:pending:` and the endpoint.
+    id: String,
+    topic: String,
+    protocol: String,
+    endpoint: String,
+    pending: bool,
+    filter_policy: Option<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl Subscription {
+    fn new(summary: SubscriptionSummary, attributes: HashMap<String, String>) -> Self {
+        let get = |name: &str| attributes.get(name).cloned();
+        let topic = summary.topic_arn.unwrap_or_default();
+        let endpoint = summary.endpoint.unwrap_or_default();
+        let (id, pending) = match summary.subscription_arn {
+            Some(arn) if arn != PENDING => (arn, get("PendingConfirmation").as_deref() == Some("true")),
+            _ => (format!("{}:pending:{}", topic, endpoint), true),
+        };
+        let details = [
+            ("Owner", summary.owner),
+            ("Raw message delivery", get("RawMessageDelivery")),
+            ("Redrive policy", get("RedrivePolicy").map(|x| format_json(&x, false))),
+            ("Delivery policy", get("EffectiveDeliveryPolicy").map(|x| format_json(&x, false))),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+
+        Subscription {
+            id,
+            topic,
+            protocol: summary.protocol.unwrap_or_default(),
+            endpoint,
+            pending,
+            filter_policy: get("FilterPolicy"),
+            details,
+        }
+    }
+}
+
+impl Resource for Subscription {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.endpoint.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Endpoint", "Protocol", "Status", "Filter policy", "Topic"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![
+            self.endpoint.clone(),
+            self.protocol.clone(),
+            if self.pending { "Pending confirmation" } else { "Confirmed" }.to_string(),
+            self.filter_policy.as_deref().map(|x| format_json(x, false)).unwrap_or_default(),
+            get_arn_name(&self.topic).to_string(),
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Subscription::get_headers(), self.get_columns());
+        if !self.pending {
+            props.insert("ARN".to_string(), self.id.clone());
+        }
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        let topic = Reference::new("Topic", SERVICE, "Topics", self.topic.clone());
+        let endpoint = self.endpoint.parse::<aws::Arn>().ok()
+            .and_then(|x| get_endpoint_reference(&x))
+            .map(|x| Reference { label: "Endpoint".to_string(), ..x });
+        std::iter::once(topic).chain(endpoint).collect()
+    }
+}
+
+/// What a subscription delivers to, where it's a resource we can show: a queue, or another topic.
+fn get_endpoint_reference(arn: &aws::Arn) -> Option<Reference> {
+    [aws::sqs::get_reference, get_reference]
+        .iter()
+        .find_map(|resolve| resolve(arn))
+}
+
+/// `text` laid out as JSON, across lines if `pretty`; as given if it isn't JSON.
+fn format_json(text: &str, pretty: bool) -> String {
+    let json = match serde_json::from_str::<Value>(text) {
+        Ok(json) => json,
+        Err(_) => return text.to_string(),
+    };
+    let formatted = match pretty {
+        true => serde_json::to_string_pretty(&json),
+        false => serde_json::to_string(&json),
+    };
+    formatted.unwrap_or_else(|_| text.to_string())
+}
+
+/// The topic's name, the last part of its ARN.
+fn get_arn_name(arn: &str) -> &str {
+    arn.rsplit(':').next().unwrap_or(arn)
+}
+
+/// The topic, or subscription, named by an SNS ARN: `arn:aws:sns:eu-west-1:123456789012:orders`
+/// for a topic, with a subscription's ID added for a subscription.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "sns" {
+        return None;
+    }
+    match arn.resource.split(':').count() {
+        1 => Some(Reference::new("Topic", SERVICE, "Topics", arn.to_string())),
+        2 => Some(Reference::new("Subscription", SERVICE, "Subscriptions", arn.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriptions() {
+        let summary = SubscriptionSummary::builder()
+            .subscription_arn("arn:aws:sns:eu-west-1:123456789012:orders:5f1c")
+            .topic_arn("arn:aws:sns:eu-west-1:123456789012:orders")
+            .protocol("sqs")
+            .endpoint("arn:aws:sqs:eu-west-1:123456789012:orders-archive")
+            .build();
+        let attributes = HashMap::from([
+            ("PendingConfirmation".to_string(), "false".to_string()),
+            ("FilterPolicy".to_string(), "{\n  \"status\": [\"shipped\"]\n}".to_string()),
+        ]);
+        let subscription = Subscription::new(summary, attributes);
+        assert_eq!(subscription.get_columns(), vec![
+            "arn:aws:sqs:eu-west-1:123456789012:orders-archive",
+            "sqs",
+            "Confirmed",
+            r#"{"status":["shipped"]}"#,
+            "orders",
+        ]);
+        assert_eq!(subscription.get_references()[1], Reference::new(
+            "Endpoint",
+            "SQS",
+            "Queues",
+            "https://sqs.eu-west-1.amazonaws.com/123456789012/orders-archive",
+        ));
+
+        let pending = SubscriptionSummary::builder()
+            .subscription_arn(PENDING)
+            .topic_arn("arn:aws:sns:eu-west-1:123456789012:orders")
+            .protocol("email")
+            .endpoint("ops@example.com")
+            .build();
+        let pending = Subscription::new(pending, HashMap::new());
+        assert_eq!(pending.get_id(), "arn:aws:sns:eu-west-1:123456789012:orders:pending:ops@example.com");
+        assert_eq!(pending.get_columns()[2], "Pending confirmation");
+    }
+}
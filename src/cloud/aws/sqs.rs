@@ -0,0 +1,547 @@
+use crate::cloud::aws::AwsProvider;
+use crate::service;
+use crate::cloud::aws;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use aws_sdk_sqs::model::{DeleteMessageBatchRequestEntry, Message, MessageAttributeValue, MessageSystemAttributeName, QueueAttributeName, SendMessageBatchRequestEntry};
+use chrono::TimeZone;
+use serde_json::Value;
+use crate::service::registry::ServiceType;
+use crate::service::resource::{is_confirmed, props, Action, ActionOutcome, Reference, Resource, ResourceDescription};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::cloud::fixtures;
+use crate::service::{Context, Service};
+use crate::cloud::aws::AwsService;
+use crate::transfer::{Job, Progress, Unit};
+
+const SERVICE: &str = "SQS";
+
+/// The most messages SQS hands over, or takes, in one request.
+const BATCH_SIZE: i32 = 10;
+
+/// Receives made to peek at a queue. Each returns messages from a sample of SQS's servers, so
+/// a few together see more of the queue.
+const PEEK_RECEIVES: usize = 3;
+
+/// How long a message being redriven is hidden from other consumers, in seconds, in case the
+/// move fails part way and it has to go back.
+const REDRIVE_VISIBILITY: i32 = 60;
+
+pub(crate) struct Sqs {
+    provider: AwsProvider,
+}
+
+#[async_trait]
+impl <'a> service::Service<'a> for Sqs {
+    type Provider = AwsProvider;
+
+    fn new(context: &Context) -> Sqs {
+        Self {
+            provider: AwsProvider::new(context),
+        }
+    }
+
+    fn get_service_type() -> ServiceType {
+        ServiceType::new(SERVICE)
+            .resource("Queues", Queues::new)
+            .references(aws::arns(get_reference))
+            .on_shutdown(aws::drop_clients::<aws_sdk_sqs::Client>)
+    }
+}
+
+#[async_trait]
+impl <'a> aws::AwsService<'a, aws_sdk_sqs::Client> for Sqs {
+    fn get_provider(&self) -> &AwsProvider {
+        &self.provider
+    }
+
+    async fn new_client(&self) -> anyhow::Result<aws_sdk_sqs::Client> {
+        let config= self.provider.get_config().await;
+        let client = aws_sdk_sqs::Client::new(&config);
+        Ok(client)
+    }
+}
+
+pub(crate) struct Queues {
+    svc: Sqs,
+}
+
+impl Queues {
+    fn new(context: &Context) -> Self {
+        Queues {
+            svc: Sqs::new(context)
+        }
+    }
+
+    async fn list_urls(&self) -> anyhow::Result<Vec<String>> {
+        let client = self.svc.get_client().await?;
+        let mut urls = vec![];
+        let mut token = None;
+        loop {
+            let output = client.list_queues().set_next_token(token).send().await?;
+            urls.extend(output.queue_urls.unwrap_or_default());
+            token = output.next_token;
+            if token.is_none() {
+                return Ok(urls);
+            }
+        }
+    }
+
+    /// The queues whose dead-letter queue is `url`.
+    async fn list_sources(&self, url: &str) -> anyhow::Result<Vec<String>> {
+        let client = self.svc.get_client().await?;
+        let mut urls = vec![];
+        let mut token = None;
+        loop {
+            let output = client.list_dead_letter_source_queues()
+                .queue_url(url)
+                .set_next_token(token)
+                .send().await?;
+            urls.extend(output.queue_urls.unwrap_or_default());
+            token = output.next_token;
+            if token.is_none() {
+                return Ok(urls);
+            }
+        }
+    }
+
+    async fn get_queue(&self, url: &str) -> anyhow::Result<Queue> {
+        let attributes = self.svc.get_client().await?.get_queue_attributes()
+            .queue_url(url)
+            .attribute_names(QueueAttributeName::All)
+            .send().await?
+            .attributes
+            .unwrap_or_default();
+        Ok(Queue::new(url, attributes))
+    }
+
+    /// Messages at the front of the queue, as text, left where they are: they're received with
+    /// no visibility timeout, and not deleted. Receiving does count towards moving a message to
+    /// the dead-letter queue, though.
+    async fn peek(&self, url: &str) -> anyhow::Result<ActionOutcome> {
+        let client = self.svc.get_client().await?;
+        let mut seen = HashSet::new();
+        let mut messages = vec![];
+        for _ in 0..PEEK_RECEIVES {
+            let received = client.receive_message()
+                .queue_url(url)
+                .max_number_of_messages(BATCH_SIZE)
+                .visibility_timeout(0)
+                .attribute_names(QueueAttributeName::All)
+                .message_attribute_names("All")
+                .send().await?
+                .messages
+                .unwrap_or_default();
+            messages.extend(received.into_iter().filter(|x| seen.insert(x.message_id.clone())));
+        }
+        let text = match messages.len() {
+            0 => "No messages visible".to_string(),
+            n => messages.iter()
+                .enumerate()
+                .map(|(i, x)| format!("Message {} of {}\n{}", i + 1, n, format_message(x)))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        Ok(ActionOutcome::Show { title: get_name(url).to_string(), text })
+    }
+
+    async fn send(&self, url: &str, text: &str) -> anyhow::Result<ActionOutcome> {
+        let message: Outgoing = serde_json::from_str(text).map_err(|x| anyhow::anyhow!("not a message: {}", x))?;
+        let mut attributes = HashMap::new();
+        for (name, value) in message.attributes {
+            let (kind, value) = match value {
+                Value::String(x) => ("String", x),
+                Value::Number(x) => ("Number", x.to_string()),
+                x => anyhow::bail!("attribute {} isn't a string or a number: {}", name, x),
+            };
+            attributes.insert(name, MessageAttributeValue::builder().data_type(kind).string_value(value).build());
+        }
+        let id = self.svc.get_client().await?.send_message()
+            .queue_url(url)
+            .message_body(message.body)
+            .set_message_attributes(Some(attributes).filter(|x| !x.is_empty()))
+            .delay_seconds(message.delay_seconds)
+            .set_message_group_id(message.group_id)
+            .set_message_deduplication_id(message.deduplication_id)
+            .send().await?
+            .message_id
+            .unwrap_or_default();
+        Ok(ActionOutcome::Status(format!("Sent message {}", id)))
+    }
+}
+
+#[async_trait]
+impl service::resource::ResourceController<Queue> for Queues {
+    async fn list(&self) -> anyhow::Result<Vec<Queue>> {
+        let urls = self.list_urls().await?;
+        let queues = futures::future::join_all(urls.iter().map(|x| self.get_queue(x))).await;
+        queues.into_iter().collect()
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Queue>>> {
+        let mut queue = self.get_queue(&id).await?;
+        queue.sources = self.list_sources(&id).await?;
+        Ok(Some(ResourceDescription::from_resource(&queue)))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'o', name: "Peek at messages" },
+            Action { key: 's', name: "Send a message" },
+            Action { key: 'P', name: "Purge" },
+            Action { key: 'R', name: "Redrive to source queue" },
+            Action { key: 'y', name: "Copy URL" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let url = id.ok_or_else(|| anyhow::anyhow!("no queue selected"))?;
+        match key {
+            'o' => self.peek(&url).await,
+            's' => {
+                let fifo = url.ends_with(".fifo");
+                let message = Outgoing {
+                    group_id: fifo.then(String::new),
+                    ..Default::default()
+                };
+                Ok(ActionOutcome::Edit { name: "message.json".to_string(), text: serde_json::to_string_pretty(&message)? })
+            }
+            'P' => Ok(ActionOutcome::confirm(&format!("Purge every message from {}?", get_name(&url)))),
+            'R' => {
+                let sources = self.list_sources(&url).await?;
+                let source = sources.first()
+                    .ok_or_else(|| anyhow::anyhow!("{} isn't the dead-letter queue of any queue", get_name(&url)))?;
+                Ok(ActionOutcome::Ask { prompt: "Move messages back to: ".to_string(), input: get_name(source).to_string() })
+            }
+            'y' => Ok(ActionOutcome::Copy(url)),
+            _ => anyhow::bail!("no action bound to '{}'", key),
+        }
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let url = id.ok_or_else(|| anyhow::anyhow!("no queue selected"))?;
+        match key {
+            's' => self.send(&url, &input).await,
+            'P' if !is_confirmed(&input) => Ok(ActionOutcome::Status("Not purged".to_string())),
+            'P' => {
+                self.svc.get_client().await?.purge_queue().queue_url(&url).send().await?;
+                Ok(ActionOutcome::Status(format!("Purging {}, which can take up to a minute", get_name(&url))))
+            }
+            'R' => {
+                let sources = self.list_sources(&url).await?;
+                let target = sources.iter()
+                    .find(|x| get_name(x) == input.trim())
+                    .ok_or_else(|| anyhow::anyhow!("{} isn't a source of {}", input.trim(), get_name(&url)))?;
+                let total = self.get_queue(&url).await?.visible.unwrap_or_default();
+                let client = self.svc.get_client().await?;
+                Ok(ActionOutcome::Transfer(vec![redrive_job(client, &url, target, total.max(0) as u64)]))
+            }
+            _ => anyhow::bail!("no input expected for '{}'", key),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Queue {
+    url: String,
+    fifo: bool,
+    visible: Option<i64>,
+    in_flight: Option<i64>,
+    delayed: Option<i64>,
+    /// ARN of the queue messages go to once received too many times.
+    dead_letter: Option<String>,
+    max_receives: Option<i64>,
+    /// URLs of the queues this is the dead-letter queue of.
+    sources: Vec<String>,
+    #[serde(deserialize_with = "fixtures::pairs")]
+    details: Vec<(String, String)>,
+}
+
+impl Queue {
+    fn new(url: &str, attributes: HashMap<QueueAttributeName, String>) -> Self {
+        let get = |name: QueueAttributeName| attributes.get(&name).cloned();
+        let count = |name: QueueAttributeName| get(name).and_then(|x| x.parse().ok());
+        // e.g. {"deadLetterTargetArn": "arn:aws:sqs:...:orders-dlq", "maxReceiveCount": 5}
+        let redrive = get(QueueAttributeName::RedrivePolicy)
+            .and_then(|x| serde_json::from_str::<Value>(&x).ok())
+            .unwrap_or_default();
+        let max_receives = match &redrive["maxReceiveCount"] {
+            Value::Number(x) => x.as_i64(),
+            Value::String(x) => x.parse().ok(),
+            _ => None,
+        };
+
+        let details = [
+            ("ARN", get(QueueAttributeName::QueueArn)),
+            ("Created", get(QueueAttributeName::CreatedTimestamp).and_then(|x| format_timestamp(&x, 1000))),
+            ("Visibility timeout", get(QueueAttributeName::VisibilityTimeout).map(|x| format!("{}s", x))),
+            ("Retention", get(QueueAttributeName::MessageRetentionPeriod).map(|x| format!("{}s", x))),
+            ("Delay", get(QueueAttributeName::DelaySeconds).map(|x| format!("{}s", x))),
+            ("Receive wait", get(QueueAttributeName::ReceiveMessageWaitTimeSeconds).map(|x| format!("{}s", x))),
+            ("Maximum size", get(QueueAttributeName::MaximumMessageSize).map(|x| format!("{} bytes", x))),
+            ("KMS key", get(QueueAttributeName::KmsMasterKeyId)),
+            ("Redrive allow policy", get(QueueAttributeName::RedriveAllowPolicy)),
+            ("Content-based deduplication", get(QueueAttributeName::ContentBasedDeduplication)),
+        ]
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect();
+
+        Queue {
+            url: url.to_string(),
+            fifo: get(QueueAttributeName::FifoQueue).as_deref() == Some("true"),
+            visible: count(QueueAttributeName::ApproximateNumberOfMessages),
+            in_flight: count(QueueAttributeName::ApproximateNumberOfMessagesNotVisible),
+            delayed: count(QueueAttributeName::ApproximateNumberOfMessagesDelayed),
+            dead_letter: redrive["deadLetterTargetArn"].as_str().map(|x| x.to_string()),
+            max_receives,
+            sources: vec![],
+            details,
+        }
+    }
+}
+
+impl Resource for Queue {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.url.clone()
+    }
+
+    fn get_name(&self) -> String {
+        get_name(&self.url).to_string()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Type", "Visible", "In flight", "Delayed", "Dead-letter queue"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        let count = |x: Option<i64>| x.map(|x| x.to_string()).unwrap_or_default();
+        let dead_letter = match (&self.dead_letter, self.max_receives) {
+            (Some(arn), Some(receives)) => format!("{} after {}", get_arn_name(arn), receives),
+            (Some(arn), None) => get_arn_name(arn).to_string(),
+            (None, _) => String::new(),
+        };
+        vec![
+            self.get_name(),
+            if self.fifo { "FIFO" } else { "Standard" }.to_string(),
+            count(self.visible),
+            count(self.in_flight),
+            count(self.delayed),
+            dead_letter,
+        ]
+    }
+
+    fn get_props(&self) -> HashMap<String, String> {
+        let mut props = props(Queue::get_headers(), self.get_columns());
+        props.insert("URL".to_string(), self.url.clone());
+        props.extend(self.details.clone());
+        props
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        let dead_letter = self.dead_letter.as_ref()
+            .and_then(|x| x.parse::<aws::Arn>().ok())
+            .and_then(|x| get_reference(&x))
+            .map(|x| Reference { label: "Dead-letter queue".to_string(), ..x });
+        dead_letter.into_iter()
+            .chain(self.sources.iter().map(|x| Reference::new("Source queue", SERVICE, "Queues", x.clone())))
+            .collect()
+    }
+}
+
+/// A message to send, as edited: the body, with attributes whose type follows from their
+/// value, and for FIFO queues the group.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Outgoing {
+    body: String,
+    attributes: BTreeMap<String, Value>,
+    delay_seconds: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deduplication_id: Option<String>,
+}
+
+fn redrive_job(client: aws_sdk_sqs::Client, from: &str, to: &str, total: u64) -> Job {
+    let (from, to) = (from.to_string(), to.to_string());
+    Job::new(format!("Redrive {} to {}", get_name(&from), get_name(&to)), move |progress| {
+        Box::pin(redrive(client.clone(), from.clone(), to.clone(), total, progress))
+    })
+        .with_unit(Unit::Items("messages"))
+}
+
+/// Move the messages in the dead-letter queue `from` back to `to`, a batch at a time, until
+/// it's empty. Each batch is deleted only once it has been sent, so a failure leaves messages
+/// in one queue or the other, not neither.
+async fn redrive(client: aws_sdk_sqs::Client, from: String, to: String, total: u64, progress: Arc<Progress>) -> anyhow::Result<()> {
+    progress.set_total(total);
+    let fifo = to.ends_with(".fifo");
+    loop {
+        let messages = client.receive_message()
+            .queue_url(&from)
+            .max_number_of_messages(BATCH_SIZE)
+            .visibility_timeout(REDRIVE_VISIBILITY)
+            .wait_time_seconds(1)
+            .attribute_names(QueueAttributeName::All)
+            .message_attribute_names("All")
+            .send().await?
+            .messages
+            .unwrap_or_default();
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let entries = messages.iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let system = x.attributes.clone().unwrap_or_default();
+                let deduplication = system.get(&MessageSystemAttributeName::MessageDeduplicationId).cloned()
+                    .or_else(|| x.message_id.clone());
+                SendMessageBatchRequestEntry::builder()
+                    .id(i.to_string())
+                    .set_message_body(x.body.clone())
+                    .set_message_attributes(x.message_attributes.clone())
+                    .set_message_group_id(system.get(&MessageSystemAttributeName::MessageGroupId).cloned())
+                    .set_message_deduplication_id(deduplication.filter(|_| fifo))
+                    .build()
+            })
+            .collect();
+        let output = client.send_message_batch()
+            .queue_url(&to)
+            .set_entries(Some(entries))
+            .send().await?;
+
+        let sent: Vec<DeleteMessageBatchRequestEntry> = output.successful
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| x.id?.parse::<usize>().ok())
+            .filter_map(|i| messages.get(i))
+            .enumerate()
+            .map(|(i, x)| DeleteMessageBatchRequestEntry::builder()
+                .id(i.to_string())
+                .set_receipt_handle(x.receipt_handle.clone())
+                .build())
+            .collect();
+        let count = sent.len() as u64;
+        if !sent.is_empty() {
+            client.delete_message_batch()
+                .queue_url(&from)
+                .set_entries(Some(sent))
+                .send().await?;
+        }
+        progress.add(count);
+        if let Some(failure) = output.failed.unwrap_or_default().into_iter().next() {
+            anyhow::bail!("couldn't send a message: {}", failure.message.unwrap_or_default());
+        }
+    }
+}
+
+/// A received message: when it was sent, how often it has been received, its attributes, and
+/// its body, laid out if it's JSON.
+fn format_message(message: &Message) -> String {
+    let system = message.attributes.clone().unwrap_or_default();
+    let mut lines = vec![format!("ID: {}", message.message_id.clone().unwrap_or_default())];
+    let mut system: Vec<(String, String)> = system.into_iter()
+        .map(|(k, v)| match k {
+            MessageSystemAttributeName::SentTimestamp | MessageSystemAttributeName::ApproximateFirstReceiveTimestamp => {
+                let v = format_timestamp(&v, 1).unwrap_or(v);
+                (k.as_str().to_string(), v)
+            }
+            k => (k.as_str().to_string(), v),
+        })
+        .collect();
+    system.sort();
+    lines.extend(system.into_iter().map(|(k, v)| format!("{}: {}", k, v)));
+
+    let mut attributes: Vec<_> = message.message_attributes.clone().unwrap_or_default().into_iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, value) in attributes {
+        let text = match (value.string_value, value.binary_value) {
+            (Some(x), _) => x,
+            (None, Some(x)) => base64::encode(x.as_ref()),
+            (None, None) => String::new(),
+        };
+        lines.push(format!("Attribute {} ({}): {}", name, value.data_type.unwrap_or_default(), text));
+    }
+
+    let body = message.body.clone().unwrap_or_default();
+    lines.push(String::new());
+    match serde_json::from_str::<Value>(&body) {
+        Ok(json @ (Value::Object(_) | Value::Array(_))) => lines.push(serde_json::to_string_pretty(&json).unwrap_or(body)),
+        _ => lines.push(body),
+    }
+    lines.join("\n")
+}
+
+/// A timestamp SQS gives as a count of units since the epoch, `1000` to a second for seconds,
+/// or `1` for milliseconds.
+fn format_timestamp(value: &str, scale: i64) -> Option<String> {
+    let millis = value.parse::<i64>().ok()? * scale;
+    Some(chrono::Utc.timestamp_millis_opt(millis).single()?.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+/// The queue's name, the last part of its URL.
+fn get_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// The queue's name, the last part of its ARN.
+fn get_arn_name(arn: &str) -> &str {
+    arn.rsplit(':').next().unwrap_or(arn)
+}
+
+/// The queue named by an SQS ARN, `arn:aws:sqs:eu-west-1:123456789012:orders`. Queues are
+/// known by their URL, which is made from the same parts.
+pub(crate) fn get_reference(arn: &aws::Arn) -> Option<Reference> {
+    if arn.service != "sqs" || arn.resource.contains(['/', ':']) {
+        return None;
+    }
+    let url = format!("https://sqs.{}.amazonaws.com/{}/{}", arn.region, arn.account, arn.resource);
+    Some(Reference::new("Queue", SERVICE, "Queues", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queues() {
+        let attributes = HashMap::from([
+            (QueueAttributeName::ApproximateNumberOfMessages, "12".to_string()),
+            (QueueAttributeName::FifoQueue, "true".to_string()),
+            (QueueAttributeName::RedrivePolicy, r#"{"deadLetterTargetArn":"arn:aws:sqs:eu-west-1:123456789012:orders-dlq.fifo","maxReceiveCount":5}"#.to_string()),
+        ]);
+        let queue = Queue::new("https://sqs.eu-west-1.amazonaws.com/123456789012/orders.fifo", attributes);
+        assert_eq!(queue.get_columns(), vec!["orders.fifo", "FIFO", "12", "", "", "orders-dlq.fifo after 5"]);
+        assert_eq!(queue.get_references(), vec![Reference::new(
+            "Dead-letter queue",
+            SERVICE,
+            "Queues",
+            "https://sqs.eu-west-1.amazonaws.com/123456789012/orders-dlq.fifo",
+        )]);
+    }
+
+    #[test]
+    fn messages() {
+        let message = Message::builder()
+            .message_id("m-1")
+            .body(r#"{"order": 7}"#)
+            .attributes(MessageSystemAttributeName::SentTimestamp, "1636104600000")
+            .attributes(MessageSystemAttributeName::ApproximateReceiveCount, "2")
+            .message_attributes("source", MessageAttributeValue::builder().data_type("String").string_value("web").build())
+            .build();
+        assert_eq!(format_message(&message), [
+            "ID: m-1",
+            "ApproximateReceiveCount: 2",
+            "SentTimestamp: 2021-11-05T09:30:00Z",
+            "Attribute source (String): web",
+            "",
+            "{\n  \"order\": 7\n}",
+        ].join("\n"));
+    }
+}
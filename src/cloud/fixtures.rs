@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::{Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
+use crate::service::{Context, Provider, ProviderKind};
+use crate::service::metrics::{Metric, Period, Series};
+use crate::service::registry::{Reader, Registry, ResourceDescriptor};
+use crate::service::resource::{AnyController, ErasedController, ResourceController, ResourceDescription, Row};
+
+/// Serves resources from files rather than the cloud, so the UI can run without credentials for
+/// demos and tests. Each resource type has a file under the directory, `<Service>/<Resource>`
+/// with a `.json`, `.yaml` or `.yml` extension, holding a list of resources:
+///
+/// ```yaml
+/// # Ec2/Instances.yaml
+/// - id: i-0abc
+///   name: web
+///   state: running
+///   tags: { Name: web, team: payments }
+/// ```
+pub struct FixtureProvider {
+    dir: PathBuf,
+}
+
+impl Provider for FixtureProvider {
+    fn new(context: &Context) -> anyhow::Result<Self> {
+        match &context.provider {
+            ProviderKind::Fixtures(dir) => Ok(FixtureProvider { dir: dir.clone() }),
+            ProviderKind::Cloud => anyhow::bail!("no fixture directory given"),
+        }
+    }
+}
+
+impl FixtureProvider {
+    /// The resources of type `resource` in `service`. Types without a file have none.
+    pub fn load<T>(&self, service: &str, resource: &str) -> anyhow::Result<Vec<T>>
+        where T: DeserializeOwned
+    {
+        let base = self.dir.join(service).join(resource);
+        for extension in ["json", "yaml", "yml"] {
+            let path = base.with_extension(extension);
+            if !path.exists() {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let items = match extension {
+                "json" => serde_json::from_str(&text).map_err(anyhow::Error::from),
+                _ => serde_yaml::from_str(&text).map_err(anyhow::Error::from),
+            };
+            return items.with_context(|| format!("parsing {}", path.display()));
+        }
+        Ok(vec![])
+    }
+}
+
+/// Serve resources from fixtures to contexts that ask for them, in place of each resource
+/// type's own controller.
+pub fn register(registry: &mut Registry) {
+    registry.add_factory(new_controller);
+}
+
+fn new_controller(context: &Context, service: &str, resource: &dyn ResourceDescriptor) -> Option<anyhow::Result<Box<dyn AnyController>>> {
+    if !matches!(context.provider, ProviderKind::Fixtures(_)) {
+        return None;
+    }
+    let ctrl = FixtureProvider::new(context).and_then(|provider| {
+        let read = resource.get_reader()
+            .ok_or_else(|| anyhow::anyhow!("{} {} can't be served from fixtures", service, resource.get_name()))?;
+        let ctrl = FixtureController {
+            provider,
+            service: service.to_string(),
+            resource: resource.get_name().to_string(),
+            headers: resource.get_headers(),
+            metrics: resource.get_metrics(),
+            read,
+            scope: None,
+        };
+        Ok(Box::new(ErasedController::new(ctrl)) as Box<dyn AnyController>)
+    });
+    Some(ctrl)
+}
+
+/// Stands in for a resource type's SDK controller, listing and describing the resources in its
+/// fixture file. Actions aren't available, and metrics have nothing reported.
+///
+/// Scoped listings treat IDs as paths: within `bucket/logs/`, the file's `bucket/logs/a.txt` and
+/// `bucket/logs/2021/` are listed, but not `bucket/logs/2021/b.txt`.
+pub struct FixtureController {
+    provider: FixtureProvider,
+    service: String,
+    resource: String,
+    headers: Vec<String>,
+    metrics: Vec<Metric>,
+    read: Reader,
+    scope: Option<String>,
+}
+
+impl FixtureController {
+    /// The resources in the fixture file, within the scope if there is one.
+    fn load(&self) -> anyhow::Result<Vec<(Row, ResourceDescription<Row>)>> {
+        let data: Vec<serde_json::Value> = self.provider.load(&self.service, &self.resource)?;
+        let resources = (self.read)(serde_json::Value::Array(data))
+            .with_context(|| format!("reading the {} {} fixtures", self.service, self.resource))?;
+        Ok(match &self.scope {
+            Some(scope) => resources.into_iter().filter(|(row, _)| is_child(scope, &row.id)).collect(),
+            None => resources,
+        })
+    }
+}
+
+#[async_trait]
+impl ResourceController<Row> for FixtureController {
+    async fn list(&self) -> anyhow::Result<Vec<Row>> {
+        Ok(self.load()?.into_iter().map(|(row, _)| row).collect())
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Row>>> {
+        Ok(self.load()?
+            .into_iter()
+            .find(|(row, _)| row.id == id)
+            .map(|(_, description)| description))
+    }
+
+    fn get_headers(&self) -> Vec<String> {
+        self.headers.clone()
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.scope = Some(scope);
+        Ok(())
+    }
+
+    /// The resource type's metrics, with nothing reported.
+    async fn metrics(&self, _id: String, period: Period) -> anyhow::Result<Vec<Series>> {
+        Ok(self.metrics.iter().map(|x| Series::new(*x, period, 0, vec![])).collect())
+    }
+}
+
+/// Whether the path `id` sits directly within `scope`.
+fn is_child(scope: &str, id: &str) -> bool {
+    match id.strip_prefix(scope) {
+        Some(rest) => !rest.is_empty() && !rest.trim_end_matches('/').contains('/'),
+        None => false,
+    }
+}
+
+/// Read key/value pairs, such as tags, from a map: `{ Name: web }` rather than `[[Name, web]]`.
+pub fn pairs<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
+    where D: Deserializer<'de>
+{
+    Ok(BTreeMap::<String, String>::deserialize(deserializer)?.into_iter().collect())
+}
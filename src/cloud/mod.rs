@@ -1 +1,2 @@
-pub(crate) mod aws;
\ No newline at end of file
+pub mod aws;
+pub mod fixtures;
\ No newline at end of file
@@ -0,0 +1,84 @@
+use crate::cloud::aws::Arn;
+
+/// Something typed at the `:` prompt.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Open a service's resource types, or go straight to a resource listing.
+    Open { service: String, resource: Option<String> },
+    /// Switch to a named profile.
+    Context(String),
+    Region(String),
+    Arn(Arn),
+}
+
+/// Everything the prompt can complete: registered services with their resource types, along with
+/// the profiles and regions available to `ctx` and `region`.
+pub(crate) struct Catalog {
+    pub(crate) services: Vec<(String, Vec<String>)>,
+    pub(crate) profiles: Vec<String>,
+    pub(crate) regions: Vec<String>,
+}
+
+const CONTEXT: &str = "ctx";
+const REGION: &str = "region";
+const ARN: &str = "arn";
+
+impl Catalog {
+    /// Parse `input`, matching service and resource type names case-insensitively.
+    pub(crate) fn parse(&self, input: &str) -> anyhow::Result<Command> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        match words.as_slice() {
+            [] => anyhow::bail!("empty command"),
+            [arn] if arn.starts_with("arn:") => Ok(Command::Arn(arn.parse()?)),
+            [ARN, arn] => Ok(Command::Arn(arn.parse()?)),
+            [CONTEXT, profile] => Ok(Command::Context(profile.to_string())),
+            [REGION, region] => Ok(Command::Region(region.to_string())),
+            [CONTEXT] | [REGION] | [ARN] => anyhow::bail!("{} needs an argument", words[0]),
+            [service, rest @ ..] if rest.len() <= 1 => {
+                let (service, resources) = self.services.iter()
+                    .find(|(x, _)| x.eq_ignore_ascii_case(service))
+                    .ok_or_else(|| anyhow::anyhow!("unknown service: {}", service))?;
+                let resource = match rest.first() {
+                    Some(resource) => Some(resources.iter()
+                        .find(|x| x.eq_ignore_ascii_case(resource))
+                        .ok_or_else(|| anyhow::anyhow!("unknown resource type: {} {}", service, resource))?
+                        .clone()),
+                    None => None,
+                };
+                Ok(Command::Open { service: service.clone(), resource })
+            }
+            _ => anyhow::bail!("unknown command: {}", input),
+        }
+    }
+
+    /// Candidates for the word being typed at the end of `input`, as full command lines.
+    pub(crate) fn complete(&self, input: &str) -> Vec<String> {
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        if input.is_empty() || input.ends_with(char::is_whitespace) {
+            words.push("");
+        }
+
+        let (partial, previous) = match words.split_last() {
+            Some((partial, previous)) => (partial.to_lowercase(), previous),
+            None => return vec![],
+        };
+        let candidates: Vec<String> = match previous {
+            [] => self.services.iter()
+                .map(|(x, _)| x.to_lowercase())
+                .chain([CONTEXT, REGION, ARN].iter().map(|x| x.to_string()))
+                .collect(),
+            [CONTEXT] => self.profiles.clone(),
+            [REGION] => self.regions.clone(),
+            [service] => self.services.iter()
+                .find(|(x, _)| x.eq_ignore_ascii_case(service))
+                .map(|(_, resources)| resources.iter().map(|x| x.to_lowercase()).collect())
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+
+        candidates.into_iter()
+            .filter(|x| x.to_lowercase().starts_with(&partial) && x.to_lowercase() != partial)
+            .map(|x| previous.iter().copied().chain(std::iter::once(x.as_str())).collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::Context;
+use serde::Deserialize;
+use crate::keymap::KeyAction;
+
+/// User settings, read from `$CLOUD_UI_CONFIG`, or `cloud-ui/config.toml` under the XDG config
+/// directory. Everything is optional, and a missing file leaves the defaults in place.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub services: ServicesConfig,
+    pub refresh: RefreshConfig,
+    /// Keys for each action, replacing the defaults. See `Keymap::configure`.
+    pub keys: HashMap<KeyAction, Vec<String>>,
+}
+
+/// Which registered services to offer.
+///
+/// ```toml
+/// [services]
+/// enabled = ["Ec2", "Kinesis"]
+/// disabled = ["Kinesis"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServicesConfig {
+    /// Only these services, if given. All of them otherwise.
+    pub enabled: Option<Vec<String>>,
+    /// Services to leave out, even if `enabled` lists them.
+    pub disabled: Vec<String>,
+}
+
+/// How often resource listings fetch themselves again, in seconds. Zero leaves a listing as it was
+/// first loaded. Resource types are named `Service/Resource`.
+///
+/// ```toml
+/// [refresh]
+/// interval = 60
+///
+/// [refresh.resources]
+/// "Ec2/Instances" = 5
+/// "Ec2/Images" = 0
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// For resource types not listed in `resources`.
+    pub interval: u64,
+    pub resources: HashMap<String, u64>,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig {
+            interval: 30,
+            resources: HashMap::new(),
+        }
+    }
+}
+
+impl RefreshConfig {
+    /// How often to refresh listings of `resource`, if at all.
+    pub fn get_interval(&self, service: &str, resource: &str) -> Option<Duration> {
+        let name = format!("{}/{}", service, resource);
+        let seconds = self.resources.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&name))
+            .map(|(_, v)| *v)
+            .unwrap_or(self.interval);
+        Some(Duration::from_secs(seconds)).filter(|x| !x.is_zero())
+    }
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Config> {
+        let path = match get_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+fn get_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("CLOUD_UI_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    Some(get_dir()?.join("config.toml"))
+}
+
+/// `cloud-ui` under the XDG config directory, where the config file and anything else kept
+/// between runs lives.
+pub fn get_dir() -> Option<PathBuf> {
+    let dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|x| PathBuf::from(x).join(".config")))
+        .ok()?;
+    Some(dir.join("cloud-ui"))
+}
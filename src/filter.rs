@@ -0,0 +1,221 @@
+use crate::service::resource::{LogEvent, Reference, Row};
+
+/// Something a list can be filtered on: the text fields shown for it, and any tags.
+pub trait Filterable {
+    fn get_fields(&self) -> Vec<String>;
+
+    fn get_tags(&self) -> &[(String, String)] {
+        &[]
+    }
+}
+
+impl Filterable for String {
+    fn get_fields(&self) -> Vec<String> {
+        vec![self.clone()]
+    }
+}
+
+impl Filterable for Row {
+    fn get_fields(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn get_tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+}
+
+impl Filterable for LogEvent {
+    fn get_fields(&self) -> Vec<String> {
+        vec![self.message.clone(), self.source.clone()]
+    }
+}
+
+impl Filterable for Reference {
+    fn get_fields(&self) -> Vec<String> {
+        vec![self.label.clone(), format!("{}/{} {}", self.service, self.resource, self.id)]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Term {
+    /// Fuzzy match against any field.
+    Any(String),
+    /// Fuzzy match against the field at this index, from `column:value`.
+    Column(usize, String),
+    /// `tag:key` or `tag:key=value`. Keys match exactly, ignoring case, and values by substring.
+    Tag(String, Option<String>),
+}
+
+/// A filter typed at the `/` prompt. Whitespace separated terms must all match.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub input: String,
+    terms: Vec<Term>,
+}
+
+impl Filter {
+    /// Parse `input`, resolving `column:value` qualifiers against `headers`. Qualifiers that don't
+    /// name a column are matched as plain text.
+    pub fn parse<S>(input: &str, headers: &[S]) -> Filter
+        where S: AsRef<str>
+    {
+        let terms = input.split_whitespace()
+            .filter_map(|word| {
+                let (qualifier, value) = match word.split_once(':') {
+                    Some((qualifier, value)) if !qualifier.is_empty() => (qualifier, value),
+                    _ => return Some(Term::Any(word.to_lowercase())),
+                };
+                if qualifier.eq_ignore_ascii_case("tag") {
+                    let (key, value) = match value.split_once('=') {
+                        Some((key, value)) => (key, Some(value.to_lowercase())),
+                        None => (value, None),
+                    };
+                    // a tag with no key yet, as while it's being typed, leaves the list as it is
+                    return (!key.is_empty()).then(|| Term::Tag(key.to_lowercase(), value));
+                }
+                let qualifier = normalize(qualifier);
+                match headers.iter().position(|x| normalize(x.as_ref()).starts_with(&qualifier)) {
+                    Some(idx) => Some(Term::Column(idx, value.to_lowercase())),
+                    None => Some(Term::Any(word.to_lowercase())),
+                }
+            })
+            .collect();
+        Filter {
+            input: input.to_string(),
+            terms,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// If `item` passes the filter, the positions of matched characters within each of its fields.
+    pub fn matches<T>(&self, item: &T) -> Option<Vec<Vec<usize>>>
+        where T: Filterable + ?Sized
+    {
+        let fields = item.get_fields();
+        let mut highlights = vec![vec![]; fields.len()];
+        for term in &self.terms {
+            match term {
+                Term::Any(pattern) => {
+                    let (idx, positions) = fields.iter()
+                        .enumerate()
+                        .find_map(|(idx, field)| fuzzy_match(pattern, field).map(|x| (idx, x)))?;
+                    highlights[idx].extend(positions);
+                }
+                Term::Column(idx, pattern) => {
+                    let positions = fuzzy_match(pattern, fields.get(*idx)?)?;
+                    highlights[*idx].extend(positions);
+                }
+                Term::Tag(key, value) => {
+                    item.get_tags()
+                        .iter()
+                        .find(|(k, v)| k.to_lowercase() == *key
+                            && value.as_ref().map(|value| v.to_lowercase().contains(value)).unwrap_or(true))?;
+                }
+            }
+        }
+        Some(highlights)
+    }
+}
+
+/// Header names are matched without case, spaces or punctuation, so `root-device:ebs` finds the
+/// "Root device" column.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Character positions in `text` matching `pattern`, ignoring case. A contiguous match is
+/// preferred, falling back to the first subsequence match.
+fn fuzzy_match(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    let chars: Vec<char> = text.chars().flat_map(|c| c.to_lowercase().next()).collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    if pattern.is_empty() {
+        return Some(vec![]);
+    }
+
+    if let Some(start) = chars.windows(pattern.len()).position(|x| x == pattern.as_slice()) {
+        return Some((start..start + pattern.len()).collect());
+    }
+
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut remaining = pattern.iter().peekable();
+    for (idx, c) in chars.iter().enumerate() {
+        match remaining.peek() {
+            Some(p) if *p == c => {
+                positions.push(idx);
+                remaining.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    match remaining.peek() {
+        None => Some(positions),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADERS: &[&str] = &["Name", "Root device", "State"];
+
+    fn row(columns: &[&str], tags: &[(&str, &str)]) -> Row {
+        Row {
+            id: columns[0].to_string(),
+            name: columns[0].to_string(),
+            columns: columns.iter().map(|x| x.to_string()).collect(),
+            tags: tags.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            contents: None,
+        }
+    }
+
+    #[test]
+    fn fuzzy() {
+        assert_eq!(fuzzy_match("web", "Web-Server"), Some(vec![0, 1, 2]));
+        assert_eq!(fuzzy_match("wsv", "web-server"), Some(vec![0, 4, 7]));
+        assert_eq!(fuzzy_match("wx", "web-server"), None);
+
+        let filter = Filter::parse("wbsrv", HEADERS);
+        assert_eq!(filter.matches(&row(&["web-server", "ebs", "running"], &[])), Some(vec![vec![0, 2, 4, 6, 7], vec![], vec![]]));
+        assert_eq!(filter.matches(&row(&["worker", "ebs", "running"], &[])), None);
+    }
+
+    #[test]
+    fn columns() {
+        let item = row(&["web", "ebs", "stopped"], &[]);
+        // headers match without case or punctuation, from their start
+        assert_eq!(Filter::parse("ROOT-DEVICE:ebs", HEADERS).matches(&item), Some(vec![vec![], vec![0, 1, 2], vec![]]));
+        assert_eq!(Filter::parse("st:stop", HEADERS).matches(&item).map(|x| x[2].len()), Some(4));
+        assert_eq!(Filter::parse("state:ebs", HEADERS).matches(&item), None);
+
+        // an unknown column is matched as text, qualifier and all
+        let filter = Filter::parse("zone:a", HEADERS);
+        assert_eq!(filter.terms, vec![Term::Any("zone:a".to_string())]);
+        assert_eq!(filter.matches(&item), None);
+        assert!(filter.matches(&row(&["zone:a-web", "ebs", "running"], &[])).is_some());
+    }
+
+    #[test]
+    fn tags() {
+        let item = row(&["web", "ebs", "running"], &[("Team", "Payments"), ("env", "prod")]);
+        assert!(Filter::parse("tag:team", HEADERS).matches(&item).is_some());
+        assert!(Filter::parse("tag:owner", HEADERS).matches(&item).is_none());
+        assert!(Filter::parse("tag:team=pay", HEADERS).matches(&item).is_some());
+        assert!(Filter::parse("tag:team=search", HEADERS).matches(&item).is_none());
+        assert!(Filter::parse("tag:env=prod web", HEADERS).matches(&item).is_some());
+        assert!(Filter::parse("tag:env=prod db", HEADERS).matches(&item).is_none());
+
+        // until a key is typed there's nothing to match on
+        let filter = Filter::parse("tag: web", HEADERS);
+        assert_eq!(filter.terms, vec![Term::Any("web".to_string())]);
+        assert!(filter.matches(&row(&["web", "ebs", "running"], &[])).is_some());
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::fmt;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Something a key can be bound to, named in the config file in kebab case, e.g. `page-down`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAction {
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    /// The first item, or with a count, the item at that line.
+    First,
+    /// The last item, or with a count, the item at that line.
+    Last,
+    Select,
+    /// Open the detail view, for rows that would otherwise open a listing.
+    Describe,
+    Unselect,
+    /// Clear the filter, or go back to the previous view.
+    Back,
+    Command,
+    Filter,
+    Arn,
+    Errors,
+    Transfers,
+    Pause,
+    Help,
+    Quit,
+}
+
+impl KeyAction {
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            KeyAction::Down => "Move down",
+            KeyAction::Up => "Move up",
+            KeyAction::PageDown => "Page down",
+            KeyAction::PageUp => "Page up",
+            KeyAction::HalfPageDown => "Half a page down",
+            KeyAction::HalfPageUp => "Half a page up",
+            KeyAction::First => "First item, or item N",
+            KeyAction::Last => "Last item, or item N",
+            KeyAction::Select => "Open the selected item",
+            KeyAction::Describe => "Describe the selected item",
+            KeyAction::Unselect => "Clear the selection",
+            KeyAction::Back => "Clear the filter, or go back",
+            KeyAction::Command => "Run a command",
+            KeyAction::Filter => "Filter the list",
+            KeyAction::Arn => "Jump to an ARN",
+            KeyAction::Errors => "Show the error log",
+            KeyAction::Transfers => "Show the transfer queue",
+            KeyAction::Pause => "Pause or resume refreshing",
+            KeyAction::Help => "Show this help",
+            KeyAction::Quit => "Quit",
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: &[(KeyAction, &[&str])] = &[
+    (KeyAction::Down, &["j", "down"]),
+    (KeyAction::Up, &["k", "up"]),
+    (KeyAction::PageDown, &["pagedown"]),
+    (KeyAction::PageUp, &["pageup"]),
+    (KeyAction::HalfPageDown, &["ctrl-d"]),
+    (KeyAction::HalfPageUp, &["ctrl-u"]),
+    (KeyAction::First, &["gg", "home"]),
+    (KeyAction::Last, &["G", "end"]),
+    (KeyAction::Select, &["enter"]),
+    (KeyAction::Describe, &["i"]),
+    (KeyAction::Unselect, &["h", "left"]),
+    (KeyAction::Back, &["esc"]),
+    (KeyAction::Command, &[":"]),
+    (KeyAction::Filter, &["/"]),
+    (KeyAction::Arn, &["@"]),
+    (KeyAction::Errors, &["!"]),
+    (KeyAction::Transfers, &["t"]),
+    (KeyAction::Pause, &["p"]),
+    (KeyAction::Help, &["?"]),
+    (KeyAction::Quit, &["q", "ctrl-c"]),
+];
+
+/// Keys with names, as written in the config file. Anything else is a single character.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("space", KeyCode::Char(' ')),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+];
+
+/// A single key press, with the modifiers that matter for bindings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+    /// Shift is dropped from characters, since it's already in the character: `G`, not `shift-g`.
+    fn from(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Key { code: event.code, modifiers }
+    }
+}
+
+impl Key {
+    pub fn char(c: char) -> Self {
+        Key { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE }
+    }
+
+    /// A plain character, without control or alt.
+    pub fn get_char(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => match self.code {
+                KeyCode::Char(c) => write!(f, "{}", c),
+                KeyCode::F(n) => write!(f, "f{}", n),
+                other => write!(f, "{:?}", other),
+            },
+        }
+    }
+}
+
+/// Parse a key sequence such as `gg`, `ctrl-d` or `g home`. Words are separated by spaces, and
+/// each is a named key, with any `ctrl-`, `alt-` or `shift-` in front, or otherwise a run of
+/// characters typed one after another.
+pub fn parse_keys(text: &str) -> anyhow::Result<Vec<Key>> {
+    let mut keys = vec![];
+    for word in text.split_whitespace() {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = word;
+        loop {
+            let (tail, modifier) = match rest.split_once('-') {
+                Some((prefix, tail)) if !tail.is_empty() => match prefix.to_lowercase().as_str() {
+                    "ctrl" => (tail, KeyModifiers::CONTROL),
+                    "alt" => (tail, KeyModifiers::ALT),
+                    "shift" => (tail, KeyModifiers::SHIFT),
+                    _ => break,
+                },
+                _ => break,
+            };
+            modifiers |= modifier;
+            rest = tail;
+        }
+
+        let named = NAMED_KEYS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+            .map(|(_, code)| *code);
+        let function = rest.strip_prefix(['f', 'F'])
+            .and_then(|x| x.parse::<u8>().ok())
+            .filter(|x| (1..=12).contains(x))
+            .map(KeyCode::F);
+        match named.or(function) {
+            Some(code) => keys.push(Key { code, modifiers }),
+            None if modifiers.is_empty() => keys.extend(rest.chars().map(Key::char)),
+            None if rest.chars().count() == 1 => {
+                // characters are bound lowercase with ctrl, as terminals report them
+                let c = rest.chars().next().unwrap_or_default().to_ascii_lowercase();
+                keys.push(Key { code: KeyCode::Char(c), modifiers: modifiers - KeyModifiers::SHIFT })
+            }
+            None => anyhow::bail!("unknown key: {}", word),
+        }
+    }
+    if keys.is_empty() {
+        anyhow::bail!("empty key binding");
+    }
+    Ok(keys)
+}
+
+/// Format a key sequence the way `parse_keys` reads it.
+pub fn format_keys(keys: &[Key]) -> String {
+    let mut text = String::new();
+    let mut previous_char = false;
+    for key in keys {
+        let is_char = key.get_char().filter(|c| !c.is_whitespace()).is_some();
+        // runs of characters are written together, as in `gg`
+        let joined = is_char && previous_char;
+        if !text.is_empty() && !joined {
+            text.push(' ');
+        }
+        text.push_str(&key.to_string());
+        previous_char = is_char;
+    }
+    text
+}
+
+/// What a key press came to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// A bound action, with the count typed before it, if any.
+    Action(KeyAction, Option<usize>),
+    /// Part of a count or a longer binding; wait for more.
+    Pending,
+    /// Not bound to anything, so free for views to use.
+    Unbound(Key),
+}
+
+/// Turns key presses into actions, following vim: a count may come first, as in `5j`, and
+/// bindings may take several keys, as in `gg`.
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, KeyAction)>,
+    /// Keys typed so far towards a longer binding.
+    pending: Vec<Key>,
+    count: Option<usize>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS.iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |x| (parse_keys(x).expect("default binding"), *action)))
+            .collect();
+        Keymap {
+            bindings,
+            pending: vec![],
+            count: None,
+        }
+    }
+}
+
+impl Keymap {
+    /// Replace the default keys of each action in `overrides`. An empty list unbinds it.
+    ///
+    /// ```toml
+    /// [keys]
+    /// down = ["j", "down", "ctrl-n"]
+    /// first = ["home"]
+    /// ```
+    pub fn configure(&mut self, overrides: &HashMap<KeyAction, Vec<String>>) -> anyhow::Result<()> {
+        for (action, keys) in overrides {
+            let keys = keys.iter()
+                .map(|x| parse_keys(x).map_err(|err| anyhow::anyhow!("binding {:?}: {}", action, err)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            self.bindings.retain(|(_, x)| x != action);
+            self.bindings.extend(keys.into_iter().map(|x| (x, *action)));
+        }
+        Ok(())
+    }
+
+    /// Take a key press, returning what it amounts to so far.
+    pub fn feed(&mut self, key: Key) -> Outcome {
+        // digits make a count, though a leading zero is a key like any other
+        if let (true, Some(digit)) = (self.pending.is_empty(), key.get_char().and_then(|c| c.to_digit(10))) {
+            if digit != 0 || self.count.is_some() {
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+                return Outcome::Pending;
+            }
+        }
+
+        self.pending.push(key);
+        let is_prefix = self.bindings.iter().any(|(keys, _)| keys.len() > self.pending.len() && keys.starts_with(&self.pending));
+        if is_prefix {
+            return Outcome::Pending;
+        }
+        let action = self.bindings.iter()
+            .find(|(keys, _)| *keys == self.pending)
+            .map(|(_, action)| *action);
+        let typed = std::mem::take(&mut self.pending);
+        let count = self.count.take();
+        match action {
+            Some(action) => Outcome::Action(action, count),
+            // a single key can still mean something to the view, but a broken sequence can't
+            None if typed.len() == 1 => Outcome::Unbound(key),
+            None => Outcome::Pending,
+        }
+    }
+
+    /// Forget a half typed count or binding.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// What has been typed towards the next action, such as `5g`.
+    pub fn get_pending(&self) -> String {
+        let count = self.count.map(|x| x.to_string()).unwrap_or_default();
+        format!("{}{}", count, format_keys(&self.pending))
+    }
+
+    /// The keys bound to `action`, formatted, in the order they were bound.
+    pub fn get_keys(&self, action: KeyAction) -> Vec<String> {
+        self.bindings.iter()
+            .filter(|(_, x)| *x == action)
+            .map(|(keys, _)| format_keys(keys))
+            .collect()
+    }
+
+    /// Every bound action along with its keys, in the order of the defaults, for help.
+    pub fn get_bindings(&self) -> Vec<(KeyAction, String)> {
+        DEFAULT_BINDINGS.iter()
+            .map(|(action, _)| (*action, self.get_keys(*action).join(", ")))
+            .filter(|(_, keys)| !keys.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctrl(c: char) -> Key {
+        Key { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }
+    }
+
+    fn feed_str(keymap: &mut Keymap, text: &str) -> Vec<Outcome> {
+        text.chars().map(|c| keymap.feed(Key::char(c))).collect()
+    }
+
+    #[test]
+    fn parse_named_and_modified_keys() {
+        assert_eq!(parse_keys("ctrl-d").unwrap(), vec![ctrl('d')]);
+        assert_eq!(parse_keys("CTRL-D").unwrap(), vec![ctrl('d')]);
+        assert_eq!(parse_keys("pagedown").unwrap(), vec![Key { code: KeyCode::PageDown, modifiers: KeyModifiers::NONE }]);
+        assert_eq!(parse_keys("f5").unwrap(), vec![Key { code: KeyCode::F(5), modifiers: KeyModifiers::NONE }]);
+        assert_eq!(parse_keys("alt-enter").unwrap(), vec![Key { code: KeyCode::Enter, modifiers: KeyModifiers::ALT }]);
+        assert_eq!(parse_keys("-").unwrap(), vec![Key::char('-')]);
+    }
+
+    #[test]
+    fn parse_sequences() {
+        assert_eq!(parse_keys("gg").unwrap(), vec![Key::char('g'), Key::char('g')]);
+        assert_eq!(parse_keys("g home").unwrap()[1].code, KeyCode::Home);
+        assert!(parse_keys("").is_err());
+        assert!(parse_keys("ctrl-nope").is_err());
+    }
+
+    #[test]
+    fn format_round_trips() {
+        for text in ["gg", "ctrl-d", "G", "g home", "space", "alt-x"] {
+            assert_eq!(format_keys(&parse_keys(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn shift_is_dropped_from_characters() {
+        let key = Key::from(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT));
+        assert_eq!(key, Key::char('G'));
+    }
+
+    #[test]
+    fn single_keys() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.feed(Key::char('j')), Outcome::Action(KeyAction::Down, None));
+        assert_eq!(keymap.feed(Key::char('k')), Outcome::Action(KeyAction::Up, None));
+        assert_eq!(keymap.feed(ctrl('d')), Outcome::Action(KeyAction::HalfPageDown, None));
+        assert_eq!(keymap.feed(Key::char('x')), Outcome::Unbound(Key::char('x')));
+    }
+
+    #[test]
+    fn sequences() {
+        let mut keymap = Keymap::default();
+        assert_eq!(feed_str(&mut keymap, "gg"), vec![Outcome::Pending, Outcome::Action(KeyAction::First, None)]);
+        assert_eq!(keymap.get_pending(), "");
+        // a broken sequence is dropped, rather than running its last key
+        assert_eq!(feed_str(&mut keymap, "gj"), vec![Outcome::Pending, Outcome::Pending]);
+        assert_eq!(keymap.feed(Key::char('j')), Outcome::Action(KeyAction::Down, None));
+    }
+
+    #[test]
+    fn counts() {
+        let mut keymap = Keymap::default();
+        assert_eq!(feed_str(&mut keymap, "5j"), vec![Outcome::Pending, Outcome::Action(KeyAction::Down, Some(5))]);
+        assert_eq!(feed_str(&mut keymap, "12G").last(), Some(&Outcome::Action(KeyAction::Last, Some(12))));
+        assert_eq!(feed_str(&mut keymap, "10gg").last(), Some(&Outcome::Action(KeyAction::First, Some(10))));
+        feed_str(&mut keymap, "3g");
+        assert_eq!(keymap.get_pending(), "3g");
+        keymap.reset();
+        assert_eq!(keymap.get_pending(), "");
+        assert_eq!(keymap.feed(Key::char('0')), Outcome::Unbound(Key::char('0')));
+    }
+
+    #[test]
+    fn the_count_goes_with_the_action() {
+        let mut keymap = Keymap::default();
+        feed_str(&mut keymap, "3j");
+        assert_eq!(keymap.feed(Key::char('j')), Outcome::Action(KeyAction::Down, None));
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        let mut keymap = Keymap::default();
+        let overrides = HashMap::from([
+            (KeyAction::Down, vec!["ctrl-n".to_string()]),
+            (KeyAction::Quit, vec![]),
+        ]);
+        keymap.configure(&overrides).unwrap();
+        assert_eq!(keymap.feed(ctrl('n')), Outcome::Action(KeyAction::Down, None));
+        assert_eq!(keymap.feed(Key::char('j')), Outcome::Unbound(Key::char('j')));
+        assert_eq!(keymap.feed(Key::char('q')), Outcome::Unbound(Key::char('q')));
+        assert_eq!(keymap.get_keys(KeyAction::Down), vec!["ctrl-n"]);
+        assert_eq!(keymap.get_keys(KeyAction::Up), vec!["k", "up"]);
+    }
+
+    #[test]
+    fn bad_overrides_are_an_error() {
+        let mut keymap = Keymap::default();
+        let overrides = HashMap::from([(KeyAction::Down, vec!["ctrl-nope".to_string()])]);
+        assert!(keymap.configure(&overrides).is_err());
+        assert_eq!(keymap.get_keys(KeyAction::Down), vec!["j", "down"]);
+    }
+}
@@ -0,0 +1,30 @@
+//! A terminal UI for browsing cloud resources.
+//!
+//! Services are looked up at runtime in a `Registry`, so crates depending on this one can add
+//! their own alongside, or instead of, the AWS services in `cloud::aws`:
+//!
+//! ```ignore
+//! let mut registry = Registry::default();
+//! cloud_ui::cloud::aws::register(&mut registry);
+//! registry.register(Box::new(ServiceType::new("Pipelines").resource("Stages", Stages::new)));
+//! cloud_ui::run(App::new(registry)).await?;
+//! ```
+//!
+//! `examples/custom_service.rs` has a complete service.
+
+pub mod ui;
+pub mod app;
+pub mod service;
+pub mod cloud;
+pub mod filter;
+pub mod list;
+pub mod keymap;
+pub mod config;
+pub mod transfer;
+mod command;
+
+pub use app::{run, App};
+pub use service::{Context, Provider, Service};
+pub use service::registry::{Registry, ResourceDescriptor, ServiceDescriptor, ServiceType};
+pub use service::resource::{Resource, ResourceController, ResourceDescription};
+pub use ui::Ui;
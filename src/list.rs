@@ -0,0 +1,539 @@
+use std::ops::Range;
+use tui::widgets::ListState;
+use crate::filter::{Filter, Filterable};
+
+/// Rows moved by a page until the list has been drawn, and knows how many it shows.
+const DEFAULT_PAGE: usize = 10;
+
+/// A list of items along with the selection and filter applied to it. Only `visible` items are
+/// shown, and the selection indexes into them rather than into `items`. Everything is safe on an
+/// empty list, which simply has nothing selected.
+pub struct StatefulList<T> {
+    pub state: ListState,
+    pub items: Vec<T>,
+    /// Indices into `items` of those passing `filter`, in order.
+    pub visible: Vec<usize>,
+    pub filter: Filter,
+    /// Rows shown at once, for paging. Set as the list is drawn.
+    pub page: usize,
+    /// Visible items scrolled past, above the first one drawn.
+    pub offset: usize,
+    /// Identifies items across `replace`, so that the selection follows an item as it moves.
+    key: Option<fn(&T) -> String>,
+}
+
+impl<T> StatefulList<T> {
+    pub fn with_items(items: Vec<T>) -> StatefulList<T> {
+        StatefulList {
+            state: ListState::default(),
+            visible: (0..items.len()).collect(),
+            items,
+            filter: Filter::default(),
+            page: DEFAULT_PAGE,
+            offset: 0,
+            key: None,
+        }
+    }
+
+    /// Identify items by `key`, so that `replace` keeps the same one selected.
+    pub fn with_key(mut self, key: fn(&T) -> String) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Number of visible items.
+    pub fn len(&self) -> usize {
+        self.visible.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visible.is_empty()
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.state.selected()
+            .and_then(|i| self.visible.get(i))
+            .map(|i| &self.items[*i])
+    }
+
+    /// Select the first visible item satisfying `predicate`, if any.
+    pub fn select_where<P>(&mut self, predicate: P)
+        where P: Fn(&T) -> bool
+    {
+        let idx = self.visible.iter().position(|i| predicate(&self.items[*i]));
+        self.state.select(idx);
+    }
+
+    /// Select the visible item identified by `key`, leaving the selection alone if there isn't
+    /// one. Returns whether it was found.
+    pub fn select_key(&mut self, key: &str) -> bool {
+        let idx = match self.key {
+            Some(get_key) => self.visible.iter().position(|i| get_key(&self.items[*i]) == key),
+            None => None,
+        };
+        if idx.is_some() {
+            self.state.select(idx);
+        }
+        idx.is_some()
+    }
+
+    pub fn get_visible(&self) -> impl Iterator<Item = &T> {
+        self.visible.iter().map(move |i| &self.items[*i])
+    }
+
+    /// Scroll as little as needed to bring the selection into view, with `rows` items shown at
+    /// once, returning the range of visible items to draw.
+    pub fn scroll(&mut self, rows: usize) -> Range<usize> {
+        self.page = rows;
+        let rows = rows.max(1);
+        let len = self.visible.len();
+        self.offset = self.offset.min(len.saturating_sub(1));
+        match self.state.selected() {
+            Some(i) if i < self.offset => self.offset = i,
+            Some(i) if i >= self.offset + rows => self.offset = i + 1 - rows,
+            _ => {}
+        }
+        self.offset..(self.offset + rows).min(len)
+    }
+
+    /// Selection state for drawing just the items `scroll` returned.
+    pub fn get_window_state(&self) -> ListState {
+        let mut state = ListState::default();
+        state.select(self.state.selected().map(|i| i.saturating_sub(self.offset)));
+        state
+    }
+
+    /// The visible item `row` items down from the top of what was drawn, if there is one there.
+    pub fn get_index_at(&self, row: usize) -> Option<usize> {
+        Some(self.offset + row).filter(|i| *i < self.visible.len())
+    }
+
+    fn get_selected_key(&self) -> Option<String> {
+        self.key.zip(self.selected()).map(|(get_key, item)| get_key(item))
+    }
+
+    /// Select the item at `idx`, or the last if there aren't that many.
+    fn select_clamped(&mut self, idx: usize) {
+        let idx = match self.visible.len() {
+            0 => None,
+            len => Some(idx.min(len - 1)),
+        };
+        self.state.select(idx);
+    }
+}
+
+impl<T> StatefulList<T>
+    where T: Filterable
+{
+    /// Swap in a fresh set of items. The selected item stays selected if it's still there, as
+    /// identified by the list's key, and otherwise the selection stays where it was, within bounds.
+    pub fn replace(&mut self, items: Vec<T>) {
+        let key = self.get_selected_key();
+        self.items = items;
+        self.refilter();
+        let found = key.map(|key| self.select_key(&key)).unwrap_or(false);
+        if let (false, Some(i)) = (found, self.state.selected()) {
+            self.select_clamped(i);
+        }
+    }
+
+    /// Apply `filter`, keeping the selected item selected if it still passes, or otherwise
+    /// moving the selection to the first item that does.
+    pub fn set_filter(&mut self, filter: Filter) {
+        let selected = self.state.selected().and_then(|i| self.visible.get(i).copied());
+        self.filter = filter;
+        self.refilter();
+        let idx = match selected {
+            Some(selected) => self.visible.iter().position(|i| *i == selected).or(Some(0)),
+            None => None,
+        };
+        self.state.select(idx.filter(|_| !self.visible.is_empty()));
+    }
+
+    fn refilter(&mut self) {
+        let filter = &self.filter;
+        self.visible = self.items.iter()
+            .enumerate()
+            .filter(|(_, x)| filter.matches(*x).is_some())
+            .map(|(i, _)| i)
+            .collect();
+    }
+}
+
+/// Moving the selection through a list, whatever it holds, so that key handling can work on
+/// whichever list the current view shows.
+pub trait Navigable {
+    /// The next item, wrapping around to the first.
+    fn next(&mut self);
+
+    /// The previous item, wrapping around to the last.
+    fn previous(&mut self);
+
+    /// A page further down, stopping at the last item.
+    fn page_down(&mut self);
+
+    /// A page further up, stopping at the first item.
+    fn page_up(&mut self);
+
+    fn first(&mut self);
+
+    fn last(&mut self);
+
+    /// `delta` items further down, or up if negative, stopping at either end.
+    fn move_by(&mut self, delta: isize);
+
+    /// The item at `idx`, or the last if there aren't that many.
+    fn select_index(&mut self, idx: usize);
+
+    /// Rows shown at once.
+    fn get_page(&self) -> usize;
+
+    fn unselect(&mut self);
+}
+
+impl<T> Navigable for StatefulList<T> {
+    fn next(&mut self) {
+        let idx = match self.state.selected() {
+            Some(i) if i + 1 < self.visible.len() => i + 1,
+            _ => 0,
+        };
+        self.select_clamped(idx);
+    }
+
+    fn previous(&mut self) {
+        let idx = match self.state.selected() {
+            Some(0) => self.visible.len().saturating_sub(1),
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.select_clamped(idx);
+    }
+
+    fn page_down(&mut self) {
+        self.move_by(self.page.max(1) as isize);
+    }
+
+    fn page_up(&mut self) {
+        self.move_by(-(self.page.max(1) as isize));
+    }
+
+    fn first(&mut self) {
+        self.select_clamped(0);
+    }
+
+    fn last(&mut self) {
+        self.select_clamped(usize::MAX);
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let idx = match self.state.selected() {
+            Some(i) => i.saturating_add_signed(delta),
+            None => 0,
+        };
+        self.select_clamped(idx);
+    }
+
+    fn select_index(&mut self, idx: usize) {
+        self.select_clamped(idx);
+    }
+
+    fn get_page(&self) -> usize {
+        self.page
+    }
+
+    fn unselect(&mut self) {
+        self.state.select(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> StatefulList<String> {
+        StatefulList::with_items(items.iter().map(|x| x.to_string()).collect())
+    }
+
+    /// Items are `name:stock`, identified by name.
+    fn keyed(items: &[&str]) -> StatefulList<String> {
+        list(items).with_key(|x| x.split(':').next().unwrap_or_default().to_string())
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn selected(list: &StatefulList<String>) -> Option<&str> {
+        list.selected().map(|x| x.as_str())
+    }
+
+    #[test]
+    fn empty_list_never_selects() {
+        let mut list = list(&[]);
+        let moves: [fn(&mut StatefulList<String>); 8] = [
+            |x| x.next(),
+            |x| x.previous(),
+            |x| x.page_down(),
+            |x| x.page_up(),
+            |x| x.first(),
+            |x| x.last(),
+            |x| x.select_index(3),
+            |x| x.move_by(-2),
+        ];
+        for f in moves {
+            f(&mut list);
+            assert_eq!(list.state.selected(), None);
+            assert_eq!(list.selected(), None);
+        }
+        assert!(list.is_empty());
+        assert!(!list.select_key("a"));
+    }
+
+    #[test]
+    fn next_wraps() {
+        let mut list = list(&["a", "b", "c"]);
+        list.next();
+        assert_eq!(selected(&list), Some("a"));
+        list.next();
+        list.next();
+        assert_eq!(selected(&list), Some("c"));
+        list.next();
+        assert_eq!(selected(&list), Some("a"));
+    }
+
+    #[test]
+    fn previous_wraps() {
+        let mut list = list(&["a", "b", "c"]);
+        list.previous();
+        assert_eq!(selected(&list), Some("a"));
+        list.previous();
+        assert_eq!(selected(&list), Some("c"));
+        list.previous();
+        assert_eq!(selected(&list), Some("b"));
+    }
+
+    #[test]
+    fn single_item_stays_selected() {
+        let mut list = list(&["a"]);
+        list.next();
+        list.next();
+        assert_eq!(selected(&list), Some("a"));
+        list.previous();
+        assert_eq!(selected(&list), Some("a"));
+    }
+
+    #[test]
+    fn paging_stops_at_the_ends() {
+        let items: Vec<String> = (0..25).map(|x| x.to_string()).collect();
+        let mut list = StatefulList::with_items(items);
+        list.page = 10;
+
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(0));
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(10));
+        list.page_down();
+        list.page_down();
+        assert_eq!(list.state.selected(), Some(24));
+
+        list.page_up();
+        assert_eq!(list.state.selected(), Some(14));
+        list.page_up();
+        list.page_up();
+        assert_eq!(list.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn paging_moves_at_least_one() {
+        let mut list = list(&["a", "b"]);
+        list.page = 0;
+        list.first();
+        list.page_down();
+        assert_eq!(selected(&list), Some("b"));
+    }
+
+    #[test]
+    fn move_by_stops_at_the_ends() {
+        let mut list = list(&["a", "b", "c", "d"]);
+        list.move_by(2);
+        assert_eq!(selected(&list), Some("a"));
+        list.move_by(2);
+        assert_eq!(selected(&list), Some("c"));
+        list.move_by(5);
+        assert_eq!(selected(&list), Some("d"));
+        list.move_by(-2);
+        assert_eq!(selected(&list), Some("b"));
+        list.move_by(-5);
+        assert_eq!(selected(&list), Some("a"));
+    }
+
+    #[test]
+    fn scroll_follows_the_selection() {
+        let items: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+        let mut list = StatefulList::with_items(items);
+        assert_eq!(list.scroll(4), 0..4);
+        list.select_index(5);
+        assert_eq!(list.scroll(4), 2..6);
+        assert_eq!(list.get_window_state().selected(), Some(3));
+        assert_eq!(list.get_index_at(0), Some(2));
+        // moving within the window doesn't scroll
+        list.select_index(3);
+        assert_eq!(list.scroll(4), 2..6);
+        list.select_index(0);
+        assert_eq!(list.scroll(4), 0..4);
+        list.last();
+        assert_eq!(list.scroll(4), 6..10);
+        assert_eq!(list.get_index_at(3), Some(9));
+        assert_eq!(list.get_index_at(4), None);
+        assert_eq!(list.page, 4);
+    }
+
+    #[test]
+    fn scroll_keeps_within_shrinking_items() {
+        let mut list = list(&["a", "b", "c", "d", "e"]);
+        list.last();
+        assert_eq!(list.scroll(2), 3..5);
+        list.replace(strings(&["a"]));
+        assert_eq!(list.scroll(2), 0..1);
+        list.replace(vec![]);
+        assert_eq!(list.scroll(2), 0..0);
+        assert_eq!(list.get_index_at(0), None);
+    }
+
+    #[test]
+    fn first_last_and_index() {
+        let mut list = list(&["a", "b", "c"]);
+        list.last();
+        assert_eq!(selected(&list), Some("c"));
+        list.first();
+        assert_eq!(selected(&list), Some("a"));
+        list.select_index(1);
+        assert_eq!(selected(&list), Some("b"));
+        list.select_index(99);
+        assert_eq!(selected(&list), Some("c"));
+        list.unselect();
+        assert_eq!(selected(&list), None);
+    }
+
+    #[test]
+    fn navigation_follows_the_filter() {
+        let mut list = list(&["apple", "banana", "apricot", "cherry"]);
+        list.set_filter(Filter::parse("ap", &["Name"]));
+        assert_eq!(list.len(), 2);
+        list.next();
+        list.next();
+        assert_eq!(selected(&list), Some("apricot"));
+        list.next();
+        assert_eq!(selected(&list), Some("apple"));
+        list.last();
+        assert_eq!(selected(&list), Some("apricot"));
+    }
+
+    #[test]
+    fn filter_keeps_the_selected_item() {
+        let mut list = list(&["apple", "banana", "apricot"]);
+        list.select_index(2);
+        list.set_filter(Filter::parse("ap", &["Name"]));
+        assert_eq!(selected(&list), Some("apricot"));
+        list.set_filter(Filter::default());
+        assert_eq!(selected(&list), Some("apricot"));
+    }
+
+    #[test]
+    fn filter_moves_a_hidden_selection_to_the_first_match() {
+        let mut list = list(&["apple", "banana", "apricot"]);
+        list.select_index(1);
+        list.set_filter(Filter::parse("ap", &["Name"]));
+        assert_eq!(selected(&list), Some("apple"));
+        list.set_filter(Filter::parse("zzz", &["Name"]));
+        assert_eq!(selected(&list), None);
+    }
+
+    #[test]
+    fn filter_leaves_no_selection_alone() {
+        let mut list = list(&["apple", "banana"]);
+        list.set_filter(Filter::parse("an", &["Name"]));
+        assert_eq!(selected(&list), None);
+    }
+
+    #[test]
+    fn replace_follows_the_key() {
+        let mut list = keyed(&["a:1", "b:2", "c:3"]);
+        list.select_index(1);
+        list.replace(strings(&["z:0", "a:1", "c:3", "b:5"]));
+        assert_eq!(selected(&list), Some("b:5"));
+    }
+
+    #[test]
+    fn replace_keeps_the_position_when_the_key_is_gone() {
+        let mut list = keyed(&["a:1", "b:2", "c:3"]);
+        list.select_index(1);
+        list.replace(strings(&["a:1", "c:3", "d:4"]));
+        assert_eq!(selected(&list), Some("c:3"));
+        list.last();
+        list.replace(strings(&["a:1"]));
+        assert_eq!(selected(&list), Some("a:1"));
+    }
+
+    #[test]
+    fn replace_without_a_key_keeps_the_position() {
+        let mut list = list(&["a", "b", "c"]);
+        list.select_index(1);
+        list.replace(strings(&["c", "b", "a"]));
+        assert_eq!(list.state.selected(), Some(1));
+        list.replace(strings(&["x"]));
+        assert_eq!(selected(&list), Some("x"));
+    }
+
+    #[test]
+    fn replace_with_nothing_clears_the_selection() {
+        let mut list = keyed(&["a:1", "b:2"]);
+        list.first();
+        list.replace(vec![]);
+        assert_eq!(list.state.selected(), None);
+        list.next();
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn replace_leaves_no_selection_alone() {
+        let mut list = keyed(&["a:1", "b:2"]);
+        list.replace(strings(&["b:2", "c:3"]));
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn replace_applies_the_filter() {
+        let mut list = keyed(&["apple:1", "banana:2"]);
+        list.set_filter(Filter::parse("apple", &["Name"]));
+        list.first();
+        list.replace(strings(&["banana:2", "cherry:3", "apple:4"]));
+        assert_eq!(list.len(), 1);
+        assert_eq!(selected(&list), Some("apple:4"));
+    }
+
+    #[test]
+    fn select_key_leaves_the_selection_when_missing() {
+        let mut list = keyed(&["a:1", "b:2", "c:3"]);
+        assert!(list.select_key("c"));
+        assert_eq!(selected(&list), Some("c:3"));
+        assert!(!list.select_key("d"));
+        assert_eq!(selected(&list), Some("c:3"));
+    }
+
+    #[test]
+    fn select_key_needs_a_key() {
+        let mut list = list(&["a", "b"]);
+        assert!(!list.select_key("a"));
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn select_where_picks_the_first_match() {
+        let mut list = list(&["a", "bb", "cc"]);
+        list.select_where(|x| x.len() == 2);
+        assert_eq!(selected(&list), Some("bb"));
+        list.select_where(|x| x.is_empty());
+        assert_eq!(selected(&list), None);
+    }
+}
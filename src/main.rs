@@ -1,74 +1,48 @@
-mod ui;
-mod kinesis;
-mod app;
-mod service;
-mod cloud;
+use cloud_ui::{App, Registry};
+use cloud_ui::cloud::aws::Arn;
+use cloud_ui::config::Config;
+use cloud_ui::service::ProviderKind;
 
-use aws_sdk_kinesis::output::ListStreamsOutput;
-use aws_sdk_kinesis::Client;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use futures::FutureExt;
-use std::{
-    error::Error,
-    io,
-    time::{Duration, Instant},
-};
-use tokio::sync::mpsc::{Receiver, Sender};
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Corner, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState},
-    Frame, Terminal,
-};
+/// `cloud-ui [--provider aws|fixtures <dir>] [arn]`
+fn parse_args() -> anyhow::Result<(ProviderKind, Option<Arn>)> {
+    let mut provider = ProviderKind::Cloud;
+    let mut arn = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--provider" => provider = match args.next().as_deref() {
+                Some("aws") => ProviderKind::Cloud,
+                Some("fixtures") => ProviderKind::Fixtures(args.next()
+                    .ok_or_else(|| anyhow::anyhow!("--provider fixtures needs a directory"))?
+                    .into()),
+                Some(other) => anyhow::bail!("unknown provider: {}", other),
+                None => anyhow::bail!("--provider needs a value"),
+            },
+            _ => arn = Some(arg.parse()?),
+        }
+    }
+    Ok((provider, arn))
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // create app and run it
-    let tick_rate = Duration::from_millis(250);
-    let app = crate::app::App::<crate::cloud::aws::Services, crate::cloud::aws::kinesis::Resources>::new();
+    let (provider, arn) = parse_args()?;
 
-    let (tx, rx) = tokio::sync::mpsc::channel(1);
-    let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+    // register services, enabling those the config asks for
+    let config = Config::load()?;
+    let mut registry = Registry::default();
+    cloud_ui::cloud::aws::register(&mut registry);
+    cloud_ui::cloud::fixtures::register(&mut registry);
+    registry.configure(&config.services)?;
 
-    let streams_handle = tokio::task::spawn(kinesis::Streams::new().await.run(tx, shutdown_tx.subscribe()));
-    let app_handle = tokio::task::spawn(app::run_app(
-        terminal,
-        app,
-        rx,
-        shutdown_tx.clone(),
-        shutdown_tx.subscribe(),
-        tick_rate,
-    ));
-    let (_, res) = tokio::try_join!(streams_handle, app_handle)?;
-
-    match res {
-        Ok(mut terminal) => {
-            // restore terminal
-            disable_raw_mode()?;
-            execute!(
-                terminal.backend_mut(),
-                LeaveAlternateScreen,
-                DisableMouseCapture
-            )?;
-            terminal.show_cursor()?;
-        }
-        Err(err) => {
-            println!("{:?}", err)
-        }
+    // create app, opening the resource named on the command line if any
+    let mut app = App::new(registry);
+    app.context.provider = provider;
+    app.refresh = config.refresh;
+    app.keymap.configure(&config.keys)?;
+    if let Some(arn) = arn {
+        app.jump(&arn)?;
     }
 
-    Ok(())
+    cloud_ui::run(app).await
 }
@@ -0,0 +1,162 @@
+use crate::transfer::format_size;
+
+/// A metric a resource type reports, charted in its detail view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metric {
+    /// As the service knows it, e.g. `CPUUtilization`.
+    pub name: &'static str,
+    /// How the values within each step are combined, e.g. `Average` or `Sum`.
+    pub statistic: &'static str,
+    pub unit: Unit,
+}
+
+impl Metric {
+    pub const fn new(name: &'static str, statistic: &'static str, unit: Unit) -> Self {
+        Metric { name, statistic, unit }
+    }
+}
+
+/// What a metric's values count, for showing them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Count,
+    Bytes,
+    Percent,
+    Milliseconds,
+}
+
+impl Unit {
+    /// `value` with its unit, e.g. `12.5%` or `3.2 MiB`.
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Unit::Count if value.fract() == 0.0 => format!("{}", value),
+            Unit::Count => format!("{:.2}", value),
+            Unit::Bytes => format_size(value.max(0.0) as u64),
+            Unit::Percent => format!("{:.1}%", value),
+            Unit::Milliseconds if value >= 1000.0 => format!("{:.1}s", value / 1000.0),
+            Unit::Milliseconds => format!("{}ms", value.round()),
+        }
+    }
+}
+
+/// How far back from now metrics are charted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Period {
+    #[default]
+    Hour,
+    SixHours,
+    Day,
+}
+
+impl Period {
+    /// In seconds.
+    pub fn get_length(&self) -> i64 {
+        match self {
+            Period::Hour => 3600,
+            Period::SixHours => 6 * 3600,
+            Period::Day => 24 * 3600,
+        }
+    }
+
+    /// Seconds covered by each value, a multiple of a minute, as services keep them.
+    pub fn get_step(&self) -> i64 {
+        match self {
+            Period::Hour => 60,
+            Period::SixHours => 5 * 60,
+            Period::Day => 15 * 60,
+        }
+    }
+
+    /// Values in a series over the period.
+    pub fn get_steps(&self) -> usize {
+        (self.get_length() / self.get_step()) as usize
+    }
+
+    /// The next longer period, round to the shortest again.
+    pub fn next(&self) -> Self {
+        match self {
+            Period::Hour => Period::SixHours,
+            Period::SixHours => Period::Day,
+            Period::Day => Period::Hour,
+        }
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Period::Hour => "1h",
+            Period::SixHours => "6h",
+            Period::Day => "24h",
+        }
+    }
+
+    /// When the period ending at `now` starts, in seconds since the epoch, with both ends on a
+    /// whole step so the values line up however often they're fetched.
+    pub fn get_start(&self, now: i64) -> i64 {
+        now - now.rem_euclid(self.get_step()) - self.get_length()
+    }
+}
+
+/// A metric's values over a period, oldest first, one for each step. Steps nothing was
+/// reported in have none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    pub metric: Metric,
+    pub values: Vec<Option<f64>>,
+}
+
+impl Series {
+    /// `points`, as `(seconds since the epoch, value)`, placed in the steps of `period` from
+    /// `start`. Those outside it are left out.
+    pub fn new(metric: Metric, period: Period, start: i64, points: impl IntoIterator<Item = (i64, f64)>) -> Self {
+        let mut values = vec![None; period.get_steps()];
+        for (time, value) in points {
+            let step = (time - start).div_euclid(period.get_step());
+            if let Some(slot) = usize::try_from(step).ok().and_then(|x| values.get_mut(x)) {
+                *slot = Some(value);
+            }
+        }
+        Series { metric, values }
+    }
+
+    /// The most recent value reported.
+    pub fn get_latest(&self) -> Option<f64> {
+        self.values.iter().rev().find_map(|x| *x)
+    }
+
+    pub fn get_max(&self) -> Option<f64> {
+        self.values.iter().flatten().copied().reduce(f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPU: Metric = Metric::new("CPUUtilization", "Average", Unit::Percent);
+
+    #[test]
+    fn periods_end_on_a_step() {
+        assert_eq!(Period::Hour.get_start(7230), 7200 - 3600);
+        assert_eq!(Period::Day.get_start(86400 * 2 + 899), 86400);
+        assert_eq!(Period::SixHours.get_steps(), 72);
+    }
+
+    #[test]
+    fn series_places_points_in_steps() {
+        let series = Series::new(CPU, Period::Hour, 0, vec![(0, 1.0), (150, 2.5), (3540, 4.0), (3600, 9.0), (-60, 9.0)]);
+        assert_eq!(series.values.len(), 60);
+        assert_eq!(&series.values[..3], &[Some(1.0), None, Some(2.5)]);
+        assert_eq!(series.get_latest(), Some(4.0));
+        assert_eq!(series.get_max(), Some(4.0));
+        assert_eq!(Series::new(CPU, Period::Hour, 0, vec![]).get_latest(), None);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Unit::Count.format(42.0), "42");
+        assert_eq!(Unit::Count.format(0.1234), "0.12");
+        assert_eq!(Unit::Percent.format(12.345), "12.3%");
+        assert_eq!(Unit::Milliseconds.format(86.4), "86ms");
+        assert_eq!(Unit::Milliseconds.format(90500.0), "90.5s");
+    }
+}
@@ -1,69 +1,43 @@
-use std::any;
-use std::future::Future;
+use std::path::PathBuf;
 use async_trait::async_trait;
-use crate::service::resource::{ResourceKind, ResourceType};
-
-pub(crate) mod resource;
-
-pub(crate) struct ServiceType(pub String);
-
-pub(crate) trait ServiceKind
-    where Self: GetItems + From<String>
-{}
-
-macro_rules! services {
-    ($($p:ident, $s:ident),*$(,)*) => {
-        pub(crate) enum Services { $($s($s),)* }
-
-        impl crate::service::ServiceKind for Services {}
-
-        impl crate::ui::Ui<()> for Services {
-            fn ui<B>(&mut self, f: &mut tui::Frame<B>, area: tui::layout::Rect, state: &mut ()) -> anyhow::Result<()>
-                where B: tui::backend::Backend {
-                match self {
-                    $(Services::$s(svc) => crate::ui::service::ServiceUi::new(svc).ui(f, area, state),)*
-                }
-            }
-        }
-
-        impl crate::app::GetItems for Services {
-            fn get_items() -> Vec<String> {
-                vec![
-                    $(stringify!($s).to_string(),)*
-                ]
-            }
-        }
-
-        impl From<String> for Services {
-            fn from(str: String) -> Services {
-                match str.as_str() {
-                    $(stringify!($s) => Services::$s($s::new()),)*
-                    _ => panic!(),
-                }
-            }
-        }
-    };
+use crate::service::registry::ServiceType;
+
+pub mod resource;
+pub mod registry;
+pub mod metrics;
+
+/// Where services should point their clients. `None` leaves the choice to the provider's
+/// defaults, e.g. the environment and config files.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Context {
+    /// Named profile from the provider's config files.
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    pub provider: ProviderKind,
 }
 
-
-pub(crate) use services;
-use crate::app::GetItems;
+/// Where resources come from.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ProviderKind {
+    /// The cloud itself, through each service's SDK.
+    #[default]
+    Cloud,
+    /// JSON or YAML files under a directory, served by `FixtureProvider`.
+    Fixtures(PathBuf),
+}
 
 #[async_trait]
-pub(crate) trait Service<'a> {
+pub trait Service<'a> {
     type Provider: Provider + ?Sized;
-    type Resources: ResourceKind;
-
-    fn new() -> Self;
 
-    async fn run(self)  -> anyhow::Result<()>;
+    fn new(context: &Context) -> Self;
 
-    fn get_resources(&self) -> anyhow::Result<Vec<ResourceType>>;
+    /// The service's resource types, for registering with the `Registry`.
+    fn get_service_type() -> ServiceType where Self: Sized;
 }
 
-#[async_trait]
-pub(crate) trait Provider
+pub trait Provider
     where Self: Sized
 {
-    async fn new() -> anyhow::Result<Self>;
+    fn new(context: &Context) -> anyhow::Result<Self>;
 }
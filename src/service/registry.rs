@@ -0,0 +1,271 @@
+use std::marker::PhantomData;
+use serde::de::DeserializeOwned;
+use crate::config::ServicesConfig;
+use crate::service::Context;
+use crate::service::metrics::Metric;
+use crate::service::resource::{AnyController, ErasedController, Reference, Resource, ResourceController, ResourceDescription, Row};
+
+/// A service as the registry sees it: a name, the resource types it offers, and the names, such
+/// as ARNs, it can resolve. `ServiceType` covers the services in this crate, but anything
+/// implementing this can be registered.
+pub trait ServiceDescriptor: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    fn get_resource_types(&self) -> &[Box<dyn ResourceDescriptor>];
+
+    /// The resource `name` identifies, in whatever form the service's provider names resources,
+    /// if it belongs to this service.
+    fn get_reference(&self, _name: &str) -> Option<Reference> {
+        None
+    }
+
+    /// Release whatever the service holds on to, such as clients, as the app exits.
+    fn shutdown(&self) {}
+}
+
+/// A resource type within a service, able to build controllers for it.
+pub trait ResourceDescriptor: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    /// A fresh controller for this resource type, talking to the provider `context` names.
+    fn new_controller(&self, context: &Context) -> anyhow::Result<Box<dyn AnyController>>;
+
+    /// The metrics resources of this type report. None by default.
+    fn get_metrics(&self) -> Vec<Metric> {
+        vec![]
+    }
+
+    /// Column headers for listings of this type.
+    fn get_headers(&self) -> Vec<String> {
+        vec!["Name".to_string()]
+    }
+
+    /// Reads resources of this type from data, for controllers that serve them from files rather
+    /// than a service. Types that can't be read have none.
+    fn get_reader(&self) -> Option<Reader> {
+        None
+    }
+}
+
+/// Resources read from `data`, a list of them, each as its row and its description.
+pub type Reader = fn(data: serde_json::Value) -> anyhow::Result<Vec<(Row, ResourceDescription<Row>)>>;
+
+/// Builds the controller for `resource` in `service` in place of the resource type's own, for
+/// contexts it serves, such as those asking for fixtures. Others it leaves alone with `None`.
+pub type ControllerFactory = fn(context: &Context, service: &str, resource: &dyn ResourceDescriptor)
+    -> Option<anyhow::Result<Box<dyn AnyController>>>;
+
+/// The reference a resource name points at, if it names one of a service's resources.
+pub type Resolver = Box<dyn Fn(&str) -> Option<Reference> + Send + Sync>;
+
+/// A service described by its resource types, each with the function constructing its controller.
+///
+/// ```ignore
+/// ServiceType::new("Kinesis")
+///     .resource("Streams", Streams::new)
+///     .references(aws::arns(get_reference))
+/// ```
+pub struct ServiceType {
+    name: &'static str,
+    resources: Vec<Box<dyn ResourceDescriptor>>,
+    resolve: Resolver,
+    shutdown: fn(),
+}
+
+impl ServiceType {
+    pub fn new(name: &'static str) -> Self {
+        ServiceType {
+            name,
+            resources: vec![],
+            resolve: Box::new(|_| None),
+            shutdown: || {},
+        }
+    }
+
+    /// Add a resource type, with the function constructing its controller.
+    pub fn resource<C, R>(mut self, name: &'static str, new: fn(&Context) -> C) -> Self
+        where C: ResourceController<R> + Send + Sync + 'static,
+              R: Resource<Id = String> + DeserializeOwned + Send + 'static
+    {
+        self.resources.push(Box::new(ResourceType { name, new, resource: PhantomData }));
+        self
+    }
+
+    /// Resolve names of this service's resources with `resolve`.
+    pub fn references<F>(mut self, resolve: F) -> Self
+        where F: Fn(&str) -> Option<Reference> + Send + Sync + 'static
+    {
+        self.resolve = Box::new(resolve);
+        self
+    }
+
+    /// Call `shutdown` as the app exits.
+    pub fn on_shutdown(mut self, shutdown: fn()) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+impl ServiceDescriptor for ServiceType {
+    fn get_name(&self) -> &str {
+        self.name
+    }
+
+    fn get_resource_types(&self) -> &[Box<dyn ResourceDescriptor>] {
+        &self.resources
+    }
+
+    fn get_reference(&self, name: &str) -> Option<Reference> {
+        (self.resolve)(name)
+    }
+
+    fn shutdown(&self) {
+        (self.shutdown)()
+    }
+}
+
+struct ResourceType<C, R> {
+    name: &'static str,
+    new: fn(&Context) -> C,
+    resource: PhantomData<fn() -> R>,
+}
+
+impl <C, R> ResourceDescriptor for ResourceType<C, R>
+    where C: ResourceController<R> + Send + Sync + 'static,
+          R: Resource<Id = String> + DeserializeOwned + Send + 'static
+{
+    fn get_name(&self) -> &str {
+        self.name
+    }
+
+    fn new_controller(&self, context: &Context) -> anyhow::Result<Box<dyn AnyController>> {
+        Ok(Box::new(ErasedController::new((self.new)(context))))
+    }
+
+    fn get_metrics(&self) -> Vec<Metric> {
+        R::get_metrics()
+    }
+
+    fn get_headers(&self) -> Vec<String> {
+        R::get_headers().into_iter().map(|x| x.to_string()).collect()
+    }
+
+    fn get_reader(&self) -> Option<Reader> {
+        Some(read::<R>)
+    }
+}
+
+fn read<R>(data: serde_json::Value) -> anyhow::Result<Vec<(Row, ResourceDescription<Row>)>>
+    where R: Resource<Id = String> + DeserializeOwned
+{
+    let resources: Vec<R> = serde_json::from_value(data)?;
+    Ok(resources.iter()
+        .map(|x| (Row::from_resource(x), ResourceDescription::from_resource(x).erase()))
+        .collect())
+}
+
+/// Every service the app can browse, looked up by name at runtime. Names match without regard to
+/// case. Disabled services stay registered, but are left out of listings and refuse lookups.
+#[derive(Default)]
+pub struct Registry {
+    services: Vec<Box<dyn ServiceDescriptor>>,
+    disabled: Vec<String>,
+    factories: Vec<ControllerFactory>,
+}
+
+impl Registry {
+    /// Add `service`, replacing any already registered under the same name.
+    pub fn register(&mut self, service: Box<dyn ServiceDescriptor>) {
+        self.services.retain(|x| !x.get_name().eq_ignore_ascii_case(service.get_name()));
+        self.services.push(service);
+    }
+
+    /// Build controllers with `factory` for the contexts it serves, in place of the resource
+    /// types' own. Factories added first are asked first.
+    pub fn add_factory(&mut self, factory: ControllerFactory) {
+        self.factories.push(factory);
+    }
+
+    /// Enable and disable services as `config` says. Names that aren't registered are an error,
+    /// rather than a typo silently leaving everything enabled.
+    pub fn configure(&mut self, config: &ServicesConfig) -> anyhow::Result<()> {
+        for name in config.enabled.iter().flatten().chain(&config.disabled) {
+            self.find(name).ok_or_else(|| anyhow::anyhow!("unknown service in config: {}", name))?;
+        }
+        self.disabled = self.services.iter()
+            .map(|x| x.get_name())
+            .filter(|name| {
+                let listed = |names: &[String]| names.iter().any(|x| x.eq_ignore_ascii_case(name));
+                config.enabled.as_deref().map(|x| !listed(x)).unwrap_or(false) || listed(&config.disabled)
+            })
+            .map(|x| x.to_string())
+            .collect();
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<&dyn ServiceDescriptor> {
+        self.services.iter()
+            .find(|x| x.get_name().eq_ignore_ascii_case(name))
+            .map(|x| x.as_ref())
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.iter().any(|x| x.eq_ignore_ascii_case(name))
+    }
+
+    /// Enabled services, in the order they were registered.
+    pub fn get_services(&self) -> impl Iterator<Item = &dyn ServiceDescriptor> {
+        self.services.iter()
+            .map(|x| x.as_ref())
+            .filter(move |x| self.is_enabled(x.get_name()))
+    }
+
+    pub fn get_service(&self, name: &str) -> anyhow::Result<&dyn ServiceDescriptor> {
+        let service = self.find(name).ok_or_else(|| anyhow::anyhow!("unknown service: {}", name))?;
+        if !self.is_enabled(service.get_name()) {
+            anyhow::bail!("service is disabled: {}", service.get_name());
+        }
+        Ok(service)
+    }
+
+    pub fn get_resource_type(&self, service: &str, resource: &str) -> anyhow::Result<&dyn ResourceDescriptor> {
+        let service = self.get_service(service)?;
+        service.get_resource_types()
+            .iter()
+            .find(|x| x.get_name().eq_ignore_ascii_case(resource))
+            .map(|x| x.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("unknown resource type: {} {}", service.get_name(), resource))
+    }
+
+    /// Names of the resource types offered by `service`.
+    pub fn get_resources(&self, service: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self.get_service(service)?
+            .get_resource_types()
+            .iter()
+            .map(|x| x.get_name().to_string())
+            .collect())
+    }
+
+    pub fn new_controller(&self, service: &str, resource: &str, context: &Context) -> anyhow::Result<Box<dyn AnyController>> {
+        let service = self.get_service(service)?.get_name();
+        let resource = self.get_resource_type(service, resource)?;
+        match self.factories.iter().find_map(|x| x(context, service, resource)) {
+            Some(ctrl) => ctrl,
+            None => resource.new_controller(context),
+        }
+    }
+
+    /// The resource `name` identifies, from whichever enabled service recognises it.
+    pub fn get_reference(&self, name: &str) -> anyhow::Result<Reference> {
+        self.get_services()
+            .find_map(|x| x.get_reference(name))
+            .ok_or_else(|| anyhow::anyhow!("unsupported resource: {}", name))
+    }
+
+    /// Shut down every service, enabled or not.
+    pub fn shutdown(&self) {
+        for service in &self.services {
+            service.shutdown();
+        }
+    }
+}
@@ -1,71 +1,382 @@
 use std::collections::HashMap;
+use std::marker::PhantomData;
 use async_trait::async_trait;
+use crate::service::metrics::{Metric, Period, Series};
+use crate::transfer::Job;
 
-pub struct ResourceType(pub String);
+pub trait Resource
+    // where Self: Sized
+{
+    type Id: Send + 'static;
+
+    fn get_id(&self) -> Self::Id;
+
+    fn get_name(&self) -> String;
+
+    /// Column headers for the resource table. Single column by default.
+    fn get_headers() -> Vec<&'static str> where Self: Sized {
+        vec!["Name"]
+    }
+
+    /// Cell values for this resource, in the same order as `get_headers`.
+    fn get_columns(&self) -> Vec<String> {
+        vec![self.get_name()]
+    }
+
+    /// Key/value tags attached to the resource, for `tag:key=value` filters.
+    fn get_tags(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    /// Properties shown in the detail view. The non-empty columns by default.
+    fn get_props(&self) -> HashMap<String, String> where Self: Sized {
+        props(Self::get_headers(), self.get_columns())
+    }
+
+    /// Other resources this one points at, followed from the detail view.
+    fn get_references(&self) -> Vec<Reference> {
+        vec![]
+    }
 
-pub(crate) trait ResourceKind
-    where
-        Self: Ui<()> + Send + GetItems {
+    /// The listing opened in place of the detail view, for resources that hold others, such as
+    /// folders.
+    fn get_contents(&self) -> Option<Reference> {
+        None
+    }
+
+    /// Metrics the resource type reports, charted in the detail view by the controller's
+    /// `metrics`. None by default.
+    fn get_metrics() -> Vec<Metric> where Self: Sized {
+        vec![]
+    }
 }
 
-macro_rules! resources {
-    ($($c:ident, $r:ident),*$(,)*) => {
-        pub(crate) enum Resources { $($c($c),)* }
+/// Pair each header with its column, leaving out empty ones.
+pub fn props(headers: Vec<&'static str>, columns: Vec<String>) -> HashMap<String, String> {
+    headers.into_iter()
+        .map(|x| x.to_string())
+        .zip(columns)
+        .filter(|(_, v)| !v.is_empty())
+        .collect()
+}
 
-        impl crate::ui::Ui<()> for Resources {
-            fn ui<B>(&mut self, f: &mut tui::Frame<B>, area: tui::layout::Rect, state: &mut ()) -> anyhow::Result<()>
-                where B: tui::backend::Backend {
-                match self {
-                    $(Resources::$c(ctrl) => crate::ui::resource::ResourceUi::<$c, $r>::new(ctrl).ui(f, area, state),)*
-                }
-            }
+/// A resource of any type, flattened for display so that views don't need to know about the
+/// controller that produced it.
+#[derive(Clone)]
+pub struct Row {
+    pub id: String,
+    pub name: String,
+    pub columns: Vec<String>,
+    pub tags: Vec<(String, String)>,
+    pub contents: Option<Reference>,
+}
+
+impl Row {
+    pub fn from_resource<T>(resource: &T) -> Row
+        where T: Resource<Id = String>
+    {
+        Row {
+            id: resource.get_id(),
+            name: resource.get_name(),
+            columns: resource.get_columns(),
+            tags: resource.get_tags(),
+            contents: resource.get_contents(),
         }
+    }
+}
+
+impl Resource for Row {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn get_tags(&self) -> Vec<(String, String)> {
+        self.tags.clone()
+    }
 
-        impl crate::app::GetItems for Resources {
-            fn get_items() -> Vec<String> {
-                vec![
-                    $(stringify!($c).to_string(),)*
-                ]
-            }
+    fn get_contents(&self) -> Option<Reference> {
+        self.contents.clone()
+    }
+}
+
+/// A typed pointer from one resource to another, by service and resource type name as registered
+/// with the `Registry`. The target may belong to a different service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub label: String,
+    pub service: String,
+    pub resource: String,
+    pub id: String,
+    pub kind: ReferenceKind,
+}
+
+/// What following a `Reference` opens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// The detail view of the resource with the reference's ID.
+    Resource,
+    /// A listing of the resource type, scoped to the reference's ID.
+    Listing,
+}
+
+impl Reference {
+    pub fn new(label: &str, service: &str, resource: &str, id: impl Into<String>) -> Self {
+        Reference {
+            label: label.to_string(),
+            service: service.to_string(),
+            resource: resource.to_string(),
+            id: id.into(),
+            kind: ReferenceKind::Resource,
         }
+    }
 
-        impl From<String> for Resources {
-            fn from(str: String) -> Resources {
-                match str.as_str() {
-                    $(stringify!($c) => Resources::$c($c::new()),)*
-                    _ => panic!(),
-                }
-            }
+    /// A reference to the resources of type `resource` within `scope`, as the controller's
+    /// `set_scope` understands it.
+    pub fn listing(label: &str, service: &str, resource: &str, scope: impl Into<String>) -> Self {
+        Reference {
+            kind: ReferenceKind::Listing,
+            ..Reference::new(label, service, resource, scope)
         }
+    }
+}
 
-        impl crate::service::resource::ResourceKind for Resources {}
-    };
+pub struct ResourceDescription<T>
+    where T: Resource
+{
+    pub id: T::Id,
+    pub name: Option<String>,
+    pub props: HashMap<String, String>,
+    pub references: Vec<Reference>,
 }
 
-pub(crate) use resources;
-use crate::app::GetItems;
-use crate::ui::Ui;
+impl <T> ResourceDescription<T>
+    where T: Resource
+{
+    /// Describe `resource` by its props and references.
+    pub fn from_resource(resource: &T) -> Self where T: Sized {
+        ResourceDescription {
+            id: resource.get_id(),
+            name: Some(resource.get_name()),
+            props: resource.get_props(),
+            references: resource.get_references(),
+        }
+    }
+}
 
-pub(crate) trait Resource
-    // where Self: Sized
+impl <T> ResourceDescription<T>
+    where T: Resource<Id = String>
 {
-    type Id;
+    pub fn erase(self) -> ResourceDescription<Row> {
+        ResourceDescription {
+            id: self.id,
+            name: self.name,
+            props: self.props,
+            references: self.references,
+        }
+    }
+}
 
-    fn get_name(&self) -> String;
+/// An operation a controller can perform from the resource view, bound to `key`.
+#[derive(Clone)]
+pub struct Action {
+    pub key: char,
+    pub name: &'static str,
 }
 
-pub(crate) struct ResourceDescription<T>
-    where T: Resource
-{
-    pub(crate) id: T::Id,
-    pub(crate) name: Option<String>,
-    pub(crate) props: HashMap<String, String>,
+/// What the UI should do once an action has completed.
+pub enum ActionOutcome {
+    /// The listing, or the description, is stale and should be fetched again.
+    Reload,
+    /// Place the value on the clipboard.
+    Copy(String),
+    /// Page through `text`, such as the contents of a file.
+    Show { title: String, text: String },
+    /// Ask for a line of input, starting from `input`, and pass the answer to `respond`.
+    Ask { prompt: String, input: String },
+    /// Run these in the background, in the transfer queue.
+    Transfer(Vec<Job>),
+    /// Open `text` in the user's editor, as a file called `name`, and pass what's saved to
+    /// `respond`.
+    Edit { name: String, text: String },
+    /// Like `Edit`, but pass on what's saved even if unchanged, as for a payload to send that
+    /// may well be sent as it is.
+    Compose { name: String, text: String },
+    /// Nothing to show, but `message` says what happened, or why nothing did.
+    Status(String),
+    /// Follow the log `id` in a view of its own, reading on from it with `tail`.
+    Tail { title: String, id: String },
+}
+
+impl ActionOutcome {
+    /// Ask a yes or no `question`, before doing something that can't be undone.
+    pub fn confirm(question: &str) -> Self {
+        ActionOutcome::Ask { prompt: format!("{} (y/n) ", question), input: String::new() }
+    }
+}
+
+/// An event read from a log, such as a line a function printed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEvent {
+    /// Tells apart events read twice, as those at the time a read carries on from are.
+    pub id: String,
+    /// When it happened, in milliseconds since the epoch.
+    pub timestamp: i64,
+    /// Where within the log it was written, such as its stream.
+    pub source: String,
+    pub message: String,
+}
+
+/// How far a listing that fills in over time has got, such as a query's results.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Shown with the listing's title, e.g. `Running, 1.2 MiB scanned`.
+    pub note: String,
+    /// Whether there's more to come, so the listing should be fetched again soon.
+    pub pending: bool,
+}
+
+/// Whether the answer to `ActionOutcome::confirm` was yes.
+pub fn is_confirmed(input: &str) -> bool {
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
 #[async_trait]
-pub(crate) trait ResourceController<T>
+pub trait ResourceController<T>
     where T: Resource
 {
     async fn list(&self) -> anyhow::Result<Vec<T>>;
     async fn describe(&self, id: T::Id) -> anyhow::Result<Option<ResourceDescription<T>>>;
-}
\ No newline at end of file
+
+    /// Column headers for the listing. The resource type's, unless they depend on what was
+    /// listed, as for items without a fixed schema.
+    fn get_headers(&self) -> Vec<String> {
+        T::get_headers().into_iter().map(|x| x.to_string()).collect()
+    }
+
+    /// How far the last listing had got, for listings that fill in over time. Nothing to say by
+    /// default.
+    fn get_progress(&self) -> Option<Progress> {
+        None
+    }
+
+    /// Narrow the listing to the resources within `scope`, such as the objects under a prefix.
+    /// Called before anything is listed. Types that don't nest refuse.
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("can't list within {}", scope))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![]
+    }
+
+    /// Run the action bound to `key`, against the selected resource if there is one.
+    async fn perform(&mut self, key: char, _id: Option<T::Id>) -> anyhow::Result<ActionOutcome> {
+        Err(anyhow::anyhow!("no action bound to '{}'", key))
+    }
+
+    /// Carry on with the action bound to `key`, given the `input` it asked for.
+    async fn respond(&mut self, key: char, _id: Option<T::Id>, _input: String) -> anyhow::Result<ActionOutcome> {
+        Err(anyhow::anyhow!("no input expected for '{}'", key))
+    }
+
+    /// Events in the log `id` matching `pattern`, oldest first, from `since` in milliseconds
+    /// since the epoch, or recent ones if not given. Only logs can be tailed.
+    async fn tail(&self, _id: T::Id, _pattern: String, _since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        Err(anyhow::anyhow!("can't be tailed"))
+    }
+
+    /// Values of the resource type's `get_metrics` for `id`, over `period` up to now.
+    async fn metrics(&self, _id: T::Id, _period: Period) -> anyhow::Result<Vec<Series>> {
+        Err(anyhow::anyhow!("no metrics reported"))
+    }
+}
+
+/// Object-safe counterpart to `ResourceController`, which `ErasedController` implements for
+/// any of them. This is what the app holds on to while browsing.
+#[async_trait]
+pub trait AnyController: Send + Sync {
+    fn get_headers(&self) -> Vec<String>;
+    fn get_progress(&self) -> Option<Progress>;
+    async fn list(&self) -> anyhow::Result<Vec<Row>>;
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Row>>>;
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()>;
+    fn get_actions(&self) -> Vec<Action>;
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome>;
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome>;
+    async fn tail(&self, id: String, pattern: String, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>>;
+    async fn metrics(&self, id: String, period: Period) -> anyhow::Result<Vec<Series>>;
+}
+
+/// Adapts a typed `ResourceController` to `AnyController`, flattening its resources into rows.
+pub struct ErasedController<C, R> {
+    ctrl: C,
+    resource: PhantomData<fn() -> R>,
+}
+
+impl <C, R> ErasedController<C, R> {
+    pub fn new(ctrl: C) -> Self {
+        ErasedController {
+            ctrl,
+            resource: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl <C, R> AnyController for ErasedController<C, R>
+    where C: ResourceController<R> + Send + Sync,
+          R: Resource<Id = String> + Send + 'static
+{
+    fn get_headers(&self) -> Vec<String> {
+        self.ctrl.get_headers()
+    }
+
+    fn get_progress(&self) -> Option<Progress> {
+        self.ctrl.get_progress()
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<Row>> {
+        Ok(self.ctrl.list().await?
+            .iter()
+            .map(Row::from_resource)
+            .collect())
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Row>>> {
+        Ok(self.ctrl.describe(id).await?.map(ResourceDescription::erase))
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.ctrl.set_scope(scope)
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        self.ctrl.get_actions()
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        self.ctrl.perform(key, id).await
+    }
+
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        self.ctrl.respond(key, id, input).await
+    }
+
+    async fn tail(&self, id: String, pattern: String, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        self.ctrl.tail(id, pattern, since).await
+    }
+
+    async fn metrics(&self, id: String, period: Period) -> anyhow::Result<Vec<Series>> {
+        self.ctrl.metrics(id, period).await
+    }
+}
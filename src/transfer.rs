@@ -0,0 +1,314 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Transfers running at once. The rest wait their turn.
+pub const CONCURRENCY: usize = 4;
+
+/// How far a transfer has got, in bytes. Shared between the transfer and the queue showing it.
+#[derive(Default)]
+pub struct Progress {
+    done: AtomicU64,
+    total: AtomicU64,
+}
+
+impl Progress {
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, bytes: u64) {
+        self.done.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Bytes done, and bytes in all.
+    pub fn get(&self) -> (u64, u64) {
+        (self.done.load(Ordering::Relaxed), self.total.load(Ordering::Relaxed))
+    }
+
+    fn reset(&self) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+    }
+}
+
+pub type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// What a job's progress counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Bytes,
+    /// Things, such as messages, called by the plural given.
+    Items(&'static str),
+}
+
+impl Unit {
+    /// `amount` of this unit, e.g. `1.5 KiB` or `12 messages`.
+    pub fn format(&self, amount: u64) -> String {
+        match self {
+            Unit::Bytes => format_size(amount),
+            Unit::Items(noun) => format!("{} {}", amount, noun),
+        }
+    }
+}
+
+/// Work for the transfer queue, such as copying a file, reporting as it goes. Retrying runs it
+/// again from the start.
+pub struct Job {
+    pub name: String,
+    pub unit: Unit,
+    run: Arc<dyn Fn(Arc<Progress>) -> JobFuture + Send + Sync>,
+}
+
+impl Job {
+    pub fn new<F>(name: String, run: F) -> Self
+        where F: Fn(Arc<Progress>) -> JobFuture + Send + Sync + 'static
+    {
+        Job { name, unit: Unit::Bytes, run: Arc::new(run) }
+    }
+
+    /// Count progress in `unit`, rather than bytes.
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// A job in the queue, along with how it's getting on.
+pub struct Transfer {
+    pub job: Job,
+    pub status: Status,
+    pub progress: Arc<Progress>,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+    task: Option<JoinHandle<()>>,
+    /// Filled in by the task as it ends, with the error if it failed.
+    outcome: Arc<Mutex<Option<Result<(), String>>>>,
+}
+
+impl Transfer {
+    /// Units a second, averaged over the time it has been running.
+    pub fn get_throughput(&self) -> Option<f64> {
+        let started = self.started?;
+        let elapsed = self.finished.unwrap_or_else(Instant::now) - started;
+        let (done, _) = self.progress.get();
+        match elapsed.as_secs_f64() {
+            secs if secs > 0.0 => Some(done as f64 / secs),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status, Status::Queued | Status::Running)
+    }
+
+    fn start(&mut self) {
+        self.progress.reset();
+        *self.outcome.lock().unwrap() = None;
+        let future = (self.job.run)(self.progress.clone());
+        let outcome = self.outcome.clone();
+        self.task = Some(tokio::spawn(async move {
+            let result = future.await.map_err(|x| format!("{:#}", x));
+            *outcome.lock().unwrap() = Some(result);
+        }));
+        self.status = Status::Running;
+        self.started = Some(Instant::now());
+        self.finished = None;
+    }
+}
+
+/// Transfers in the order they were queued, `CONCURRENCY` of them running at a time.
+#[derive(Default)]
+pub struct TransferQueue {
+    pub transfers: Vec<Transfer>,
+}
+
+impl TransferQueue {
+    pub fn push(&mut self, job: Job) {
+        self.transfers.push(Transfer {
+            job,
+            status: Status::Queued,
+            progress: Arc::new(Progress::default()),
+            started: None,
+            finished: None,
+            task: None,
+            outcome: Arc::new(Mutex::new(None)),
+        });
+    }
+
+    /// Note the transfers that have ended, and start queued ones while there's room. Returns
+    /// the name and error of each that failed since last time.
+    pub fn poll(&mut self) -> Vec<(String, String)> {
+        let mut failures = vec![];
+        for transfer in self.transfers.iter_mut().filter(|x| x.status == Status::Running) {
+            let outcome = transfer.outcome.lock().unwrap().take();
+            match outcome {
+                Some(Ok(())) => transfer.status = Status::Done,
+                Some(Err(err)) => {
+                    failures.push((transfer.job.name.clone(), err.clone()));
+                    transfer.status = Status::Failed(err);
+                }
+                None => continue,
+            }
+            transfer.finished = Some(Instant::now());
+            transfer.task = None;
+        }
+
+        let running = self.get_active();
+        for transfer in self.transfers.iter_mut()
+            .filter(|x| x.status == Status::Queued)
+            .take(CONCURRENCY.saturating_sub(running))
+        {
+            transfer.start();
+        }
+        failures
+    }
+
+    /// Transfers running.
+    pub fn get_active(&self) -> usize {
+        self.transfers.iter().filter(|x| x.status == Status::Running).count()
+    }
+
+    /// Transfers queued or running.
+    pub fn get_pending(&self) -> usize {
+        self.transfers.iter().filter(|x| !x.is_finished()).count()
+    }
+
+    /// Stop the transfer at `idx`, or take it out of the queue if it hasn't started.
+    pub fn cancel(&mut self, idx: usize) {
+        if let Some(transfer) = self.transfers.get_mut(idx).filter(|x| !x.is_finished()) {
+            if let Some(task) = transfer.task.take() {
+                task.abort();
+            }
+            transfer.status = Status::Cancelled;
+            transfer.finished = Some(Instant::now());
+        }
+    }
+
+    /// Queue the transfer at `idx` again, if it failed or was cancelled.
+    pub fn retry(&mut self, idx: usize) {
+        if let Some(transfer) = self.transfers.get_mut(idx) {
+            if matches!(transfer.status, Status::Failed(_) | Status::Cancelled) {
+                transfer.status = Status::Queued;
+            }
+        }
+    }
+
+    /// Forget the transfers that completed, keeping failures around to retry.
+    pub fn clear_done(&mut self) {
+        self.transfers.retain(|x| x.status != Status::Done);
+    }
+}
+
+/// `812 B`, `1.5 KiB`, `20.0 GiB` and so on.
+pub fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64;
+    let mut unit = "B";
+    for x in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = x;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A job that moves `size` bytes once `gate` opens, failing instead if `fail` is set.
+    fn job(size: u64, gate: Arc<tokio::sync::Notify>, fail: bool) -> Job {
+        Job::new("copy".to_string(), move |progress| {
+            let gate = gate.clone();
+            Box::pin(async move {
+                progress.set_total(size);
+                gate.notified().await;
+                if fail {
+                    anyhow::bail!("disk full");
+                }
+                progress.add(size);
+                Ok(())
+            })
+        })
+    }
+
+    async fn settle(queue: &mut TransferQueue) -> Vec<(String, String)> {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        queue.poll()
+    }
+
+    #[tokio::test]
+    async fn runs_a_few_at_a_time() {
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let mut queue = TransferQueue::default();
+        for _ in 0..CONCURRENCY + 2 {
+            queue.push(job(10, gate.clone(), false));
+        }
+        queue.poll();
+        assert_eq!(queue.get_active(), CONCURRENCY);
+        assert_eq!(queue.get_pending(), CONCURRENCY + 2);
+
+        // let the tasks reach the gate before opening it
+        settle(&mut queue).await;
+        gate.notify_waiters();
+        settle(&mut queue).await;
+        assert_eq!(queue.transfers.iter().filter(|x| x.status == Status::Done).count(), CONCURRENCY);
+        assert_eq!(queue.get_active(), 2);
+        assert_eq!(queue.transfers[0].progress.get(), (10, 10));
+
+        queue.clear_done();
+        assert_eq!(queue.transfers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn cancel_and_retry() {
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let mut queue = TransferQueue::default();
+        queue.push(job(10, gate.clone(), true));
+        queue.push(job(10, gate.clone(), false));
+        queue.poll();
+
+        queue.cancel(1);
+        assert_eq!(queue.transfers[1].status, Status::Cancelled);
+        settle(&mut queue).await;
+        gate.notify_waiters();
+        let failures = settle(&mut queue).await;
+        assert_eq!(failures, vec![("copy".to_string(), "disk full".to_string())]);
+        assert_eq!(queue.transfers[1].status, Status::Cancelled);
+
+        queue.retry(1);
+        queue.poll();
+        assert_eq!(queue.transfers[1].status, Status::Running);
+        settle(&mut queue).await;
+        gate.notify_waiters();
+        settle(&mut queue).await;
+        assert_eq!(queue.transfers[1].status, Status::Done);
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_size(812), "812 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(20 * 1024 * 1024 * 1024), "20.0 GiB");
+    }
+}
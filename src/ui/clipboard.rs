@@ -0,0 +1,10 @@
+use std::io::Write;
+
+/// Place `text` on the system clipboard using the OSC 52 terminal escape, which works across
+/// ssh sessions and without any clipboard tooling installed locally.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64::encode(text))?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph};
+use crate::ui;
+
+/// Breadcrumbs leading to the current view, with the latest status message underneath.
+pub struct Header {
+    pub title: String,
+    pub status: Option<String>,
+}
+
+impl ui::Ui<Vec<String>> for Header {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut Vec<String>) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let mut crumbs = vec![];
+        for (i, crumb) in state.iter().enumerate() {
+            if i > 0 {
+                crumbs.push(Span::raw(" > "));
+            }
+            let style = if i + 1 == state.len() {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            crumbs.push(Span::styled(crumb.clone(), style));
+        }
+
+        let lines = vec![
+            Spans::from(crumbs),
+            Spans::from(Span::styled(self.status.clone().unwrap_or_default(), Style::default().fg(Color::Yellow))),
+        ];
+        let header = Paragraph::new(lines)
+            .block(Block::default().title(self.title.clone()).borders(Borders::ALL))
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(header, area);
+        Ok(())
+    }
+
+    fn hit(&self, area: Rect, state: &Vec<String>, column: u16, row: u16) -> Option<ui::Hit> {
+        // the breadcrumbs are on the first line inside the border
+        if row != area.y + 1 {
+            return None;
+        }
+        let mut x = area.x + 1;
+        for (i, crumb) in state.iter().enumerate() {
+            let width = crumb.chars().count() as u16;
+            if column >= x && column < x + width {
+                return Some(ui::Hit::Crumb(i));
+            }
+            x += width + " > ".len() as u16;
+        }
+        None
+    }
+}
@@ -1,13 +1,15 @@
-mod table;
-mod header;
+pub mod table;
+pub mod header;
 
-use std::io::Stdout;
-use tui::backend::{Backend, CrosstermBackend};
+pub use table::Table;
+pub use header::Header;
+
+use tui::backend::Backend;
 use tui::Frame;
 use tui::layout::Rect;
 use tui::style::{Color, Modifier, Style};
-use tui::widgets::{List, ListItem};
-use crate::app::StatefulList;
+use tui::widgets::{Block, Borders, List, ListItem};
+use crate::list::StatefulList;
 use tui::text::{Span, Spans};
 use crate::ui;
 
@@ -19,25 +21,69 @@ impl ui::Ui<StatefulList<String>> for TableList {
     fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut StatefulList<String>) -> anyhow::Result<()>
         where B: Backend
     {
+        let inner = get_list_area(area, state);
+        let range = state.scroll(inner.height as usize);
         let items: Vec<ListItem> = state
-            .items
-            .iter()
+            .get_visible()
+            .skip(range.start)
+            .take(range.len())
             .map(|r| {
-                let mut lines = vec![Spans::from(r.clone())];
+                let matched = state.filter.matches(r).unwrap_or_default();
+                let lines = vec![Spans::from(highlight(r, matched.first().map(|x| x.as_slice()).unwrap_or_default()))];
                 ListItem::new(lines).style(Style::default().fg(Color::Black).bg(Color::White))
             })
             .collect();
 
-        let table = List::new(items)
+        let mut table = List::new(items)
             .highlight_style(
                 Style::default()
                     .bg(Color::LightGreen)
                     .add_modifier(Modifier::BOLD),
             )
             .highlight_symbol(">> ");
+        if !state.filter.is_empty() {
+            table = table.block(Block::default().title(format!("/{}", state.filter.input)).borders(Borders::ALL));
+        }
 
-        f.render_stateful_widget(table, area, &mut state.state);
+        f.render_stateful_widget(table, area, &mut state.get_window_state());
         Ok(())
     }
+
+    fn hit(&self, area: Rect, state: &StatefulList<String>, column: u16, row: u16) -> Option<ui::Hit> {
+        let inner = get_list_area(area, state);
+        if !ui::contains(inner, column, row) {
+            return None;
+        }
+        Some(state.get_index_at((row - inner.y) as usize).map(ui::Hit::Item).unwrap_or(ui::Hit::List))
+    }
 }
 
+/// Where the items go: within a border while filtered, to show the filter.
+fn get_list_area(area: Rect, state: &StatefulList<String>) -> Rect {
+    if state.filter.is_empty() {
+        area
+    } else {
+        Block::default().borders(Borders::ALL).inner(area)
+    }
+}
+
+/// Split `text` into spans, picking out the characters at `positions` (as matched by a filter).
+pub fn highlight<'a>(text: &str, positions: &[usize]) -> Vec<Span<'a>> {
+    let style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = vec![];
+    let mut current = String::new();
+    let mut matched = false;
+    for (idx, c) in text.chars().enumerate() {
+        let is_match = positions.contains(&idx);
+        if is_match != matched && !current.is_empty() {
+            let text = std::mem::take(&mut current);
+            spans.push(if matched { Span::styled(text, style) } else { Span::raw(text) });
+        }
+        matched = is_match;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(if matched { Span::styled(current, style) } else { Span::raw(current) });
+    }
+    spans
+}
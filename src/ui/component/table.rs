@@ -1,3 +1,119 @@
-struct Table {
+use std::collections::HashMap;
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use crate::list::StatefulList;
+use crate::filter::Filterable;
+use crate::service::resource::Resource;
+use crate::ui;
+use crate::ui::component::highlight;
+use crate::ui::resource::Change;
 
+/// Widest a column may grow before its cells are truncated.
+const MAX_COLUMN_WIDTH: usize = 48;
+
+const HIGHLIGHT_SYMBOL: &str = ">> ";
+
+/// A multi-column listing of resources, with a header row taken from the controller's
+/// `get_headers`.
+pub struct Table {
+    pub title: String,
+    pub headers: Vec<String>,
+    /// Rows to highlight, by ID, for having changed recently.
+    pub changes: HashMap<String, Change>,
+}
+
+impl <T> ui::Ui<StatefulList<T>> for Table
+    where T: Resource<Id = String> + Filterable
+{
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut StatefulList<T>) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let rows: Vec<_> = state.get_visible()
+            .map(|x| (x.get_columns(), state.filter.matches(x).unwrap_or_default(), self.changes.get(&x.get_id()).copied()))
+            .collect();
+        let mut widths: Vec<usize> = self.headers.iter().map(|x| x.chars().count()).collect();
+        for (row, _, _) in &rows {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.chars().count()).min(MAX_COLUMN_WIDTH);
+            }
+        }
+
+        let mut title = self.title.clone();
+        if !state.filter.is_empty() {
+            title.push_str(&format!(" /{}", state.filter.input));
+        }
+        let block = Block::default().title(title).borders(Borders::ALL);
+        f.render_widget(block, area);
+        let chunks = split(area);
+
+        let mut header = vec![Span::raw(" ".repeat(HIGHLIGHT_SYMBOL.len()))];
+        header.extend(format_row(&self.headers, &[], &widths));
+        let header = Paragraph::new(Spans::from(header))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        f.render_widget(header, chunks[0]);
+
+        let range = state.scroll(chunks[1].height as usize);
+        let items: Vec<ListItem> = rows[range]
+            .iter()
+            .map(|(cells, matched, change)| {
+                let style = Style::default().fg(Color::Black).bg(Color::White);
+                let style = match change {
+                    Some(Change::Added) => style.bg(Color::LightCyan),
+                    Some(Change::Changed) => style.bg(Color::LightYellow),
+                    Some(Change::Removed) => style.fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT),
+                    None => style,
+                };
+                ListItem::new(Spans::from(format_row(cells, matched, &widths))).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+        f.render_stateful_widget(list, chunks[1], &mut state.get_window_state());
+        Ok(())
+    }
+
+    fn hit(&self, area: Rect, state: &StatefulList<T>, column: u16, row: u16) -> Option<ui::Hit> {
+        let rows = split(area)[1];
+        if !ui::contains(rows, column, row) {
+            return None;
+        }
+        Some(state.get_index_at((row - rows.y) as usize).map(ui::Hit::Item).unwrap_or(ui::Hit::List))
+    }
+}
+
+/// The header row, and the rows below it, within the border.
+fn split(area: Rect) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+        .split(Block::default().borders(Borders::ALL).inner(area))
+}
+
+/// Pad each cell out to its column width, truncating anything longer, and highlighting the
+/// characters the filter matched.
+fn format_row<'a>(cells: &[String], matched: &[Vec<usize>], widths: &[usize]) -> Vec<Span<'a>> {
+    let mut spans = vec![];
+    for (i, (cell, width)) in cells.iter().zip(widths).enumerate() {
+        let cell: String = if cell.chars().count() > *width {
+            cell.chars().take(width.saturating_sub(1)).chain(std::iter::once('…')).collect()
+        } else {
+            cell.clone()
+        };
+        if i > 0 {
+            spans.push(Span::raw("  "));
+        }
+        let padding = width - cell.chars().count();
+        spans.extend(highlight(&cell, matched.get(i).map(|x| x.as_slice()).unwrap_or_default()));
+        spans.push(Span::raw(" ".repeat(padding)));
+    }
+    spans
 }
@@ -0,0 +1,62 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use crate::ui::prompt::Question;
+
+/// Text waiting to be edited in the user's editor, for the action that asked.
+pub struct Editing {
+    pub question: Question,
+    /// File name to edit under, whose extension tells the editor how to highlight it.
+    pub name: String,
+    pub text: String,
+    /// Whether the text is passed on even if saved unchanged.
+    pub compose: bool,
+}
+
+/// The command to edit with, from `$VISUAL` or `$EDITOR`, falling back to `vi`. Either may hold
+/// arguments, as in `code --wait`.
+fn get_editor() -> Vec<String> {
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(|x| std::env::var(x).ok())
+        .map(|x| x.split_whitespace().map(|x| x.to_string()).collect::<Vec<_>>())
+        .find(|x| !x.is_empty())
+        .unwrap_or_else(|| vec!["vi".to_string()])
+}
+
+/// Create the file at `path`, readable only by the user, as it may hold items and messages.
+/// Anything already there, such as a link planted in the shared temp dir, is an error.
+fn create_private(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Edit `text` in the user's editor, handing the terminal over to it until it exits, and
+/// return what was saved.
+pub fn edit(name: &str, text: &str) -> anyhow::Result<String> {
+    let path = std::env::temp_dir().join(format!("cloud-ui-{}-{}", std::process::id(), name));
+    create_private(&path)?.write_all(text.as_bytes())?;
+    let editor = get_editor();
+
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::event::DisableMouseCapture,
+        crossterm::cursor::Show,
+    )?;
+    let status = Command::new(&editor[0]).args(&editor[1..]).arg(&path).status();
+    crossterm::terminal::enable_raw_mode()?;
+    crossterm::execute!(std::io::stdout(), crossterm::terminal::EnterAlternateScreen, crossterm::event::EnableMouseCapture)?;
+
+    let edited = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path).map_err(anyhow::Error::from),
+        Ok(status) => Err(anyhow::anyhow!("{} exited with {}", editor[0], status)),
+        Err(err) => Err(anyhow::anyhow!("can't run {}: {}", editor[0], err)),
+    };
+    let _ = std::fs::remove_file(&path);
+    edited
+}
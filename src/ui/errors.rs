@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::{Alignment, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use crate::ui;
+
+/// How long a toast stays up.
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// Toasts shown at once, newest at the bottom.
+const MAX_TOASTS: usize = 3;
+
+const TOAST_WIDTH: u16 = 60;
+
+/// A failed operation, kept for the error log.
+pub struct ErrorEntry {
+    pub time: DateTime<Local>,
+    /// What was being done, e.g. `Ec2 Instances: list`.
+    pub operation: String,
+    pub message: String,
+    /// A plain explanation, for errors that have one.
+    pub hint: Option<&'static str>,
+    /// Times it happened in a row.
+    pub count: usize,
+}
+
+impl ErrorEntry {
+    /// The one line shown in a toast: the explanation if there is one, otherwise the error.
+    pub fn get_summary(&self) -> String {
+        format!("{}: {}", self.operation, self.hint.map(|x| x.to_string()).unwrap_or_else(|| self.message.clone()))
+    }
+}
+
+/// A message shown briefly over the bottom right of the screen.
+pub struct Toast {
+    pub message: String,
+    pub shown: Instant,
+}
+
+impl Toast {
+    pub fn new(message: String) -> Self {
+        Toast {
+            message,
+            shown: Instant::now(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.shown.elapsed() >= TOAST_DURATION
+    }
+}
+
+/// Stacks the latest toasts up from the bottom right corner of the area.
+pub struct Toasts {}
+
+impl ui::Ui<Vec<Toast>> for Toasts {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut Vec<Toast>) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let width = TOAST_WIDTH.min(area.width);
+        let mut bottom = area.bottom();
+        for toast in state.iter().rev().take(MAX_TOASTS) {
+            let text_width = width.saturating_sub(2).max(1) as usize;
+            let lines = toast.message.chars().count().div_ceil(text_width);
+            let height = (lines as u16 + 2).min(bottom.saturating_sub(area.y));
+            if height < 3 {
+                break;
+            }
+            bottom -= height;
+            let rect = Rect::new(area.right() - width, bottom, width, height);
+            let paragraph = Paragraph::new(toast.message.clone())
+                .wrap(Wrap { trim: true })
+                .block(Block::default()
+                    .title(Span::styled("Error (! for log)", Style::default().add_modifier(Modifier::BOLD)))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red)));
+            f.render_widget(Clear, rect);
+            f.render_widget(paragraph, rect);
+        }
+        Ok(())
+    }
+}
+
+/// Every error so far, newest first, over most of the screen.
+pub struct ErrorLog {
+    /// Lines scrolled past.
+    pub scroll: u16,
+}
+
+impl ui::Ui<Vec<ErrorEntry>> for ErrorLog {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut Vec<ErrorEntry>) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let rect = ui::get_overlay_area(area);
+
+        let mut lines = vec![];
+        for entry in state.iter().rev() {
+            let mut heading = vec![
+                Span::styled(entry.time.format("%H:%M:%S").to_string(), Style::default().fg(Color::DarkGray)),
+                Span::raw("  "),
+                Span::styled(entry.operation.clone(), Style::default().add_modifier(Modifier::BOLD)),
+            ];
+            if entry.count > 1 {
+                heading.push(Span::raw(format!(" (x{})", entry.count)));
+            }
+            lines.push(Spans::from(heading));
+            if let Some(hint) = entry.hint {
+                lines.push(Spans::from(Span::styled(format!("  {}", hint), Style::default().fg(Color::Yellow))));
+            }
+            lines.extend(entry.message.lines().map(|x| Spans::from(format!("  {}", x))));
+            lines.push(Spans::from(""));
+        }
+        if lines.is_empty() {
+            lines.push(Spans::from("No errors"));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .alignment(Alignment::Left)
+            .block(Block::default()
+                .title("Errors (j/k to scroll, esc to close)")
+                .borders(Borders::ALL));
+        f.render_widget(Clear, rect);
+        f.render_widget(paragraph, rect);
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+use crate::ui;
+
+/// The keys for the current view, each with what it does, over most of the screen.
+pub struct Help {
+    /// Lines scrolled past.
+    pub scroll: u16,
+}
+
+impl ui::Ui<Vec<(String, String)>> for Help {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut Vec<(String, String)>) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let rect = ui::get_overlay_area(area);
+        let width = state.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or_default();
+        let lines: Vec<Spans> = state.iter()
+            .map(|(keys, description)| Spans::from(vec![
+                Span::styled(format!("{:width$}  ", keys, width = width), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(description.clone()),
+            ]))
+            .collect();
+        let paragraph = Paragraph::new(lines)
+            .scroll((self.scroll, 0))
+            .block(Block::default()
+                .title("Keys (j/k to scroll, esc to close)")
+                .borders(Borders::ALL));
+        f.render_widget(Clear, rect);
+        f.render_widget(paragraph, rect);
+        Ok(())
+    }
+}
@@ -1,51 +1,140 @@
-pub(crate) mod service;
-mod component;
-pub(crate) mod resource;
+pub mod component;
+pub mod resource;
+pub mod clipboard;
+pub mod editor;
+pub mod prompt;
+pub mod errors;
+pub mod help;
+pub mod pager;
+pub mod tail;
+pub mod transfers;
 
-use std::io::Stdout;
-use tui::backend::{Backend, CrosstermBackend};
+use tui::backend::Backend;
 use tui::Frame;
-use tui::layout::{Constraint, Corner, Direction, Layout, Rect};
-use tui::style::{Color, Modifier, Style};
-use tui::text::{Span, Spans};
-use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
-use crate::app::AppState;
-use crate::cloud::aws::Services;
-use crate::ui::component::TableList;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use crate::app::{AppState, Overlay};
+use crate::service::ProviderKind;
+use crate::ui::component::{Header, TableList};
+use crate::ui::errors::{ErrorLog, Toasts};
+use crate::ui::help::Help;
+use crate::ui::pager::Pager;
+use crate::ui::prompt::Prompt;
+use crate::ui::resource::{ResourceDetail, ResourceList};
+use crate::ui::tail::Tail;
+use crate::ui::transfers::Transfers;
 
 pub trait Ui<T> {
     fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut T) -> anyhow::Result<()> where B: Backend;
+
+    /// What is at `column`, `row` on screen, given the area `state` was last drawn in. Nothing
+    /// by default.
+    fn hit(&self, _area: Rect, _state: &T, _column: u16, _row: u16) -> Option<Hit> {
+        None
+    }
+}
+
+/// Something a mouse event landed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hit {
+    /// The item at this index among those visible in the current view's list.
+    Item(usize),
+    /// The current view's list, away from any item.
+    List,
+    /// The detail view's properties.
+    Props,
+    /// The breadcrumb at this depth.
+    Crumb(usize),
+}
+
+/// Where the parts of the screen were last drawn, for hit testing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Areas {
+    pub header: Rect,
+    pub body: Rect,
 }
 
-pub(crate) fn ui<B: Backend, Svc, Res>(f: &mut Frame<B>, app: &mut crate::app::App<Svc, Res>) -> anyhow::Result<()> {
+/// Whether `area` covers the cell at `column`, `row`.
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.left() && column < area.right() && row >= area.top() && row < area.bottom()
+}
+
+pub fn ui<B: Backend>(f: &mut Frame<B>, app: &mut crate::app::App) -> anyhow::Result<()> {
+    let prompt_height = if app.prompt.is_some() { 3 } else { 0 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
+        .constraints([Constraint::Length(4), Constraint::Min(0), Constraint::Length(prompt_height)].as_ref())
         .split(f.size());
+    app.areas = Areas { header: chunks[0], body: chunks[1] };
 
-    let header = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
-        .split(chunks[0]);
-
-
-    let title = Paragraph::new("")
-        .block(Block::default().title("Paragraph").borders(Borders::ALL))
-        .style(Style::default().fg(Color::White).bg(Color::Black))
-        .wrap(Wrap { trim: true });
+    if let Some(prompt) = &mut app.prompt {
+        Prompt {}.ui(f, chunks[2], prompt)?;
+    }
 
-    f.render_widget(title, chunks[0]);
+    let mut context: Vec<String> = [&app.context.profile, &app.context.region].iter()
+        .filter_map(|x| (*x).clone())
+        .collect();
+    if let ProviderKind::Fixtures(dir) = &app.context.provider {
+        context.push(format!("fixtures:{}", dir.display()));
+    }
+    let mut title = match context.as_slice() {
+        [] => "cloud-ui".to_string(),
+        context => format!("cloud-ui [{}]", context.join(" ")),
+    };
+    match app.transfers.get_pending() {
+        0 => {}
+        1 => title.push_str(" 1 transfer (t)"),
+        n => title.push_str(&format!(" {} transfers (t)", n)),
+    }
+    match app.errors.len() {
+        0 => {}
+        1 => title.push_str(" 1 error (!)"),
+        n => title.push_str(&format!(" {} errors (!)", n)),
+    }
+    // a count or key sequence being typed shows in place of the status
+    let pending = app.keymap.get_pending();
+    let status = if pending.is_empty() { app.status.clone() } else { Some(pending) };
+    let mut header = Header { title, status };
+    header.ui(f, chunks[0], &mut app.get_breadcrumbs())?;
 
     match &mut app.state {
-        AppState::Services { service } => {
+        AppState::Services { services } => {
             let mut tl = TableList {};
-            tl.ui(f, chunks[1], &mut service.items)?;
+            tl.ui(f, chunks[1], services)?;
         }
-        AppState::Resources { resources } => {
+        AppState::Resources { resources, .. } => {
             let mut tl = TableList {};
-            tl.ui(f, chunks[1], &mut resources.items)?;
+            tl.ui(f, chunks[1], resources)?;
+        }
+        AppState::Listing(state) => {
+            ResourceList {}.ui(f, chunks[1], state)?;
+        }
+        AppState::Detail(state) => {
+            ResourceDetail {}.ui(f, chunks[1], state)?;
+        }
+        AppState::Pager(state) => {
+            Pager {}.ui(f, chunks[1], state)?;
+        }
+        AppState::Tail(state) => {
+            Tail {}.ui(f, chunks[1], state)?;
         }
     };
 
+    match app.overlay {
+        Some(Overlay::Errors { scroll }) => ErrorLog { scroll }.ui(f, chunks[1], &mut app.errors)?,
+        Some(Overlay::Help { scroll }) => Help { scroll }.ui(f, chunks[1], &mut app.get_help())?,
+        Some(Overlay::Transfers { selected }) => Transfers { selected }.ui(f, chunks[1], &mut app.transfers)?,
+        None => Toasts {}.ui(f, chunks[1], &mut app.toasts)?,
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Where overlays go: most of `area`, leaving a margin of a tenth on each side.
+pub fn get_overlay_area(area: Rect) -> Rect {
+    Rect::new(
+        area.x + area.width / 10,
+        area.y + area.height / 10,
+        area.width - area.width / 5,
+        area.height - area.height / 5,
+    )
+}
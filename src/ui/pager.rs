@@ -0,0 +1,69 @@
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::Spans;
+use tui::widgets::{Block, Borders, List, ListItem};
+use crate::list::{Navigable, StatefulList};
+use crate::ui;
+use crate::ui::component::highlight;
+
+/// Columns a tab is expanded to, since the terminal would otherwise leave cells undrawn.
+const TAB_WIDTH: usize = 4;
+
+/// Text shown a line at a time, such as a preview of a file. Moving through it moves a cursor
+/// over the lines, and filtering keeps only the lines that match.
+pub struct PagerState {
+    pub title: String,
+    pub lines: StatefulList<String>,
+}
+
+impl PagerState {
+    pub fn new(title: String, text: &str) -> Self {
+        let lines = text.lines()
+            .map(|x| x.replace('\t', &" ".repeat(TAB_WIDTH)))
+            .collect();
+        let mut lines = StatefulList::with_items(lines);
+        lines.first();
+        PagerState { title, lines }
+    }
+}
+
+pub struct Pager {}
+
+impl ui::Ui<PagerState> for Pager {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut PagerState) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        let range = state.lines.scroll(inner.height as usize);
+        let filter = &state.lines.filter;
+        let items: Vec<ListItem> = state.lines.get_visible()
+            .skip(range.start)
+            .take(range.len())
+            .map(|x| {
+                let matched = filter.matches(x).unwrap_or_default();
+                ListItem::new(Spans::from(highlight(x, matched.first().map(|x| x.as_slice()).unwrap_or_default())))
+            })
+            .collect();
+
+        let line = state.lines.state.selected().map(|x| x + 1).unwrap_or_default();
+        let mut title = format!("{} (line {} of {})", state.title, line, state.lines.len());
+        if !filter.is_empty() {
+            title.push_str(&format!(" /{}", filter.input));
+        }
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+        f.render_stateful_widget(list, area, &mut state.lines.get_window_state());
+        Ok(())
+    }
+
+    fn hit(&self, area: Rect, state: &PagerState, column: u16, row: u16) -> Option<ui::Hit> {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        if !ui::contains(inner, column, row) {
+            return None;
+        }
+        Some(state.lines.get_index_at((row - inner.y) as usize).map(ui::Hit::Item).unwrap_or(ui::Hit::List))
+    }
+}
@@ -0,0 +1,98 @@
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph};
+use crate::ui;
+
+/// What a prompt's input will be used for once submitted.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Arn,
+    Command,
+    Filter,
+    /// Input an action asked for, as in `question`.
+    Answer,
+    /// The filter pattern for the log being followed.
+    Pattern,
+    /// Where to save the log being followed.
+    Save,
+}
+
+/// An action waiting on input before it can go ahead, such as where to download to.
+pub struct Question {
+    /// The view whose controller asked.
+    pub view: usize,
+    pub key: char,
+    pub id: Option<String>,
+    pub prompt: String,
+}
+
+/// A single line of user input, shown along the bottom of the screen.
+pub struct PromptState {
+    pub kind: PromptKind,
+    pub input: String,
+    /// Full lines the input could be completed to, best first.
+    pub completions: Vec<String>,
+    /// Position in the command history being shown, if the input was recalled from it.
+    pub history: Option<usize>,
+    pub question: Option<Question>,
+}
+
+impl PromptState {
+    pub fn new(kind: PromptKind) -> Self {
+        PromptState {
+            kind,
+            input: String::new(),
+            completions: vec![],
+            history: None,
+            question: None,
+        }
+    }
+
+    /// Ask `question`, with `input` to start from.
+    pub fn ask(question: Question, input: String) -> Self {
+        PromptState {
+            input,
+            question: Some(question),
+            ..PromptState::new(PromptKind::Answer)
+        }
+    }
+
+    pub fn get_label(&self) -> &str {
+        match self.kind {
+            PromptKind::Arn => "ARN: ",
+            PromptKind::Command => ":",
+            PromptKind::Filter => "/",
+            PromptKind::Answer => self.question.as_ref().map(|x| x.prompt.as_str()).unwrap_or_default(),
+            PromptKind::Pattern => "Filter pattern: ",
+            PromptKind::Save => "Save to: ",
+        }
+    }
+}
+
+pub struct Prompt {}
+
+impl ui::Ui<PromptState> for Prompt {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut PromptState) -> anyhow::Result<()>
+        where B: Backend {
+        let label = state.get_label();
+        // the rest of the best completion is hinted after the cursor
+        let hint = state.completions.first()
+            .and_then(|x| x.strip_prefix(state.input.as_str()))
+            .unwrap_or_default();
+        let line = Spans::from(vec![
+            Span::styled(label, Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(state.input.clone()),
+            Span::styled(hint, Style::default().fg(Color::DarkGray)),
+        ]);
+        let mut block = Block::default().borders(Borders::ALL);
+        if !state.completions.is_empty() {
+            block = block.title(format!("tab: {}", state.completions.join(" | ")));
+        }
+        f.render_widget(Paragraph::new(line).block(block), area);
+        f.set_cursor(area.x + 1 + (label.len() + state.input.chars().count()) as u16, area.y + 1);
+        Ok(())
+    }
+}
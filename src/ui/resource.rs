@@ -1,59 +1,385 @@
-use std::io::Stdout;
-use std::marker::PhantomData;
-use futures::StreamExt;
-use tui::backend::{Backend, CrosstermBackend};
-use tui::buffer::Buffer;
+use std::collections::HashMap;
+use std::time::Duration;
+use chrono::{DateTime, Local};
+use tokio::time::Instant;
+use tui::backend::Backend;
 use tui::Frame;
-use tui::layout::Rect;
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
-use tui::text::Spans;
-use tui::widgets::{Block, Borders, List, ListItem};
-use crate::app::StatefulList;
-use crate::service;
-use crate::service::{Service};
-use crate::service::resource::{Resource, ResourceController};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Sparkline, Wrap};
+use crate::app::SharedController;
+use crate::list::StatefulList;
+use crate::service::metrics::{Metric, Period, Series};
+use crate::service::resource::{Action, Progress, Reference, ResourceDescription, Row};
 use crate::ui;
-use crate::ui::component::TableList;
+use crate::filter::Filterable;
+use crate::ui::component::{highlight, Table};
 
-pub(crate) struct ResourceState {
-    pub(crate) items: StatefulList<String>,
+/// How long a row stays highlighted after a refresh changed it.
+pub const CHANGE_DURATION: Duration = Duration::from_secs(5);
+
+/// How often a listing with more to come is fetched again, however often it refreshes otherwise.
+pub const PENDING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a refresh did to a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Changed,
+    /// Gone from the listing, but kept in view until its highlight expires.
+    Removed,
 }
 
-pub(crate) struct ResourceUi<'a, R, T>
-{
-    ctrl: &'a R,
-    state: ResourceState,
-    _t: PhantomData<T>,
+/// A listing of every resource of one type, or of those within `scope`.
+pub struct ResourceListState {
+    pub view: usize,
+    pub service: String,
+    pub resource: String,
+    /// What the listing was narrowed to, as followed from a resource holding others.
+    pub scope: Option<Reference>,
+    pub ctrl: SharedController,
+    /// Column headers, as of the last listing for resources whose columns vary.
+    pub headers: Vec<String>,
+    pub actions: Vec<Action>,
+    pub items: StatefulList<Row>,
+    pub loading: bool,
+    /// How often to fetch the listing again, if at all.
+    pub interval: Option<Duration>,
+    /// Stops the listing refreshing itself until unpaused.
+    pub paused: bool,
+    /// When the listing was last requested, refreshes counting from then.
+    pub requested: Instant,
+    /// When the last listing arrived.
+    pub updated: Option<DateTime<Local>>,
+    /// Rows changed by recent refreshes, by ID, along with when they changed.
+    pub changes: HashMap<String, (Change, Instant)>,
+    /// How far the last listing had got, if it fills in over time.
+    pub progress: Option<Progress>,
 }
 
-impl <'a, R, T> ResourceUi<'a, R, T>
-    where R: ResourceController<T>,
-          T: Resource {
-    pub fn new(ctrl: &'a R) -> Self {
-        Self {
-            ctrl,
-            state: ResourceState { items: StatefulList::with_items(vec![]) },
-            _t: Default::default()
+impl ResourceListState {
+    /// The scope's label, for scoped listings, or the resource type.
+    pub fn get_title(&self) -> String {
+        match &self.scope {
+            Some(scope) => scope.label.clone(),
+            None => self.resource.clone(),
+        }
+    }
+
+    /// Take in a fresh listing, noting how each row differs from the last one. Rows that have
+    /// gone stay where they were until their highlight expires. The first listing isn't
+    /// compared, since there's nothing to compare it with.
+    pub fn update(&mut self, rows: Vec<Row>, now: DateTime<Local>) {
+        let time = Instant::now();
+        let mut items = rows;
+        if self.updated.is_some() {
+            let previous = &self.items.items;
+            for row in &items {
+                let change = match previous.iter().find(|x| x.id == row.id) {
+                    None => Some(Change::Added),
+                    Some(old) if old.columns != row.columns || old.tags != row.tags => Some(Change::Changed),
+                    // a row that went and came back again
+                    Some(_) if self.is_removed(&row.id) => Some(Change::Added),
+                    Some(_) => None,
+                };
+                if let Some(change) = change {
+                    self.changes.insert(row.id.clone(), (change, time));
+                }
+            }
+            let removed: Vec<(usize, Row)> = previous.iter()
+                .enumerate()
+                .filter(|(_, x)| !items.iter().any(|row| row.id == x.id))
+                .map(|(idx, x)| (idx, x.clone()))
+                .collect();
+            for (idx, row) in removed {
+                if !self.is_removed(&row.id) {
+                    self.changes.insert(row.id.clone(), (Change::Removed, time));
+                }
+                items.insert(idx.min(items.len()), row);
+            }
+        }
+        self.loading = false;
+        self.updated = Some(now);
+        self.items.replace(items);
+    }
+
+    fn is_removed(&self, id: &str) -> bool {
+        matches!(self.changes.get(id), Some((Change::Removed, _)))
+    }
+
+    /// Let highlights lapse, dropping the rows that were only kept to show their removal.
+    pub fn expire_changes(&mut self) {
+        let expired: Vec<(String, Change)> = self.changes.iter()
+            .filter(|(_, (_, time))| time.elapsed() >= CHANGE_DURATION)
+            .map(|(id, (change, _))| (id.clone(), *change))
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+        for (id, _) in &expired {
+            self.changes.remove(id);
+        }
+        let gone: Vec<&String> = expired.iter()
+            .filter(|(_, change)| *change == Change::Removed)
+            .map(|(id, _)| id)
+            .collect();
+        if !gone.is_empty() {
+            let items = self.items.items.iter()
+                .filter(|x| !gone.contains(&&x.id))
+                .cloned()
+                .collect();
+            self.items.replace(items);
+        }
+    }
+
+    /// Whether it's time to fetch the listing again.
+    pub fn is_due(&self) -> bool {
+        let interval = match &self.progress {
+            Some(progress) if progress.pending => Some(PENDING_INTERVAL),
+            _ => self.interval,
+        };
+        match interval {
+            Some(interval) => !self.paused && !self.loading && self.requested.elapsed() >= interval,
+            None => false,
         }
     }
+}
+
+/// The description of a single resource, along with the resources it refers to.
+pub struct ResourceDetailState {
+    pub view: usize,
+    pub target: Reference,
+    pub ctrl: SharedController,
+    /// The controller's actions, performed on the resource described.
+    pub actions: Vec<Action>,
+    pub description: Option<ResourceDescription<Row>>,
+    pub references: StatefulList<Reference>,
+    pub loading: bool,
+    /// Lines of properties scrolled past.
+    pub scroll: u16,
+    /// What the resource type reports, charted below the properties.
+    pub metrics: Vec<Metric>,
+    pub period: Period,
+    /// The values of `metrics` over `period`, once fetched.
+    pub series: Vec<Series>,
+    pub metrics_loading: bool,
+}
+
+/// The keys detail views charting metrics handle themselves, with what they do.
+pub const METRICS_KEYS: &[(char, &str)] = &[
+    ('M', "Chart metrics over 1h, 6h or 24h"),
+];
 
-    pub async fn init(&mut self) -> anyhow::Result<()> {
-        self.state.items.items = self.ctrl.list().await?
-            .iter()
-            .map(|x| x.get_name())
+pub struct ResourceList {}
+
+impl ui::Ui<ResourceListState> for ResourceList {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut ResourceListState) -> anyhow::Result<()>
+        where B: Backend {
+        let mut title = state.get_title();
+        match state.updated {
+            None if state.loading => title.push_str(" (loading)"),
+            None => {}
+            Some(updated) => {
+                let mut notes: Vec<String> = state.progress.iter().map(|x| x.note.clone()).collect();
+                notes.push(format!("updated {}", updated.format("%H:%M:%S")));
+                if state.loading {
+                    notes.push("refreshing".to_string());
+                }
+                match state.interval {
+                    _ if state.paused => notes.push("paused, p to resume".to_string()),
+                    Some(interval) => notes.push(format!("every {}, p to pause", format_interval(interval))),
+                    None => {}
+                }
+                title.push_str(&format!(" ({})", notes.join(", ")));
+            }
+        }
+        let changes = state.changes.iter()
+            .map(|(id, (change, _))| (id.clone(), *change))
             .collect();
+        let mut table = Table { title, headers: state.headers.clone(), changes };
+        table.ui(f, area, &mut state.items)?;
         Ok(())
     }
+
+    fn hit(&self, area: Rect, state: &ResourceListState, column: u16, row: u16) -> Option<ui::Hit> {
+        let table = Table { title: String::new(), headers: vec![], changes: HashMap::new() };
+        table.hit(area, &state.items, column, row)
+    }
 }
 
-impl <'a, R, T> ui::Ui<()> for ResourceUi<'a, R, T>
-    where R: ResourceController<T>,
-          T: Resource,
-{
-    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut ()) -> anyhow::Result<()>
+/// `90s` or `5m`, say.
+fn format_interval(interval: Duration) -> String {
+    match interval.as_secs() {
+        secs if secs >= 60 && secs % 60 == 0 => format!("{}m", secs / 60),
+        secs => format!("{}s", secs),
+    }
+}
+
+pub struct ResourceDetail {}
+
+impl ui::Ui<ResourceDetailState> for ResourceDetail {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut ResourceDetailState) -> anyhow::Result<()>
         where B: Backend {
-        let mut tl = TableList {};
-        tl.ui(f, area, &mut self.state.items)?;
+        let chunks = split(area, state.metrics.len());
+
+        let title = format!("{} / {}", state.target.service, state.target.resource);
+        let lines = match &state.description {
+            Some(description) => {
+                let mut props: Vec<_> = description.props.iter().collect();
+                props.sort();
+                let width = props.iter()
+                    .filter(|(_, v)| !v.contains('\n'))
+                    .map(|(k, _)| k.len())
+                    .max()
+                    .unwrap_or_default();
+                // values across several lines, such as JSON documents, go indented under their name
+                let mut lines = vec![];
+                for (k, v) in props {
+                    let bold = Style::default().add_modifier(Modifier::BOLD);
+                    if v.contains('\n') {
+                        lines.push(Spans::from(Span::styled(k.clone(), bold)));
+                        lines.extend(v.lines().map(|x| Spans::from(format!("  {}", x))));
+                    } else {
+                        lines.push(Spans::from(vec![
+                            Span::styled(format!("{:width$}  ", k, width = width), bold),
+                            Span::raw(v.clone()),
+                        ]));
+                    }
+                }
+                lines
+            }
+            None if state.loading => vec![Spans::from("Loading...")],
+            None => vec![Spans::from(format!("{} not found", state.target.id))],
+        };
+        let props = Paragraph::new(lines)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .wrap(Wrap { trim: false })
+            .scroll((state.scroll, 0));
+        f.render_widget(props, chunks[0]);
+
+        let range = state.references.scroll(get_reference_rows(chunks[1]));
+        let filter = &state.references.filter;
+        let items: Vec<ListItem> = state.references.get_visible()
+            .skip(range.start)
+            .take(range.len())
+            .map(|x| {
+                let matched = filter.matches(x).unwrap_or_default();
+                let mut target = vec![Span::raw("  ")];
+                target.extend(highlight(&x.get_fields()[1], &matched[1]));
+                ListItem::new(vec![
+                    Spans::from(highlight(&x.label, &matched[0])
+                        .into_iter()
+                        .map(|mut x| {
+                            x.style = x.style.add_modifier(Modifier::BOLD);
+                            x
+                        })
+                        .collect::<Vec<_>>()),
+                    Spans::from(target),
+                ])
+            })
+            .collect();
+        let mut title = "References (enter to follow)".to_string();
+        if !filter.is_empty() {
+            title.push_str(&format!(" /{}", filter.input));
+        }
+        let references = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(references, chunks[1], &mut state.references.get_window_state());
+
+        if !state.metrics.is_empty() {
+            draw_metrics(f, chunks[2], state);
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn hit(&self, area: Rect, state: &ResourceDetailState, column: u16, row: u16) -> Option<ui::Hit> {
+        let chunks = split(area, state.metrics.len());
+        if ui::contains(chunks[0], column, row) {
+            return Some(ui::Hit::Props);
+        }
+        let inner = Block::default().borders(Borders::ALL).inner(chunks[1]);
+        if !ui::contains(inner, column, row) {
+            return None;
+        }
+        let idx = state.references.get_index_at(((row - inner.y) / REFERENCE_HEIGHT) as usize);
+        Some(idx.map(ui::Hit::Item).unwrap_or(ui::Hit::List))
+    }
+}
+
+/// Lines taken by each reference: its label, then its target.
+const REFERENCE_HEIGHT: u16 = 2;
+
+/// Lines taken by each metric: its name and figures, then its sparkline.
+const METRIC_HEIGHT: u16 = 3;
+
+/// Properties on the left, above a chart of any `metrics`, and references on the right.
+fn split(area: Rect, metrics: usize) -> Vec<Rect> {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(area);
+    // the chart takes no more than half the column, leaving the properties room
+    let height = match metrics {
+        0 => 0,
+        n => (2 + n as u16 * METRIC_HEIGHT).min(columns[0].height / 2),
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(height)].as_ref())
+        .split(columns[0]);
+    vec![rows[0], columns[1], rows[1]]
+}
+
+/// A sparkline for each metric, over the last of its values that fit, with its latest and
+/// highest value.
+fn draw_metrics<B: Backend>(f: &mut Frame<B>, area: Rect, state: &ResourceDetailState) {
+    let mut title = format!("Metrics, last {} (M for {})", state.period.get_label(), state.period.next().get_label());
+    if state.metrics_loading {
+        title.push_str(" (loading)");
+    }
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    for (i, metric) in state.metrics.iter().enumerate() {
+        let y = inner.y + i as u16 * METRIC_HEIGHT;
+        if y + METRIC_HEIGHT > inner.bottom() {
+            break;
+        }
+        let series = state.series.iter().find(|x| x.metric == *metric);
+        let figures = match series.and_then(|x| Some((x.get_latest()?, x.get_max()?))) {
+            Some((latest, max)) => format!("latest {}, max {}", metric.unit.format(latest), metric.unit.format(max)),
+            None if state.metrics_loading => String::new(),
+            None => "no data".to_string(),
+        };
+        let label = Spans::from(vec![
+            Span::styled(metric.name, Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" {}  {}", metric.statistic.to_lowercase(), figures), Style::default().fg(Color::DarkGray)),
+        ]);
+        f.render_widget(Paragraph::new(label), Rect::new(inner.x, y, inner.width, 1));
+
+        let values = series.map(|x| x.values.as_slice()).unwrap_or_default();
+        let values = &values[values.len().saturating_sub(inner.width as usize)..];
+        let max = values.iter().flatten().copied().fold(0.0, f64::max);
+        // scaled, as sparklines take whole numbers, with steps nothing was reported in empty
+        let data: Vec<u64> = values.iter()
+            .map(|x| match x {
+                Some(value) if max > 0.0 => (value / max * 100.0).round() as u64,
+                _ => 0,
+            })
+            .collect();
+        let sparkline = Sparkline::default()
+            .data(&data)
+            .max(100)
+            .style(Style::default().fg(Color::LightGreen));
+        f.render_widget(sparkline, Rect::new(inner.x, y + 1, inner.width, METRIC_HEIGHT - 1));
+    }
+}
+
+/// References that fit in `area`, within its border.
+fn get_reference_rows(area: Rect) -> usize {
+    (area.height.saturating_sub(2) / REFERENCE_HEIGHT) as usize
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use chrono::TimeZone;
+use tokio::time::{Duration, Instant};
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem};
+use crate::app::SharedController;
+use crate::list::{Navigable, StatefulList};
+use crate::service::resource::LogEvent;
+use crate::ui;
+
+/// How often the log is read on from where it got to.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Events kept in the buffer. The oldest go as more arrive.
+const MAX_EVENTS: usize = 10_000;
+
+/// The keys the tail view handles itself, with what they do.
+pub const KEYS: &[(char, &str)] = &[
+    ('f', "Follow new events"),
+    ('u', "Switch between local time and UTC"),
+    ('J', "Lay out JSON messages"),
+    ('F', "Set the filter pattern"),
+    ('w', "Save the buffer to a file"),
+];
+
+/// A log being followed: the events read so far, polled for more every `POLL_INTERVAL`.
+pub struct TailState {
+    pub view: usize,
+    pub ctrl: SharedController,
+    /// The log, as the controller knows it.
+    pub id: String,
+    pub title: String,
+    /// What events must match, in the service's own syntax. Everything if empty.
+    pub pattern: String,
+    pub events: StatefulList<LogEvent>,
+    /// Whether the latest event stays selected as more arrive.
+    pub follow: bool,
+    pub utc: bool,
+    /// Whether JSON messages are laid out across lines.
+    pub pretty: bool,
+    pub paused: bool,
+    pub loading: bool,
+    pub requested: Instant,
+    /// The latest event's timestamp, which the next read starts from, and the IDs of the events
+    /// read with it, which it reads again.
+    since: Option<i64>,
+    seen: HashSet<String>,
+}
+
+impl TailState {
+    pub fn new(view: usize, ctrl: SharedController, id: String, title: String) -> Self {
+        TailState {
+            view,
+            ctrl,
+            id,
+            title,
+            pattern: String::new(),
+            events: StatefulList::with_items(vec![]).with_key(|x| x.id.clone()),
+            follow: true,
+            utc: false,
+            pretty: false,
+            paused: false,
+            loading: true,
+            requested: Instant::now(),
+            since: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn get_since(&self) -> Option<i64> {
+        self.since
+    }
+
+    pub fn is_due(&self) -> bool {
+        !self.loading && !self.paused && self.requested.elapsed() >= POLL_INTERVAL
+    }
+
+    /// Add the events read since last time, leaving out any already there.
+    pub fn append(&mut self, events: Vec<LogEvent>) {
+        let fresh: Vec<LogEvent> = events.into_iter()
+            .filter(|x| !self.seen.contains(&x.id))
+            .collect();
+        if let Some(latest) = fresh.iter().map(|x| x.timestamp).max() {
+            if self.since != Some(latest) {
+                self.seen.clear();
+            }
+            self.since = Some(latest);
+            self.seen.extend(fresh.iter().filter(|x| x.timestamp == latest).map(|x| x.id.clone()));
+        }
+
+        if fresh.is_empty() {
+            return;
+        }
+        // copied rather than taken, as `replace` looks up the selected item among them first
+        let mut items = self.events.items.clone();
+        items.extend(fresh);
+        items.drain(..items.len().saturating_sub(MAX_EVENTS));
+        self.events.replace(items);
+        if self.follow {
+            self.events.last();
+        }
+    }
+
+    /// Start again from recent events, matching `pattern`.
+    pub fn set_pattern(&mut self, pattern: String) {
+        self.pattern = pattern;
+        self.since = None;
+        self.seen.clear();
+        self.events.replace(vec![]);
+    }
+
+    /// The time `event` happened, as shown, and its message's lines, laid out if it's JSON and
+    /// that's asked for.
+    pub fn format_event(&self, event: &LogEvent) -> (String, Vec<String>) {
+        (format_time(event.timestamp, self.utc), format_message(&event.message, self.pretty))
+    }
+
+    /// The selected event, titled by when and where it was written, with its message in full.
+    pub fn get_selected_text(&self) -> Option<(String, String)> {
+        let event = self.events.selected()?;
+        let title = format!("{} {}", format_time(event.timestamp, self.utc), event.source);
+        Some((title, format_message(&event.message, true).join("\n")))
+    }
+
+    /// The events that pass the filter, as shown, each line after an event's first indented.
+    pub fn get_text(&self) -> String {
+        let mut text = String::new();
+        for event in self.events.get_visible() {
+            let (time, lines) = self.format_event(event);
+            text.push_str(&time);
+            for (i, line) in lines.iter().enumerate() {
+                text.push_str(if i == 0 { " " } else { "\n  " });
+                text.push_str(line);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// The lines of `message`, laid out if it's a JSON object or array and `pretty` is set.
+fn format_message(message: &str, pretty: bool) -> Vec<String> {
+    let message = message.trim_end();
+    let json = match pretty {
+        true => serde_json::from_str::<serde_json::Value>(message).ok().filter(|x| x.is_object() || x.is_array()),
+        false => None,
+    };
+    match json.and_then(|x| serde_json::to_string_pretty(&x).ok()) {
+        Some(text) => text.lines().map(|x| x.to_string()).collect(),
+        None => message.lines().map(|x| x.to_string()).collect(),
+    }
+}
+
+/// `timestamp`, in milliseconds since the epoch, as the time of day with its date, e.g.
+/// `2021-11-05 09:30:00.125`, in UTC or local time.
+fn format_time(timestamp: i64, utc: bool) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+    match (utc, chrono::Utc.timestamp_millis_opt(timestamp).single()) {
+        (true, Some(time)) => time.format(FORMAT).to_string(),
+        (false, Some(time)) => time.with_timezone(&chrono::Local).format(FORMAT).to_string(),
+        (_, None) => timestamp.to_string(),
+    }
+}
+
+pub struct Tail {}
+
+impl ui::Ui<TailState> for Tail {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut TailState) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        let range = state.events.scroll(inner.height as usize);
+        let items: Vec<ListItem> = state.events.get_visible()
+            .skip(range.start)
+            .take(range.len())
+            .map(|x| {
+                let (time, lines) = state.format_event(x);
+                let first = lines.first().cloned().unwrap_or_default();
+                let mut spans = vec![Spans::from(vec![
+                    Span::styled(time, Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!(" {}", first)),
+                ])];
+                spans.extend(lines.iter().skip(1).map(|x| Spans::from(format!("  {}", x))));
+                ListItem::new(spans)
+            })
+            .collect();
+
+        let mut flags = vec![format!("{} events", state.events.len())];
+        if !state.pattern.is_empty() {
+            flags.push(format!("pattern {}", state.pattern));
+        }
+        if state.paused {
+            flags.push("paused".to_string());
+        } else if state.follow {
+            flags.push("following".to_string());
+        }
+        flags.push(if state.utc { "UTC" } else { "local time" }.to_string());
+        let mut title = format!("{} ({})", state.title, flags.join(", "));
+        if !state.events.filter.is_empty() {
+            title.push_str(&format!(" /{}", state.events.filter.input));
+        }
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+        f.render_stateful_widget(list, area, &mut state.events.get_window_state());
+        Ok(())
+    }
+
+    fn hit(&self, area: Rect, state: &TailState, column: u16, row: u16) -> Option<ui::Hit> {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        if !ui::contains(inner, column, row) {
+            return None;
+        }
+        Some(state.events.get_index_at((row - inner.y) as usize).map(ui::Hit::Item).unwrap_or(ui::Hit::List))
+    }
+}
@@ -0,0 +1,99 @@
+use tui::backend::Backend;
+use tui::Frame;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use crate::transfer::{Status, Transfer, TransferQueue, Unit};
+use crate::ui;
+
+/// Width of the progress bar, in cells.
+const BAR_WIDTH: usize = 20;
+
+/// The transfer queue over most of the screen, each transfer with its progress.
+pub struct Transfers {
+    pub selected: usize,
+}
+
+impl ui::Ui<TransferQueue> for Transfers {
+    fn ui<B>(&mut self, f: &mut Frame<B>, area: Rect, state: &mut TransferQueue) -> anyhow::Result<()>
+        where B: Backend
+    {
+        let rect = ui::get_overlay_area(area);
+        let mut items: Vec<ListItem> = state.transfers.iter()
+            .map(|x| ListItem::new(vec![
+                Spans::from(vec![
+                    Span::styled(format!("{:9} ", get_label(&x.status)), get_style(&x.status)),
+                    Span::styled(x.job.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
+                Spans::from(format!("          {}", get_progress(x))),
+            ]))
+            .collect();
+        let mut list_state = ListState::default();
+        if items.is_empty() {
+            items.push(ListItem::new("No transfers"));
+        } else {
+            list_state.select(Some(self.selected));
+        }
+
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .block(Block::default()
+                .title("Transfers (c to cancel, r to retry, x to clear done, esc to close)")
+                .borders(Borders::ALL));
+        f.render_widget(Clear, rect);
+        f.render_stateful_widget(list, rect, &mut list_state);
+        Ok(())
+    }
+}
+
+fn get_label(status: &Status) -> &'static str {
+    match status {
+        Status::Queued => "queued",
+        Status::Running => "running",
+        Status::Done => "done",
+        Status::Failed(_) => "failed",
+        Status::Cancelled => "cancelled",
+    }
+}
+
+fn get_style(status: &Status) -> Style {
+    match status {
+        Status::Running => Style::default().fg(Color::Cyan),
+        Status::Done => Style::default().fg(Color::Green),
+        Status::Failed(_) => Style::default().fg(Color::Red),
+        Status::Queued | Status::Cancelled => Style::default().fg(Color::DarkGray),
+    }
+}
+
+/// A bar and counts while running, with the speed; the error once failed.
+fn get_progress(transfer: &Transfer) -> String {
+    let (done, total) = transfer.progress.get();
+    let unit = transfer.job.unit;
+    match &transfer.status {
+        Status::Queued => String::new(),
+        Status::Failed(err) => err.lines().next().unwrap_or_default().to_string(),
+        Status::Done => unit.format(done),
+        Status::Cancelled => format!("{} of {}", unit.format(done), unit.format(total)),
+        Status::Running => {
+            let fraction = if total > 0 { (done as f64 / total as f64).min(1.0) } else { 0.0 };
+            let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+            let mut progress = format!(
+                "[{}{}] {:3.0}%  {} of {}",
+                "#".repeat(filled),
+                ".".repeat(BAR_WIDTH - filled),
+                fraction * 100.0,
+                unit.format(done),
+                unit.format(total),
+            );
+            if let Some(throughput) = transfer.get_throughput() {
+                let throughput = match unit {
+                    Unit::Bytes => unit.format(throughput as u64),
+                    Unit::Items(noun) => format!("{:.1} {}", throughput, noun),
+                };
+                progress.push_str(&format!("  {}/s", throughput));
+            }
+            progress
+        }
+    }
+}
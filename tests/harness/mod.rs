@@ -0,0 +1,511 @@
+//! Drives an `App` with scripted key presses and compares what it draws against golden files in
+//! `tests/snapshots`. Run with `UPDATE_SNAPSHOTS=1` to write the files afresh after an intended
+//! change to the UI, then review the diff.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use async_trait::async_trait;
+use chrono::{Local, TimeZone, Utc};
+use cloud_ui::{App, Context, Registry, Resource, ResourceController, ResourceDescription, ServiceType};
+use cloud_ui::service::ProviderKind;
+use cloud_ui::service::metrics::{Metric, Period, Series, Unit};
+use cloud_ui::service::resource::{is_confirmed, Action, ActionOutcome, LogEvent, Progress, Reference};
+use cloud_ui::transfer::Job;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use serde::Deserialize;
+use tui::backend::TestBackend;
+use tui::Terminal;
+
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 16;
+
+pub const SERVICE: &str = "Grocer";
+
+/// A fake resource, served by `Fruits` without any cloud behind it.
+#[derive(Clone, Deserialize)]
+pub struct Fruit {
+    name: String,
+    colour: String,
+    stock: u32,
+    /// Name of another fruit, shown as a reference in the detail view.
+    pairs_with: Option<String>,
+}
+
+impl Resource for Fruit {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_headers() -> Vec<&'static str> {
+        vec!["Name", "Colour", "Stock"]
+    }
+
+    fn get_columns(&self) -> Vec<String> {
+        vec![self.name.clone(), self.colour.clone(), self.stock.to_string()]
+    }
+
+    fn get_references(&self) -> Vec<Reference> {
+        self.pairs_with.iter()
+            .map(|x| Reference::new("Pairs with", SERVICE, "Fruits", x.as_str()))
+            .collect()
+    }
+}
+
+pub struct Fruits {
+    fruits: Vec<Fruit>,
+}
+
+impl Fruits {
+    fn new(_context: &Context) -> Self {
+        let fruit = |name: &str, colour: &str, stock, pairs_with: Option<&str>| Fruit {
+            name: name.to_string(),
+            colour: colour.to_string(),
+            stock,
+            pairs_with: pairs_with.map(|x| x.to_string()),
+        };
+        Fruits {
+            fruits: vec![
+                fruit("apple", "red", 12, Some("blackberry")),
+                fruit("banana", "yellow", 30, None),
+                fruit("blackberry", "purple", 200, Some("apple")),
+                fruit("grape", "green", 150, None),
+                fruit("papaya", "orange", 3, Some("banana")),
+            ],
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceController<Fruit> for Fruits {
+    async fn list(&self) -> anyhow::Result<Vec<Fruit>> {
+        Ok(self.fruits.clone())
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Fruit>>> {
+        Ok(self.fruits.iter()
+            .find(|x| x.name == id)
+            .map(ResourceDescription::from_resource))
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![Action { key: 'w', name: "Weigh" }]
+    }
+
+    /// Weighing changes nothing, but has the view fetched afresh, as actions that do would.
+    async fn perform(&mut self, _key: char, _id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        Ok(ActionOutcome::Reload)
+    }
+}
+
+/// A listing that changes after the first time it's fetched: banana sells out, grape drops in
+/// stock and kiwi arrives.
+pub struct Market {
+    calls: AtomicUsize,
+}
+
+impl Market {
+    fn new(_context: &Context) -> Self {
+        Market { calls: AtomicUsize::new(0) }
+    }
+}
+
+#[async_trait]
+impl ResourceController<Fruit> for Market {
+    async fn list(&self) -> anyhow::Result<Vec<Fruit>> {
+        let fruit = |name: &str, colour: &str, stock| Fruit {
+            name: name.to_string(),
+            colour: colour.to_string(),
+            stock,
+            pairs_with: None,
+        };
+        Ok(match self.calls.fetch_add(1, Ordering::SeqCst) {
+            0 => vec![fruit("apple", "red", 12), fruit("banana", "yellow", 30), fruit("grape", "green", 150)],
+            _ => vec![fruit("kiwi", "brown", 40), fruit("apple", "red", 12), fruit("grape", "green", 140)],
+        })
+    }
+
+    async fn describe(&self, _id: String) -> anyhow::Result<Option<ResourceDescription<Fruit>>> {
+        Ok(None)
+    }
+}
+
+/// A listing that fills in over three fetches, a fruit at a time, as a query's results do.
+pub struct Harvest {
+    calls: AtomicUsize,
+}
+
+impl Harvest {
+    fn new(_context: &Context) -> Self {
+        Harvest { calls: AtomicUsize::new(0) }
+    }
+
+    fn get_picked(&self) -> usize {
+        self.calls.load(Ordering::SeqCst).min(3)
+    }
+}
+
+#[async_trait]
+impl ResourceController<Fruit> for Harvest {
+    async fn list(&self) -> anyhow::Result<Vec<Fruit>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let fruits = [("pear", "green"), ("plum", "purple"), ("quince", "yellow")];
+        Ok(fruits.iter()
+            .take(self.get_picked())
+            .map(|(name, colour)| Fruit { name: name.to_string(), colour: colour.to_string(), stock: 1, pairs_with: None })
+            .collect())
+    }
+
+    async fn describe(&self, _id: String) -> anyhow::Result<Option<ResourceDescription<Fruit>>> {
+        Ok(None)
+    }
+
+    fn get_progress(&self) -> Option<Progress> {
+        let picked = self.get_picked();
+        let state = if picked < 3 { "Picking" } else { "Picked" };
+        Some(Progress { note: format!("{}, {} of 3", state, picked), pending: picked < 3 })
+    }
+}
+
+/// Fails every request, as a controller without permission would.
+pub struct Rotten {}
+
+impl Rotten {
+    fn new(_context: &Context) -> Self {
+        Rotten {}
+    }
+}
+
+#[async_trait]
+impl ResourceController<Fruit> for Rotten {
+    async fn list(&self) -> anyhow::Result<Vec<Fruit>> {
+        Err(anyhow::anyhow!(r#"Error {{ code: "AccessDeniedException", message: "not allowed to list" }}"#))
+    }
+
+    async fn describe(&self, _id: String) -> anyhow::Result<Option<ResourceDescription<Fruit>>> {
+        Err(anyhow::anyhow!("not found"))
+    }
+}
+
+/// Jars on shelves, nested like files in folders: `shelf/` holds `shelf/jar`. Opening a shelf
+/// lists its jars, a jar's label can be read in the pager or rewritten in the editor, and jars
+/// can be fetched through the transfer queue, or thrown out once confirmed. The column is headed
+/// for what's listed, shelves or jars.
+#[derive(Clone, Deserialize)]
+pub struct Jar {
+    path: String,
+}
+
+impl Resource for Jar {
+    type Id = String;
+
+    fn get_id(&self) -> String {
+        self.path.clone()
+    }
+
+    fn get_name(&self) -> String {
+        self.path.trim_end_matches('/').rsplit('/').next().unwrap_or_default().to_string()
+    }
+
+    fn get_contents(&self) -> Option<Reference> {
+        self.path.ends_with('/')
+            .then(|| Reference::listing(&self.get_name(), SERVICE, "Pantry", self.path.as_str()))
+    }
+
+    fn get_metrics() -> Vec<Metric> {
+        vec![
+            Metric::new("Level", "Average", Unit::Percent),
+            Metric::new("Opened", "Sum", Unit::Count),
+        ]
+    }
+}
+
+pub struct Pantry {
+    scope: String,
+    /// Times the log has been tailed, each of which finds another event written to it.
+    reads: AtomicUsize,
+}
+
+impl Pantry {
+    fn new(_context: &Context) -> Self {
+        Pantry { scope: String::new(), reads: AtomicUsize::new(0) }
+    }
+}
+
+/// What's been happening to the jars, as `(seconds past 09:30, message)`. Two events share a
+/// time, so reads carrying on from it see them again.
+const PANTRY_LOG: &[(i64, &str)] = &[
+    (0, "jam opened"),
+    (1, r#"{"jar":"jam","level":"low"}"#),
+    (1, "jam lid tightened"),
+    (5, "honey lid loosened"),
+    (9, "honey opened"),
+];
+
+#[async_trait]
+impl ResourceController<Jar> for Pantry {
+    async fn list(&self) -> anyhow::Result<Vec<Jar>> {
+        let paths: &[&str] = match self.scope.as_str() {
+            "" => &["top/", "bottom/"],
+            "top/" => &["top/jam", "top/honey"],
+            _ => &[],
+        };
+        Ok(paths.iter().map(|x| Jar { path: x.to_string() }).collect())
+    }
+
+    async fn describe(&self, id: String) -> anyhow::Result<Option<ResourceDescription<Jar>>> {
+        Ok(Some(ResourceDescription::from_resource(&Jar { path: id })))
+    }
+
+    fn get_headers(&self) -> Vec<String> {
+        let header = if self.scope.is_empty() { "Shelf" } else { "Jar" };
+        vec![header.to_string()]
+    }
+
+    fn set_scope(&mut self, scope: String) -> anyhow::Result<()> {
+        self.scope = scope;
+        Ok(())
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        vec![
+            Action { key: 'o', name: "Read the label" },
+            Action { key: 'f', name: "Fetch" },
+            Action { key: 'e', name: "Relabel" },
+            Action { key: 'n', name: "Note" },
+            Action { key: 'x', name: "Throw out" },
+            Action { key: 'T', name: "Watch" },
+        ]
+    }
+
+    async fn perform(&mut self, key: char, id: Option<String>) -> anyhow::Result<ActionOutcome> {
+        let id = id.unwrap_or_default();
+        match key {
+            'f' => return Ok(ActionOutcome::Ask { prompt: format!("Fetch {} to: ", id), input: "kitchen".to_string() }),
+            'e' => return Ok(ActionOutcome::Edit { name: "label.txt".to_string(), text: id }),
+            'n' => return Ok(ActionOutcome::Compose { name: "note.txt".to_string(), text: "Best before".to_string() }),
+            'x' => return Ok(ActionOutcome::confirm(&format!("Throw out {}?", id))),
+            'T' => return Ok(ActionOutcome::Tail { title: format!("{} log", id), id }),
+            _ => {}
+        }
+        let text = (1..=20).map(|x| format!("{}, line {}", id, x)).collect::<Vec<_>>().join("\n");
+        Ok(ActionOutcome::Show { title: id, text })
+    }
+
+    /// Fetching a jar moves its 512 bytes at once, unless it's to the cellar, which is locked.
+    async fn respond(&mut self, key: char, id: Option<String>, input: String) -> anyhow::Result<ActionOutcome> {
+        let id = id.unwrap_or_default();
+        match key {
+            'e' => return Ok(ActionOutcome::Status(format!("Relabelled {} as {}", id, input))),
+            'n' => return Ok(ActionOutcome::Status(format!("Noted on {}: {}", id, input))),
+            'x' if is_confirmed(&input) => return Ok(ActionOutcome::Status(format!("Threw out {}", id))),
+            'x' => return Ok(ActionOutcome::Status(format!("Kept {}", id))),
+            _ => {}
+        }
+        let job = Job::new(format!("{} to {}", id, input), move |progress| {
+            let locked = input == "cellar";
+            Box::pin(async move {
+                progress.set_total(512);
+                if locked {
+                    anyhow::bail!("the cellar is locked");
+                }
+                progress.add(512);
+                Ok(())
+            })
+        });
+        Ok(ActionOutcome::Transfer(vec![job]))
+    }
+
+    /// The level falls steadily over any period, while the jar was only opened in its second
+    /// half, every tenth step.
+    async fn metrics(&self, _id: String, period: Period) -> anyhow::Result<Vec<Series>> {
+        let steps = period.get_steps();
+        let level = (0..steps).map(|i| Some(100.0 - (i * 80 / steps) as f64)).collect();
+        let opened = (0..steps).map(|i| (i >= steps / 2).then_some((i % 10 == 0) as u8 as f64)).collect();
+        let metrics = Jar::get_metrics();
+        Ok(vec![
+            Series { metric: metrics[0], values: level },
+            Series { metric: metrics[1], values: opened },
+        ])
+    }
+
+    /// The first three events of `PANTRY_LOG`, then one more with each read.
+    async fn tail(&self, _id: String, pattern: String, since: Option<i64>) -> anyhow::Result<Vec<LogEvent>> {
+        let start = Utc.with_ymd_and_hms(2021, 11, 5, 9, 30, 0).unwrap().timestamp_millis();
+        let written = 3 + self.reads.fetch_add(1, Ordering::SeqCst);
+        Ok(PANTRY_LOG.iter()
+            .take(written)
+            .enumerate()
+            .map(|(i, (seconds, message))| LogEvent {
+                id: i.to_string(),
+                timestamp: start + seconds * 1000,
+                source: "shelf".to_string(),
+                message: message.to_string(),
+            })
+            .filter(|x| since.is_none_or(|since| x.timestamp >= since) && x.message.contains(&pattern))
+            .collect())
+    }
+}
+
+/// An app with only the fake service registered, drawn onto a `TestBackend`.
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut registry = Registry::default();
+        registry.register(Box::new(ServiceType::new(SERVICE)
+            .resource("Fruits", Fruits::new)
+            .resource("Rotten", Rotten::new)
+            .resource("Market", Market::new)
+            .resource("Pantry", Pantry::new)
+            .resource("Harvest", Harvest::new)));
+        registry.register(Box::new(ServiceType::new("Empty")));
+        Harness::with_registry(registry)
+    }
+
+    /// An app with the AWS services registered, serving the repository's `fixtures`.
+    pub fn with_fixtures() -> Self {
+        let mut registry = Registry::default();
+        cloud_ui::cloud::aws::register(&mut registry);
+        cloud_ui::cloud::fixtures::register(&mut registry);
+        let mut harness = Harness::with_registry(registry);
+        // relative, as tests run from the crate's directory, so the header is the same anywhere
+        let dir = PathBuf::from("fixtures");
+        harness.app.context = Context { provider: ProviderKind::Fixtures(dir), ..Context::default() };
+        harness
+    }
+
+    /// An app with the AWS services registered against the cloud, for what can be done without
+    /// reaching it, such as the prompts actions ask with.
+    pub fn with_aws() -> Self {
+        let mut registry = Registry::default();
+        cloud_ui::cloud::aws::register(&mut registry);
+        Harness::with_registry(registry)
+    }
+
+    fn with_registry(registry: Registry) -> Self {
+        let mut app = App::new(registry);
+        // timestamps would otherwise differ from one run to the next
+        app.clock = || Local.with_ymd_and_hms(2021, 11, 5, 9, 30, 0).unwrap();
+        Harness {
+            app,
+            terminal: Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap(),
+        }
+    }
+
+    /// Press `key` while holding control.
+    pub async fn press_ctrl(&mut self, key: char) -> bool {
+        let running = self.app.on_key_press(KeyEvent::new(KeyCode::Char(key), KeyModifiers::CONTROL));
+        self.settle().await;
+        running
+    }
+
+    /// Press each key in turn, as `run_app` would, letting any requests they make complete
+    /// before the next. Returns whether the app is still running.
+    pub async fn press(&mut self, keys: &[KeyCode]) -> bool {
+        for key in keys {
+            if !self.app.on_key_press(KeyEvent::new(*key, KeyModifiers::NONE)) {
+                return false;
+            }
+            self.settle().await;
+        }
+        true
+    }
+
+    /// Let a tick pass, as `run_app` does between key presses, first letting any transfers
+    /// that are running get as far as they can.
+    pub async fn tick(&mut self) {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        self.app.on_tick();
+        self.settle().await;
+    }
+
+    /// Click at `column`, `row`. The screen is drawn first, as `run_app` does before reading
+    /// events, so that there is something to click on.
+    pub async fn click(&mut self, column: u16, row: u16) {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row).await;
+    }
+
+    /// Turn the mouse wheel over `column`, `row`, towards the bottom if `down`.
+    pub async fn wheel(&mut self, column: u16, row: u16, down: bool) {
+        let kind = if down { MouseEventKind::ScrollDown } else { MouseEventKind::ScrollUp };
+        self.mouse(kind, column, row).await;
+    }
+
+    async fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) {
+        self.render();
+        self.app.on_mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE });
+        self.settle().await;
+    }
+
+    /// Save `text` from the editor the app is waiting on, as `run_app` would once it exits.
+    pub async fn edit(&mut self, text: &str) {
+        let editing = self.app.editing.take().expect("nothing to edit");
+        self.app.on_edited(editing, text.to_string());
+        self.settle().await;
+    }
+
+    /// Type `text` one character at a time.
+    pub async fn type_str(&mut self, text: &str) {
+        let keys: Vec<KeyCode> = text.chars().map(KeyCode::Char).collect();
+        self.press(&keys).await;
+    }
+
+    async fn settle(&mut self) {
+        loop {
+            let requests = self.app.take_requests();
+            if requests.is_empty() {
+                break;
+            }
+            for request in requests {
+                let response = request.execute().await;
+                self.app.on_response(response);
+            }
+        }
+    }
+
+    /// The screen as text, one line per row with trailing spaces trimmed.
+    pub fn render(&mut self) -> String {
+        let app = &mut self.app;
+        self.terminal.draw(|f| cloud_ui::ui::ui(f, app).unwrap()).unwrap();
+        let buffer = self.terminal.backend().buffer();
+        let mut lines = vec![];
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect();
+            lines.push(line.trim_end().to_string());
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Compare the screen against `tests/snapshots/<name>.txt`.
+    pub fn assert_snapshot(&mut self, name: &str) {
+        let actual = self.render();
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{}.txt", name));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+        assert!(
+            actual == expected,
+            "snapshot {} differs\n--- expected\n{}--- actual\n{}",
+            name, expected, actual,
+        );
+    }
+}
@@ -0,0 +1,625 @@
+mod harness;
+
+use std::time::Duration;
+use cloud_ui::app::AppState;
+use cloud_ui::service::metrics::Period;
+use cloud_ui::ui::resource::{Change, ResourceDetailState};
+use crossterm::event::KeyCode;
+use harness::Harness;
+
+/// From the service list down to the listing of fruits.
+async fn open_fruits(harness: &mut Harness) {
+    harness.press(&[KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+}
+
+#[tokio::test]
+async fn services() {
+    let mut harness = Harness::new();
+    harness.assert_snapshot("services");
+
+    harness.press(&[KeyCode::Down]).await;
+    harness.assert_snapshot("services_selected");
+}
+
+#[tokio::test]
+async fn resource_types() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.assert_snapshot("resource_types");
+}
+
+#[tokio::test]
+async fn listing() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.assert_snapshot("listing");
+
+    harness.press(&[KeyCode::Down, KeyCode::Down]).await;
+    harness.assert_snapshot("listing_selected");
+}
+
+#[tokio::test]
+async fn escape_walks_back() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.press(&[KeyCode::Esc, KeyCode::Esc]).await;
+    harness.assert_snapshot("services_selected");
+}
+
+#[tokio::test]
+async fn quit() {
+    let mut harness = Harness::new();
+    assert!(!harness.press(&[KeyCode::Char('q')]).await);
+}
+
+#[tokio::test]
+async fn filter() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.press(&[KeyCode::Char('/')]).await;
+    harness.type_str("ap").await;
+    harness.assert_snapshot("filter_typing");
+
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("filter_applied");
+
+    // the first escape clears the filter rather than leaving the listing
+    harness.press(&[KeyCode::Esc]).await;
+    harness.assert_snapshot("listing");
+}
+
+#[tokio::test]
+async fn detail_filter_survives_reload() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.press(&[KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Char('/')]).await;
+    harness.type_str("black").await;
+    harness.press(&[KeyCode::Enter]).await;
+
+    // an action reloading the detail view keeps the references' filter and selection
+    harness.type_str("w").await;
+    let references = &get_detail(&harness).references;
+    assert_eq!(references.filter.input, "black");
+    assert_eq!(references.selected().map(|x| x.id.as_str()), Some("blackberry"));
+}
+
+#[tokio::test]
+async fn filter_by_column() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.press(&[KeyCode::Char('/')]).await;
+    harness.type_str("colour:gr").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("filter_by_column");
+}
+
+#[tokio::test]
+async fn filter_is_kept_per_view() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Char('/')]).await;
+    harness.type_str("gro").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Esc]).await;
+    harness.assert_snapshot("filter_kept");
+}
+
+#[tokio::test]
+async fn detail() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.assert_snapshot("detail");
+
+    // follow the reference to another fruit, then come back
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.assert_snapshot("detail_followed");
+    harness.press(&[KeyCode::Esc]).await;
+    harness.assert_snapshot("detail_returned");
+}
+
+#[tokio::test]
+async fn empty_service() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Down, KeyCode::Enter]).await;
+    harness.assert_snapshot("empty_service");
+
+    // nothing to select, and nothing to go wrong
+    harness.press(&[KeyCode::Down, KeyCode::Up, KeyCode::PageDown, KeyCode::PageUp, KeyCode::End, KeyCode::Home, KeyCode::Enter]).await;
+    harness.assert_snapshot("empty_service");
+}
+
+#[tokio::test]
+async fn unknown_command() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Char(':')]).await;
+    harness.type_str("nope").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("unknown_command");
+}
+
+#[tokio::test]
+async fn errors() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Down, KeyCode::Enter]).await;
+    harness.assert_snapshot("error_toast");
+
+    harness.press(&[KeyCode::Char('!')]).await;
+    harness.assert_snapshot("error_log");
+
+    // the log takes keys while open, so q closes it rather than quitting
+    assert!(harness.press(&[KeyCode::Char('q')]).await);
+    assert!(harness.app.overlay.is_none());
+}
+
+/// The changes the last refresh made to the listing, by ID.
+fn get_changes(harness: &Harness) -> Vec<(String, Change)> {
+    let mut changes: Vec<_> = match &harness.app.state {
+        AppState::Listing(state) => state.changes.iter().map(|(id, (change, _))| (id.clone(), *change)).collect(),
+        _ => panic!("not a listing"),
+    };
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    changes
+}
+
+#[tokio::test(start_paused = true)]
+async fn refresh() {
+    let mut harness = Harness::new();
+    harness.app.refresh.interval = 10;
+    // open the market, selecting grape
+    harness.press(&[KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Down, KeyCode::Down, KeyCode::Enter]).await;
+    harness.press(&[KeyCode::Down, KeyCode::Down, KeyCode::Down]).await;
+    harness.assert_snapshot("refresh_before");
+
+    // not yet due
+    tokio::time::advance(Duration::from_secs(5)).await;
+    harness.tick().await;
+    assert!(get_changes(&harness).is_empty());
+
+    // banana stays in view until its highlight lapses, and grape stays selected as it moves
+    tokio::time::advance(Duration::from_secs(5)).await;
+    harness.tick().await;
+    harness.assert_snapshot("refresh_after");
+    assert_eq!(get_changes(&harness), vec![
+        ("banana".to_string(), Change::Removed),
+        ("grape".to_string(), Change::Changed),
+        ("kiwi".to_string(), Change::Added),
+    ]);
+
+    tokio::time::advance(Duration::from_secs(5)).await;
+    harness.press(&[KeyCode::Char('p')]).await;
+    harness.tick().await;
+    harness.assert_snapshot("refresh_paused");
+    assert!(get_changes(&harness).is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn progress() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("5j").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Harvest"]);
+    harness.assert_snapshot("progress");
+
+    // fetched again every second until complete, then only as often as any other listing
+    let mut counts = vec![];
+    for _ in 0..3 {
+        tokio::time::advance(Duration::from_secs(1)).await;
+        harness.tick().await;
+        match &harness.app.state {
+            AppState::Listing(state) => counts.push(state.items.len()),
+            _ => panic!("not a listing"),
+        }
+    }
+    assert_eq!(counts, vec![2, 3, 3]);
+    harness.assert_snapshot("progress_complete");
+}
+
+/// Name of the selected fruit in the listing.
+fn get_selected(harness: &Harness) -> Option<String> {
+    match &harness.app.state {
+        AppState::Listing(state) => state.items.selected().map(|x| x.name.clone()),
+        _ => panic!("not a listing"),
+    }
+}
+
+#[tokio::test]
+async fn vim_motions() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+
+    harness.type_str("G").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("papaya"));
+    harness.type_str("gg").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("apple"));
+    harness.type_str("3j").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("grape"));
+    harness.type_str("k").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("blackberry"));
+    harness.type_str("2G").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("banana"));
+
+    // a half typed count shows in the header until the motion completes it
+    harness.type_str("4").await;
+    harness.assert_snapshot("pending_count");
+    harness.type_str("gg").await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("grape"));
+
+    harness.press_ctrl('u').await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("apple"));
+    harness.press_ctrl('d').await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("papaya"));
+}
+
+#[tokio::test]
+async fn help() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+    harness.type_str("?").await;
+    harness.assert_snapshot("help");
+
+    harness.type_str("5j").await;
+    harness.assert_snapshot("help_scrolled");
+
+    harness.press(&[KeyCode::Esc]).await;
+    assert!(harness.app.overlay.is_none());
+}
+
+#[tokio::test]
+async fn mouse() {
+    let mut harness = Harness::new();
+    open_fruits(&mut harness).await;
+
+    // rows start below the border and the header row
+    harness.click(10, 8).await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("blackberry"));
+    harness.wheel(10, 8, true).await;
+    harness.wheel(10, 8, true).await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("papaya"));
+    harness.wheel(10, 8, false).await;
+    assert_eq!(get_selected(&harness).as_deref(), Some("grape"));
+
+    // a double click opens the row
+    harness.click(10, 6).await;
+    harness.click(10, 6).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Fruits", "apple"]);
+
+    // the wheel scrolls the properties
+    harness.wheel(10, 6, true).await;
+    assert_eq!(get_detail(&harness).scroll, 3);
+
+    // clicking a breadcrumb goes back to it, and the current one does nothing
+    harness.click(14, 1).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer"]);
+    harness.click(14, 1).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer"]);
+    harness.click(3, 1).await;
+    harness.assert_snapshot("services_selected");
+}
+
+#[tokio::test]
+async fn nested_listing() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(get_selected(&harness), None);
+
+    // a shelf opens the listing of its jars, titled after it
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top"]);
+    harness.assert_snapshot("nested_listing");
+
+    // whereas describing it opens its detail view
+    harness.press(&[KeyCode::Esc, KeyCode::Char('i')]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top top/"]);
+}
+
+#[tokio::test]
+async fn pager() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down]).await;
+    harness.type_str("o").await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top", "top/jam"]);
+
+    harness.type_str("5j").await;
+    harness.assert_snapshot("pager");
+
+    harness.type_str("/line 1").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("pager_filtered");
+
+    harness.press(&[KeyCode::Esc, KeyCode::Esc]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top"]);
+}
+
+#[tokio::test]
+async fn metrics() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top", "jam top/jam"]);
+    harness.assert_snapshot("metrics");
+
+    // the period goes round from the longest to the shortest
+    harness.type_str("M").await;
+    let state = get_detail(&harness);
+    assert_eq!((state.period, state.series[0].values.len()), (Period::SixHours, 72));
+    harness.type_str("MM").await;
+    assert_eq!(get_detail(&harness).period, Period::Hour);
+    assert!(harness.app.get_help().iter().any(|(key, _)| key == "M"));
+}
+
+/// Messages of the events in the tail being followed, and that of the selected one.
+fn get_tail(harness: &Harness) -> (Vec<String>, Option<String>) {
+    match &harness.app.state {
+        AppState::Tail(state) => (
+            state.events.items.iter().map(|x| x.message.clone()).collect(),
+            state.events.selected().map(|x| x.message.clone()),
+        ),
+        _ => panic!("not a tail"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn tail() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down]).await;
+
+    // in UTC, so that the times read the same anywhere
+    harness.type_str("Tu").await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top", "top/jam log"]);
+    harness.assert_snapshot("tail");
+
+    // the next read carries on from the latest event, without repeating those at its time, and
+    // the latest stays selected
+    tokio::time::advance(Duration::from_secs(2)).await;
+    harness.tick().await;
+    let (messages, selected) = get_tail(&harness);
+    assert_eq!(messages.len(), 4);
+    assert_eq!(selected.as_deref(), Some("honey lid loosened"));
+
+    harness.type_str("J").await;
+    harness.assert_snapshot("tail_pretty");
+
+    // a new pattern reads again from the start
+    harness.type_str("Flid").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(get_tail(&harness).0, vec!["jam lid tightened", "honey lid loosened"]);
+
+    let path = std::env::temp_dir().join(format!("tail-{}.log", std::process::id()));
+    harness.type_str("w").await;
+    for _ in 0.."top-jam log.log".len() {
+        harness.press(&[KeyCode::Backspace]).await;
+    }
+    harness.type_str(&path.display().to_string()).await;
+    harness.press(&[KeyCode::Enter]).await;
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(saved, "2021-11-05 09:30:01.000 jam lid tightened\n2021-11-05 09:30:05.000 honey lid loosened\n");
+    assert_eq!(harness.app.status, Some(format!("Saved 2 events to {}", path.display())));
+}
+
+#[tokio::test]
+async fn transfers() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down]).await;
+
+    // the action asks where to, offering a default
+    harness.type_str("f").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.type_str("f").await;
+    for _ in 0.."kitchen".len() {
+        harness.press(&[KeyCode::Backspace]).await;
+    }
+    harness.type_str("cellar").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.transfers.transfers.len(), 2);
+
+    harness.tick().await;
+    assert_eq!(harness.app.transfers.get_pending(), 0);
+    harness.type_str("t").await;
+    harness.assert_snapshot("transfers");
+
+    // failures can be tried again, and completed transfers cleared away
+    harness.press(&[KeyCode::Down]).await;
+    harness.type_str("r").await;
+    assert_eq!(harness.app.transfers.get_pending(), 1);
+    harness.type_str("x").await;
+    assert_eq!(harness.app.transfers.transfers.len(), 1);
+    harness.press(&[KeyCode::Esc]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Grocer", "Pantry", "top"]);
+}
+
+#[tokio::test]
+async fn edit_and_confirm() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(get_headers(&harness), vec!["Shelf"]);
+
+    // the columns are as the controller has them for what it listed
+    harness.press(&[KeyCode::Down, KeyCode::Enter, KeyCode::Down]).await;
+    assert_eq!(get_headers(&harness), vec!["Jar"]);
+
+    harness.type_str("e").await;
+    assert_eq!(harness.app.editing.as_ref().map(|x| x.text.as_str()), Some("top/jam"));
+    harness.edit("top/jam").await;
+    assert_eq!(harness.app.status.as_deref(), Some("No changes made"));
+    harness.type_str("e").await;
+    harness.edit("top/marmalade").await;
+    assert_eq!(harness.app.status.as_deref(), Some("Relabelled top/jam as top/marmalade"));
+
+    // notes are composed from a template, so are passed on even if saved as they are
+    harness.type_str("n").await;
+    harness.edit("Best before").await;
+    assert_eq!(harness.app.status.as_deref(), Some("Noted on top/jam: Best before"));
+
+    harness.type_str("x").await;
+    assert_eq!(harness.app.prompt.as_ref().map(|x| x.get_label()), Some("Throw out top/jam? (y/n) "));
+    harness.type_str("n").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.status.as_deref(), Some("Kept top/jam"));
+    harness.type_str("xy").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.status.as_deref(), Some("Threw out top/jam"));
+}
+
+#[tokio::test]
+async fn detail_actions() {
+    let mut harness = Harness::new();
+    harness.press(&[KeyCode::Down, KeyCode::Enter]).await;
+    harness.type_str("4j").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down]).await;
+
+    // the detail view offers the listing's actions, on the resource it describes
+    harness.type_str("i").await;
+    assert!(harness.app.get_help().contains(&("x".to_string(), "Throw out".to_string())));
+    harness.type_str("xy").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.status.as_deref(), Some("Threw out top/jam"));
+}
+
+fn get_headers(harness: &Harness) -> Vec<String> {
+    match &harness.app.state {
+        AppState::Listing(state) => state.headers.clone(),
+        _ => panic!("not a listing"),
+    }
+}
+
+fn get_detail(harness: &Harness) -> &ResourceDetailState {
+    match &harness.app.state {
+        AppState::Detail(state) => state,
+        _ => panic!("not a detail view"),
+    }
+}
+
+#[tokio::test]
+async fn fixtures() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":S3 Buckets").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("fixtures_buckets");
+
+    // buckets open like folders, in turn holding folders
+    harness.press(&[KeyCode::Down, KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "S3", "Buckets", "data-lake-raw", "orders/"]);
+    harness.assert_snapshot("fixtures_objects");
+}
+
+#[tokio::test]
+async fn fixtures_tables() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":DynamoDB Tables").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down]).await;
+    harness.assert_snapshot("fixtures_tables");
+
+    // tables open onto their items, and describe with their keys and indexes
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "DynamoDB", "Tables", "orders"]);
+    harness.press(&[KeyCode::Esc, KeyCode::Char('i')]).await;
+    let props = &get_detail(&harness).description.as_ref().expect("not described").props;
+    assert_eq!(props["Sort key"], "order_id (S)");
+    assert_eq!(props["Stream"], "NEW_AND_OLD_IMAGES");
+}
+
+#[tokio::test]
+async fn fixtures_queues() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":SQS Queues").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down]).await;
+    harness.assert_snapshot("fixtures_queues");
+
+    // the dead-letter queue is linked from the queue it serves
+    harness.press(&[KeyCode::Char('i')]).await;
+    let references = &get_detail(&harness).description.as_ref().expect("not described").references;
+    assert_eq!(references[0].label, "Dead-letter queue");
+    assert_eq!(references[0].id, "https://sqs.eu-west-1.amazonaws.com/123456789012/orders-dlq");
+}
+
+#[tokio::test]
+async fn fixtures_topics() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":SNS Topics").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "SNS", "Topics", "orders"]);
+    harness.assert_snapshot("fixtures_topics");
+
+    // subscriptions to queues link to them
+    harness.press(&[KeyCode::Down, KeyCode::Char('i')]).await;
+    let references = &get_detail(&harness).description.as_ref().expect("not described").references;
+    assert_eq!(references[1].label, "Endpoint");
+    assert_eq!(references[1].id, "https://sqs.eu-west-1.amazonaws.com/123456789012/orders");
+}
+
+#[tokio::test]
+async fn fixtures_functions() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":Lambda Functions").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down]).await;
+    harness.assert_snapshot("fixtures_functions");
+
+    // environment variables are masked, and event sources link to their streams and queues
+    harness.press(&[KeyCode::Char('i')]).await;
+    let description = get_detail(&harness).description.as_ref().expect("not described");
+    assert_eq!(description.props["Env TABLE_NAME"], "********");
+    assert_eq!(description.props["Alias live"], "7, 10% to 8");
+    let targets: Vec<(&str, &str)> = description.references.iter().map(|x| (x.service.as_str(), x.id.as_str())).collect();
+    assert_eq!(targets, vec![("Kinesis", "orders"), ("SQS", "https://sqs.eu-west-1.amazonaws.com/123456789012/orders-dlq")]);
+}
+
+#[tokio::test]
+async fn fixtures_log_groups() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":Logs Groups").await;
+    harness.press(&[KeyCode::Enter]).await;
+    harness.assert_snapshot("fixtures_log_groups");
+
+    // a group opens its streams, named without the group
+    harness.press(&[KeyCode::Down, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Logs", "Groups", "Streams"]);
+    let names: Vec<String> = match &harness.app.state {
+        AppState::Listing(state) => state.items.items.iter().map(|x| x.name.clone()).collect(),
+        _ => panic!("not a listing"),
+    };
+    assert_eq!(names, vec!["web-4f2a9c71d0b84e3a", "web-0c8e51b2f7a64d19", "web-91d3e6a0b5c24f87"]);
+}
+
+#[tokio::test]
+async fn insights_log_groups() {
+    let mut harness = Harness::with_aws();
+    harness.type_str(":Logs Insights").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "Logs", "Insights"]);
+
+    // the key isn't taken by the keymap, as the first of a sequence
+    harness.press(&[KeyCode::Char('l')]).await;
+    assert_eq!(harness.app.prompt.as_ref().map(|x| x.get_label()), Some("Log groups, separated by commas: "));
+    harness.type_str("/app/web, /app/worker").await;
+    harness.press(&[KeyCode::Enter]).await;
+    assert_eq!(harness.app.status.as_deref(), Some("Querying /app/web, /app/worker over 1h"));
+}
+
+#[tokio::test]
+async fn fixtures_roles() {
+    let mut harness = Harness::with_fixtures();
+    harness.type_str(":IAM Roles").await;
+    harness.press(&[KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "IAM", "Roles", "orders-ingest"]);
+    harness.assert_snapshot("fixtures_roles");
+
+    // policies are laid out across lines, and the role leads to who can assume it
+    let props = &get_detail(&harness).description.as_ref().expect("not described").props;
+    assert_eq!(props["Last used"], "2021-11-05T09:30:00Z in eu-west-1");
+    assert!(props["Trust policy"].contains("\n      \"Principal\": {\n        \"Service\": \"lambda.amazonaws.com\""));
+    harness.press(&[KeyCode::Esc, KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter]).await;
+    assert_eq!(harness.app.get_breadcrumbs(), vec!["Services", "IAM", "Roles", "deploy", "Who can assume"]);
+    harness.assert_snapshot("fixtures_principals");
+}